@@ -48,11 +48,18 @@ fn labs_to_rgb_bytes_simd(c: &mut Criterion) {
     });
 }
 
+fn labs_to_rgbs_fast(c: &mut Criterion) {
+    c.bench_function("[Lab] -> [RGB] (fast, medium)", move |b| {
+        b.iter(|| lab::fast_labs_to_rgbs(&LABS, lab::Precision::Medium))
+    });
+}
+
 criterion_group!(
     benches,
     labs_to_rgbs,
     labs_to_rgb_bytes,
     labs_to_rgbs_simd,
-    labs_to_rgb_bytes_simd
+    labs_to_rgb_bytes_simd,
+    labs_to_rgbs_fast
 );
 criterion_main!(benches);
@@ -47,11 +47,30 @@ fn rgb_bytes_to_labs_simd(c: &mut Criterion) {
     });
 }
 
+fn rgbs_to_labs_lut(c: &mut Criterion) {
+    let exact = lab::RgbToLabLut::new(lab::LutSize::Exact);
+    c.bench_function("[RGB] -> [Lab] (exact lut)", move |b| {
+        b.iter(|| exact.rgbs_to_labs(&RGBS))
+    });
+    let medium = lab::RgbToLabLut::new(lab::LutSize::Medium);
+    c.bench_function("[RGB] -> [Lab] (medium lut)", move |b| {
+        b.iter(|| medium.rgbs_to_labs(&RGBS))
+    });
+}
+
+fn rgbs_to_labs_fast(c: &mut Criterion) {
+    c.bench_function("[RGB] -> [Lab] (fast, medium)", move |b| {
+        b.iter(|| lab::fast_rgbs_to_labs(&RGBS, lab::Precision::Medium))
+    });
+}
+
 criterion_group!(
     benches,
     rgbs_to_labs,
     rgb_bytes_to_labs,
     rgbs_to_labs_simd,
-    rgb_bytes_to_labs_simd
+    rgb_bytes_to_labs_simd,
+    rgbs_to_labs_lut,
+    rgbs_to_labs_fast
 );
 criterion_main!(benches);
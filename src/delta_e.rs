@@ -0,0 +1,218 @@
+//! Color difference formulas.
+
+use core::f64::consts::PI;
+use math::{atan2, cos, exp, hypot, sin, sqrt, sqrtf};
+use Lab;
+
+/// A formula for the perceptual difference between two colors, ΔE
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum DeltaE {
+    /// The original CIE 1976 formula, the Euclidean distance in Lab space:
+    /// the square root of [`Lab::squared_distance`](struct.Lab.html#method.squared_distance)
+    #[default]
+    Cie76,
+    /// The CIEDE2000 formula, which corrects CIE76 for how much less
+    /// sensitive people are to differences in chroma and in saturated blues,
+    /// among others. See [`Lab::delta_e_2000`](struct.Lab.html#method.delta_e_2000).
+    Cie2000,
+}
+
+impl DeltaE {
+    /// Returns the difference between two colors by this formula
+    ///
+    /// # Example
+    /// ```
+    /// # use lab::{DeltaE, Lab};
+    /// let a = Lab { l: 50.0, a: 2.5, b: 0.0 };
+    /// let b = Lab { l: 50.0, a: 0.0, b: -2.5 };
+    /// assert_eq!(DeltaE::Cie76.distance(&a, &b), a.squared_distance(&b).sqrt());
+    /// assert_eq!(DeltaE::Cie2000.distance(&a, &b), a.delta_e_2000(&b));
+    /// ```
+    pub fn distance(self, a: &Lab, b: &Lab) -> f32 {
+        match self {
+            DeltaE::Cie76 => sqrtf(a.squared_distance(b)),
+            DeltaE::Cie2000 => a.delta_e_2000(b),
+        }
+    }
+}
+
+impl Lab {
+    /// Measures the perceptual distance between the colors of one `Lab` and
+    /// an `other` with the CIEDE2000 formula
+    ///
+    /// This follows Sharma, Wu and Dalal's
+    /// [implementation notes](https://hajim.rochester.edu/ece/sites/gsharma/ciede2000/),
+    /// with the weighting factors k<sub>L</sub>, k<sub>C</sub> and
+    /// k<sub>H</sub> all 1. It's computed in `f64`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lab::Lab;
+    /// let a = Lab { l: 50.0, a: 2.6772, b: -79.7751 };
+    /// let b = Lab { l: 50.0, a: 0.0, b: -82.7485 };
+    /// assert!((a.delta_e_2000(&b) - 2.0425).abs() < 0.0001);
+    /// ```
+    pub fn delta_e_2000(&self, other: &Lab) -> f32 {
+        ciede2000(self, other) as f32
+    }
+}
+
+fn ciede2000(lab1: &Lab, lab2: &Lab) -> f64 {
+    let (l1, a1, b1) = (lab1.l as f64, lab1.a as f64, lab1.b as f64);
+    let (l2, a2, b2) = (lab2.l as f64, lab2.a as f64, lab2.b as f64);
+    let pow7 = |x: f64| {
+        let x3 = x * x * x;
+        x3 * x3 * x
+    };
+    // 25⁷
+    const POW25_7: f64 = 6103515625.0;
+    let degrees = |radians: f64| radians * 180.0 / PI;
+    let radians = |degrees: f64| degrees * PI / 180.0;
+
+    let c_bar = (hypot(a1, b1) + hypot(a2, b2)) / 2.0;
+    let g = 0.5 * (1.0 - sqrt(pow7(c_bar) / (pow7(c_bar) + POW25_7)));
+    let a1 = (1.0 + g) * a1;
+    let a2 = (1.0 + g) * a2;
+    let c1 = hypot(a1, b1);
+    let c2 = hypot(a2, b2);
+    let hue = |b: f64, a: f64| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            let h = degrees(atan2(b, a));
+            if h < 0.0 {
+                h + 360.0
+            } else {
+                h
+            }
+        }
+    };
+    let h1 = hue(b1, a1);
+    let h2 = hue(b2, a2);
+
+    let delta_l = l2 - l1;
+    let delta_c = c2 - c1;
+    let delta_h = if c1 * c2 == 0.0 {
+        0.0
+    } else if h2 - h1 > 180.0 {
+        h2 - h1 - 360.0
+    } else if h2 - h1 < -180.0 {
+        h2 - h1 + 360.0
+    } else {
+        h2 - h1
+    };
+    let delta_h = 2.0 * sqrt(c1 * c2) * sin(radians(delta_h) / 2.0);
+
+    let l_bar = (l1 + l2) / 2.0;
+    let c_bar = (c1 + c2) / 2.0;
+    let h_bar = if c1 * c2 == 0.0 {
+        h1 + h2
+    } else if (h1 - h2).abs() <= 180.0 {
+        (h1 + h2) / 2.0
+    } else if h1 + h2 < 360.0 {
+        (h1 + h2 + 360.0) / 2.0
+    } else {
+        (h1 + h2 - 360.0) / 2.0
+    };
+
+    let t = 1.0 - 0.17 * cos(radians(h_bar - 30.0))
+        + 0.24 * cos(radians(2.0 * h_bar))
+        + 0.32 * cos(radians(3.0 * h_bar + 6.0))
+        - 0.20 * cos(radians(4.0 * h_bar - 63.0));
+    let delta_theta = 30.0 * exp(-((h_bar - 275.0) / 25.0) * ((h_bar - 275.0) / 25.0));
+    let r_c = 2.0 * sqrt(pow7(c_bar) / (pow7(c_bar) + POW25_7));
+    let l_50 = (l_bar - 50.0) * (l_bar - 50.0);
+    let s_l = 1.0 + 0.015 * l_50 / sqrt(20.0 + l_50);
+    let s_c = 1.0 + 0.045 * c_bar;
+    let s_h = 1.0 + 0.015 * c_bar * t;
+    let r_t = -sin(radians(2.0 * delta_theta)) * r_c;
+
+    let l = delta_l / s_l;
+    let c = delta_c / s_c;
+    let h = delta_h / s_h;
+    sqrt(l * l + c * c + h * h + r_t * c * h)
+}
+
+#[cfg(test)]
+mod test {
+    use super::DeltaE;
+    use Lab;
+
+    fn lab(l: f32, a: f32, b: f32) -> Lab {
+        Lab { l, a, b }
+    }
+
+    // Test data from Sharma, Wu and Dalal, "The CIEDE2000 Color-Difference
+    // Formula: Implementation Notes, Supplementary Test Data, and
+    // Mathematical Observations", 2005.
+    #[rustfmt::skip]
+    static SHARMA: [(Lab, Lab, f32); 34] = [
+        (Lab { l: 50.0, a: 2.6772, b: -79.7751 }, Lab { l: 50.0, a: 0.0, b: -82.7485 }, 2.0425),
+        (Lab { l: 50.0, a: 3.1571, b: -77.2803 }, Lab { l: 50.0, a: 0.0, b: -82.7485 }, 2.8615),
+        (Lab { l: 50.0, a: 2.8361, b: -74.0200 }, Lab { l: 50.0, a: 0.0, b: -82.7485 }, 3.4412),
+        (Lab { l: 50.0, a: -1.3802, b: -84.2814 }, Lab { l: 50.0, a: 0.0, b: -82.7485 }, 1.0000),
+        (Lab { l: 50.0, a: -1.1848, b: -84.8006 }, Lab { l: 50.0, a: 0.0, b: -82.7485 }, 1.0000),
+        (Lab { l: 50.0, a: -0.9009, b: -85.5211 }, Lab { l: 50.0, a: 0.0, b: -82.7485 }, 1.0000),
+        (Lab { l: 50.0, a: 0.0, b: 0.0 }, Lab { l: 50.0, a: -1.0, b: 2.0 }, 2.3669),
+        (Lab { l: 50.0, a: -1.0, b: 2.0 }, Lab { l: 50.0, a: 0.0, b: 0.0 }, 2.3669),
+        (Lab { l: 50.0, a: 2.4900, b: -0.0010 }, Lab { l: 50.0, a: -2.4900, b: 0.0009 }, 7.1792),
+        (Lab { l: 50.0, a: 2.4900, b: -0.0010 }, Lab { l: 50.0, a: -2.4900, b: 0.0010 }, 7.1792),
+        (Lab { l: 50.0, a: 2.4900, b: -0.0010 }, Lab { l: 50.0, a: -2.4900, b: 0.0011 }, 7.2195),
+        (Lab { l: 50.0, a: 2.4900, b: -0.0010 }, Lab { l: 50.0, a: -2.4900, b: 0.0012 }, 7.2195),
+        (Lab { l: 50.0, a: -0.0010, b: 2.4900 }, Lab { l: 50.0, a: 0.0009, b: -2.4900 }, 4.8045),
+        (Lab { l: 50.0, a: -0.0010, b: 2.4900 }, Lab { l: 50.0, a: 0.0010, b: -2.4900 }, 4.8045),
+        (Lab { l: 50.0, a: -0.0010, b: 2.4900 }, Lab { l: 50.0, a: 0.0011, b: -2.4900 }, 4.7461),
+        (Lab { l: 50.0, a: 2.5000, b: 0.0 }, Lab { l: 50.0, a: 0.0, b: -2.5000 }, 4.3065),
+        (Lab { l: 50.0, a: 2.5000, b: 0.0 }, Lab { l: 73.0, a: 25.0, b: -18.0 }, 27.1492),
+        (Lab { l: 50.0, a: 2.5000, b: 0.0 }, Lab { l: 61.0, a: -5.0, b: 29.0 }, 22.8977),
+        (Lab { l: 50.0, a: 2.5000, b: 0.0 }, Lab { l: 56.0, a: -27.0, b: -3.0 }, 31.9030),
+        (Lab { l: 50.0, a: 2.5000, b: 0.0 }, Lab { l: 58.0, a: 24.0, b: 15.0 }, 19.4535),
+        (Lab { l: 50.0, a: 2.5000, b: 0.0 }, Lab { l: 50.0, a: 3.1736, b: 0.5854 }, 1.0000),
+        (Lab { l: 50.0, a: 2.5000, b: 0.0 }, Lab { l: 50.0, a: 3.2972, b: 0.0 }, 1.0000),
+        (Lab { l: 50.0, a: 2.5000, b: 0.0 }, Lab { l: 50.0, a: 1.8634, b: 0.5757 }, 1.0000),
+        (Lab { l: 50.0, a: 2.5000, b: 0.0 }, Lab { l: 50.0, a: 3.2592, b: 0.3350 }, 1.0000),
+        (Lab { l: 60.2574, a: -34.0099, b: 36.2677 }, Lab { l: 60.4626, a: -34.1751, b: 39.4387 }, 1.2644),
+        (Lab { l: 63.0109, a: -31.0961, b: -5.8663 }, Lab { l: 62.8187, a: -29.7946, b: -4.0864 }, 1.2630),
+        (Lab { l: 61.2901, a: 3.7196, b: -5.3901 }, Lab { l: 61.4292, a: 2.2480, b: -4.9620 }, 1.8731),
+        (Lab { l: 35.0831, a: -44.1164, b: 3.7933 }, Lab { l: 35.0232, a: -40.0716, b: 1.5901 }, 1.8645),
+        (Lab { l: 22.7233, a: 20.0904, b: -46.6940 }, Lab { l: 23.0331, a: 14.9730, b: -42.5619 }, 2.0373),
+        (Lab { l: 36.4612, a: 47.8580, b: 18.3852 }, Lab { l: 36.2715, a: 50.5065, b: 21.2231 }, 1.4146),
+        (Lab { l: 90.8027, a: -2.0831, b: 1.4410 }, Lab { l: 91.1528, a: -1.6435, b: 0.0447 }, 1.4441),
+        (Lab { l: 90.9257, a: -0.5406, b: -0.9208 }, Lab { l: 88.6381, a: -0.8985, b: -0.7239 }, 1.5381),
+        (Lab { l: 6.7747, a: -0.2908, b: -2.4247 }, Lab { l: 5.8714, a: -0.0985, b: -2.2286 }, 0.6377),
+        (Lab { l: 2.0776, a: 0.0795, b: -1.1350 }, Lab { l: 0.9033, a: -0.0636, b: -0.5514 }, 0.9082),
+    ];
+
+    #[test]
+    fn test_ciede2000() {
+        for (i, &(a, b, expected)) in SHARMA.iter().enumerate() {
+            let delta_e = a.delta_e_2000(&b);
+            let close = |e: f32| (delta_e - e).abs() < 0.0001;
+            // The hues of pair 10 are exactly 180° apart, where the mean hue
+            // jumps, so rounding decides which side of the jump it lands on.
+            assert!(
+                close(expected) || (i + 1 == 10 && close(7.2195)),
+                "pair {}: {}",
+                i + 1,
+                delta_e
+            );
+            // The formula is symmetric.
+            assert_eq!(delta_e, b.delta_e_2000(&a), "pair {}", i + 1);
+        }
+    }
+
+    #[test]
+    fn test_identical_colors() {
+        let pink = lab(66.6377, 52.25, 14.86);
+        assert_eq!(pink.delta_e_2000(&pink), 0.0);
+        assert_eq!(DeltaE::Cie76.distance(&pink, &pink), 0.0);
+    }
+
+    #[test]
+    fn test_cie76() {
+        let a = lab(50.0, 0.0, 0.0);
+        let b = lab(53.0, 4.0, 0.0);
+        assert_eq!(DeltaE::Cie76.distance(&a, &b), 5.0);
+    }
+}
@@ -0,0 +1,262 @@
+//! Comparing images by the perceptual difference of their pixels.
+//!
+//! Comparing images byte for byte fails on differences too small to see,
+//! such as those from a different JPEG encoder or antialiasing. Measuring the
+//! ΔE of each pixel instead allows a tolerance in terms of what is visible.
+
+use delta_e::DeltaE;
+use gradient::GradientBuilder;
+use scielab::scielab;
+use stats::Distribution;
+use {rgb_bytes_to_labs, Lab};
+
+/// The per-pixel color difference of two images
+///
+/// # Example
+/// ```
+/// # use lab::{DeltaE, ImageDiff};
+/// let before = [255, 0, 0, 0, 0, 255, 255, 255, 255, 0, 0, 0];
+/// let after = [254, 0, 0, 0, 0, 255, 255, 255, 255, 60, 60, 60];
+/// let diff = ImageDiff::new(&before, &after, 2, 2, DeltaE::Cie2000);
+/// assert!(diff.distribution().max() > 10.0);
+/// assert_eq!(diff.count_over(1.0), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    width: usize,
+    height: usize,
+    deltas: Vec<f32>,
+    distribution: Distribution,
+}
+
+impl ImageDiff {
+    /// Compares two `width`×`height` images of RGB bytes, measuring the
+    /// difference of each pair of pixels with `delta_e`
+    ///
+    /// # Panics
+    ///
+    /// Panics if either image isn't `width * height * 3` long, or if it has
+    /// no pixels.
+    pub fn new(before: &[u8], after: &[u8], width: usize, height: usize, delta_e: DeltaE) -> Self {
+        assert_eq!(
+            before.len(),
+            width * height * 3,
+            "image length must be width * height * 3"
+        );
+        assert_eq!(
+            after.len(),
+            width * height * 3,
+            "image length must be width * height * 3"
+        );
+        ImageDiff::from_labs(
+            &rgb_bytes_to_labs(before),
+            &rgb_bytes_to_labs(after),
+            width,
+            height,
+            delta_e,
+        )
+    }
+
+    /// Compares two `width`×`height` images of RGB bytes as they are seen
+    /// with `pixels_per_degree` pixels in each degree of the field of view
+    ///
+    /// Both images are converted with [`scielab`](fn.scielab.html) before
+    /// measuring the difference of each pair of pixels with `delta_e`, so
+    /// that differences in detail too fine to see, such as between a
+    /// halftone and its average color, count for little.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either image isn't `width * height * 3` long, if it has no
+    /// pixels, or if `pixels_per_degree` isn't positive.
+    pub fn scielab(
+        before: &[u8],
+        after: &[u8],
+        width: usize,
+        height: usize,
+        pixels_per_degree: f32,
+        delta_e: DeltaE,
+    ) -> Self {
+        ImageDiff::from_labs(
+            &scielab(before, width, height, pixels_per_degree),
+            &scielab(after, width, height, pixels_per_degree),
+            width,
+            height,
+            delta_e,
+        )
+    }
+
+    /// Compares two `width`×`height` images of `Lab` pixels, measuring the
+    /// difference of each pair of pixels with `delta_e`
+    ///
+    /// # Panics
+    ///
+    /// Panics if either image isn't `width * height` long, or if it has no
+    /// pixels.
+    pub fn from_labs(
+        before: &[Lab],
+        after: &[Lab],
+        width: usize,
+        height: usize,
+        delta_e: DeltaE,
+    ) -> Self {
+        assert_eq!(
+            before.len(),
+            width * height,
+            "image length must be width * height"
+        );
+        assert_eq!(
+            after.len(),
+            width * height,
+            "image length must be width * height"
+        );
+        assert!(!before.is_empty(), "there are no pixels to compare");
+        let deltas: Vec<f32> = before
+            .iter()
+            .zip(after)
+            .map(|(before, after)| delta_e.distance(before, after))
+            .collect();
+        ImageDiff {
+            width,
+            height,
+            distribution: Distribution::new(deltas.clone()),
+            deltas,
+        }
+    }
+
+    /// Returns the width of the images
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the images
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the difference of each pixel, row by row
+    pub fn deltas(&self) -> &[f32] {
+        &self.deltas
+    }
+
+    /// Returns the distribution of the differences, for their mean, maximum
+    /// or 95th percentile
+    pub fn distribution(&self) -> &Distribution {
+        &self.distribution
+    }
+
+    /// Returns how many pixels differ by more than `threshold`
+    pub fn count_over(&self, threshold: f32) -> usize {
+        self.deltas
+            .iter()
+            .filter(|&&delta| delta > threshold)
+            .count()
+    }
+
+    /// Draws the differences as an image of RGB bytes
+    ///
+    /// Identical pixels are black, and larger differences go through red and
+    /// yellow to white at `scale` or more.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` isn't positive.
+    pub fn heatmap(&self, scale: f32) -> Vec<u8> {
+        let mut bytes = vec![0; self.deltas.len() * 3];
+        self.heatmap_into(scale, &mut bytes);
+        bytes
+    }
+
+    /// Draws the differences as an image of RGB bytes as
+    /// [`heatmap`](#method.heatmap) does, writing them to `bytes`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `scale` isn't positive, or if `bytes` isn't three times as
+    /// long as the images have pixels.
+    pub fn heatmap_into(&self, scale: f32, bytes: &mut [u8]) {
+        assert!(scale > 0.0, "a heatmap scale must be positive");
+        assert_eq!(
+            bytes.len(),
+            self.deltas.len() * 3,
+            "output length must match input"
+        );
+        let stops = [
+            Lab::from_rgb(&[0, 0, 0]),
+            Lab::from_rgb(&[200, 0, 0]),
+            Lab::from_rgb(&[255, 200, 0]),
+            Lab::from_rgb(&[255, 255, 255]),
+        ];
+        let mut colors = [[0; 3]; 256];
+        GradientBuilder::new(&stops)
+            .uniform(DeltaE::Cie76)
+            .build()
+            .to_rgbs_into(&mut colors);
+        for (rgb, &delta) in bytes.chunks_exact_mut(3).zip(&self.deltas) {
+            let i = (delta / scale * 255.0).clamp(0.0, 255.0).round() as usize;
+            rgb.copy_from_slice(&colors[i]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ImageDiff;
+    use {DeltaE, Lab};
+
+    #[test]
+    fn test_identical() {
+        let image = [10, 20, 30, 200, 100, 0, 255, 255, 255];
+        let diff = ImageDiff::new(&image, &image, 3, 1, DeltaE::Cie2000);
+        assert_eq!(diff.deltas(), [0.0, 0.0, 0.0]);
+        assert_eq!(diff.distribution().max(), 0.0);
+        assert_eq!(diff.count_over(0.0), 0);
+        assert_eq!(diff.heatmap(1.0), vec![0; 9]);
+    }
+
+    #[test]
+    fn test_statistics() {
+        let before: Vec<u8> = (0..20).flat_map(|_| vec![128, 128, 128]).collect();
+        let mut after = before.clone();
+        after[..3].copy_from_slice(&[140, 128, 128]);
+        after[3..6].copy_from_slice(&[255, 255, 255]);
+
+        for &delta_e in &[DeltaE::Cie76, DeltaE::Cie2000] {
+            let diff = ImageDiff::new(&before, &after, 5, 4, delta_e);
+            let grey = Lab::from_rgb(&[128, 128, 128]);
+            let small = delta_e.distance(&grey, &Lab::from_rgb(&[140, 128, 128]));
+            let large = delta_e.distance(&grey, &Lab::from_rgb(&[255, 255, 255]));
+            // The batch conversion may round differently from `from_rgb`.
+            assert!((diff.deltas()[0] - small).abs() < 1e-3);
+            assert!((diff.distribution().max() - large).abs() < 1e-3);
+            assert!((diff.distribution().mean() - (small + large) / 20.0).abs() < 1e-4);
+            // The 95th percentile falls between the two changed pixels.
+            let p95 = diff.distribution().percentile(95.0);
+            assert!(p95 > small && p95 < large, "{}", p95);
+            assert_eq!(diff.count_over(0.0), 2);
+            assert_eq!(diff.count_over(small + 1e-3), 1);
+        }
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let before = [0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let after = [0, 0, 0, 60, 60, 60, 255, 255, 255];
+        let diff = ImageDiff::new(&before, &after, 3, 1, DeltaE::Cie76);
+        let heatmap = diff.heatmap(diff.distribution().max());
+        assert_eq!(&heatmap[..3], &[0, 0, 0]);
+        assert_eq!(&heatmap[6..], &[255, 255, 255]);
+        let middle = &heatmap[3..6];
+        assert!(middle[0] > middle[2], "{:?}", middle);
+
+        let mut bytes = vec![0; 9];
+        diff.heatmap_into(1.0, &mut bytes);
+        assert_eq!(&bytes[3..], &[255, 255, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    #[should_panic(expected = "image length must be width * height * 3")]
+    fn test_length_mismatch() {
+        ImageDiff::new(&[0; 12], &[0; 9], 2, 2, DeltaE::Cie76);
+    }
+}
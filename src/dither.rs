@@ -0,0 +1,427 @@
+//! Dithering images to a palette.
+//!
+//! Mapping every pixel to its closest palette color gives flat bands where
+//! an image has smooth gradients. Dithering spreads the difference between
+//! each pixel and its palette color over neighboring pixels instead, so that
+//! areas of the image average out to their original colors.
+
+use delta_e::DeltaE;
+use palette::Palette;
+use {
+    lab_to_xyz, linear_rgb_to_xyz, rgb_to_lab, rgb_to_xyz_map, xyz_to_lab, xyz_to_linear_rgb, Lab,
+};
+
+/// A dithering method
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Dither {
+    /// Floyd–Steinberg error diffusion, which spreads each pixel's error over
+    /// its four unvisited neighbors
+    FloydSteinberg,
+    /// Atkinson error diffusion, which spreads only three quarters of each
+    /// pixel's error, over six pixels, for more contrast and less noise
+    Atkinson,
+    /// Sierra error diffusion, which spreads each pixel's error over ten
+    /// pixels in three rows, for smoother results than Floyd–Steinberg
+    Sierra,
+    /// Ordered dithering with a `size`×`size` Bayer matrix
+    ///
+    /// The L\* of each pixel is offset by its threshold in the matrix, scaled
+    /// to how far apart the palette's colors are, before it's mapped, in Lab
+    /// whatever the `DitherSpace`. Unlike error diffusion, every pixel is
+    /// mapped independently, so it gives a regular pattern that doesn't
+    /// change where the rest of the image does.
+    ///
+    /// `size` must be a power of two from 2 to 16.
+    Bayer { size: usize },
+}
+
+/// The color space to diffuse dithering error in
+///
+/// Palette colors are picked in Lab either way.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DitherSpace {
+    /// Diffuses error in Lab
+    Lab,
+    /// Diffuses error in linear RGB, where mixing colors is physically
+    /// accurate, so that areas average out to their original brightness
+    LinearRgb,
+}
+
+// Where error diffusion spreads a pixel's error to, as (dx, dy, weight), and
+// what the weights are divided by.
+type Kernel = (&'static [(isize, usize, f32)], f32);
+
+const FLOYD_STEINBERG: Kernel = (&[(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)], 16.0);
+
+const ATKINSON: Kernel = (
+    &[
+        (1, 0, 1.0),
+        (2, 0, 1.0),
+        (-1, 1, 1.0),
+        (0, 1, 1.0),
+        (1, 1, 1.0),
+        (0, 2, 1.0),
+    ],
+    8.0,
+);
+
+const SIERRA: Kernel = (
+    &[
+        (1, 0, 5.0),
+        (2, 0, 3.0),
+        (-2, 1, 2.0),
+        (-1, 1, 4.0),
+        (0, 1, 5.0),
+        (1, 1, 4.0),
+        (2, 1, 2.0),
+        (-1, 2, 2.0),
+        (0, 2, 3.0),
+        (1, 2, 2.0),
+    ],
+    32.0,
+);
+
+/// Dithers a `width`×`height` image of RGB bytes, row by row, to `palette`,
+/// returning the index of the palette color for each pixel
+///
+/// Palette colors are picked by CIE76.
+///
+/// # Panics
+///
+/// Panics if `bytes` isn't `width * height * 3` long, or if a Bayer matrix
+/// size isn't a power of two from 2 to 16.
+///
+/// # Example
+/// ```
+/// # use lab::{dither, Dither, DitherSpace, Palette};
+/// let palette = Palette::from_rgbs(&[[0, 0, 0], [255, 255, 255]]);
+/// let grey = [119; 4 * 4 * 3];
+/// let indices = dither(&grey, 4, 4, &palette, Dither::Bayer { size: 4 }, DitherSpace::Lab);
+/// // Half of the pixels are black, and half white.
+/// assert_eq!(indices.iter().filter(|&&i| i == 1).count(), 8);
+/// ```
+pub fn dither(
+    bytes: &[u8],
+    width: usize,
+    height: usize,
+    palette: &Palette,
+    method: Dither,
+    space: DitherSpace,
+) -> Vec<usize> {
+    let mut indices = vec![0; width * height];
+    dither_into(bytes, width, height, palette, method, space, &mut indices);
+    indices
+}
+
+/// Dithers a `width`×`height` image of RGB bytes to `palette` as
+/// [`dither`](fn.dither.html) does, writing the palette indices to `indices`
+///
+/// # Panics
+///
+/// Panics if `bytes` isn't `width * height * 3` long, `indices` isn't
+/// `width * height` long, or if a Bayer matrix size isn't a power of two from
+/// 2 to 16.
+pub fn dither_into(
+    bytes: &[u8],
+    width: usize,
+    height: usize,
+    palette: &Palette,
+    method: Dither,
+    space: DitherSpace,
+    indices: &mut [usize],
+) {
+    assert_eq!(
+        bytes.len(),
+        width * height * 3,
+        "image length must be width * height * 3"
+    );
+    assert_eq!(
+        indices.len(),
+        width * height,
+        "output length must match input"
+    );
+    let space = Space::new(palette, space);
+    match method {
+        Dither::FloydSteinberg => diffuse(bytes, width, &space, FLOYD_STEINBERG, indices),
+        Dither::Atkinson => diffuse(bytes, width, &space, ATKINSON, indices),
+        Dither::Sierra => diffuse(bytes, width, &space, SIERRA, indices),
+        Dither::Bayer { size } => ordered(bytes, width, palette, size, indices),
+    }
+}
+
+// A palette, with its colors in the space that dithering works in.
+struct Space<'a> {
+    palette: &'a Palette,
+    space: DitherSpace,
+    colors: Vec<[f32; 3]>,
+}
+
+impl<'a> Space<'a> {
+    fn new(palette: &'a Palette, space: DitherSpace) -> Self {
+        let colors = palette.colors().iter().map(|lab| match space {
+            DitherSpace::Lab => [lab.l, lab.a, lab.b],
+            DitherSpace::LinearRgb => xyz_to_linear_rgb(lab_to_xyz(lab)),
+        });
+        Space {
+            palette,
+            space,
+            colors: colors.collect(),
+        }
+    }
+
+    fn convert(&self, rgb: &[u8]) -> [f32; 3] {
+        match self.space {
+            DitherSpace::Lab => {
+                let lab = rgb_to_lab(rgb[0], rgb[1], rgb[2]);
+                [lab.l, lab.a, lab.b]
+            }
+            DitherSpace::LinearRgb => [
+                rgb_to_xyz_map(rgb[0] as f32),
+                rgb_to_xyz_map(rgb[1] as f32),
+                rgb_to_xyz_map(rgb[2] as f32),
+            ],
+        }
+    }
+
+    fn nearest(&self, color: [f32; 3]) -> usize {
+        let lab = match self.space {
+            DitherSpace::Lab => Lab {
+                l: color[0],
+                a: color[1],
+                b: color[2],
+            },
+            DitherSpace::LinearRgb => xyz_to_lab(linear_rgb_to_xyz(color[0], color[1], color[2])),
+        };
+        self.palette.nearest(&lab, DeltaE::Cie76)
+    }
+}
+
+fn diffuse(bytes: &[u8], width: usize, space: &Space, kernel: Kernel, indices: &mut [usize]) {
+    let (offsets, divisor) = kernel;
+    let mut colors: Vec<[f32; 3]> = bytes
+        .chunks_exact(3)
+        .map(|rgb| space.convert(rgb))
+        .collect();
+    for i in 0..colors.len() {
+        let color = colors[i];
+        let index = space.nearest(color);
+        indices[i] = index;
+        let target = space.colors[index];
+        let error = [
+            color[0] - target[0],
+            color[1] - target[1],
+            color[2] - target[2],
+        ];
+        let (x, y) = (i % width, i / width);
+        for &(dx, dy, weight) in offsets {
+            let nx = x as isize + dx;
+            if nx < 0 || nx >= width as isize {
+                continue;
+            }
+            let j = (y + dy) * width + nx as usize;
+            if let Some(neighbor) = colors.get_mut(j) {
+                let weight = weight / divisor;
+                neighbor[0] += error[0] * weight;
+                neighbor[1] += error[1] * weight;
+                neighbor[2] += error[2] * weight;
+            }
+        }
+    }
+}
+
+// The `size`×`size` Bayer matrix, with thresholds from 0 to size² - 1.
+fn bayer_matrix(size: usize) -> Vec<usize> {
+    assert!(
+        size.is_power_of_two() && (2..=16).contains(&size),
+        "a Bayer matrix size must be a power of two from 2 to 16"
+    );
+    let mut matrix = vec![0];
+    let mut n = 1;
+    while n < size {
+        // Each threshold t of the smaller matrix becomes 4t, 4t + 2, 4t + 3
+        // and 4t + 1 in the four quadrants of the larger one.
+        let mut next = vec![0; 4 * n * n];
+        for y in 0..n {
+            for x in 0..n {
+                let t = 4 * matrix[y * n + x];
+                next[y * 2 * n + x] = t;
+                next[y * 2 * n + x + n] = t + 2;
+                next[(y + n) * 2 * n + x] = t + 3;
+                next[(y + n) * 2 * n + x + n] = t + 1;
+            }
+        }
+        matrix = next;
+        n *= 2;
+    }
+    matrix
+}
+
+// How far apart the palette's colors are: the mean distance from each to
+// the closest other one.
+fn spacing(palette: &Palette) -> f32 {
+    if palette.len() < 2 {
+        return 0.0;
+    }
+    let total: f32 = palette
+        .colors()
+        .iter()
+        .map(|lab| palette.k_nearest(lab, 2, DeltaE::Cie76)[1].1)
+        .sum();
+    total / palette.len() as f32
+}
+
+fn ordered(bytes: &[u8], width: usize, palette: &Palette, size: usize, indices: &mut [usize]) {
+    let matrix = bayer_matrix(size);
+    let spacing = spacing(palette);
+    let levels = (size * size) as f32;
+    for (i, (rgb, index)) in bytes.chunks_exact(3).zip(indices.iter_mut()).enumerate() {
+        let (x, y) = (i % width, i / width);
+        // From -0.5 to 0.5, centered on 0.
+        let threshold = (matrix[(y % size) * size + x % size] as f32 + 0.5) / levels - 0.5;
+        let mut lab = rgb_to_lab(rgb[0], rgb[1], rgb[2]);
+        lab.l += threshold * spacing;
+        *index = palette.nearest(&lab, DeltaE::Cie76);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bayer_matrix, dither, Dither, DitherSpace};
+    use palette::Palette;
+    use Lab;
+
+    const METHODS: [Dither; 4] = [
+        Dither::FloydSteinberg,
+        Dither::Atkinson,
+        Dither::Sierra,
+        Dither::Bayer { size: 8 },
+    ];
+    const SPACES: [DitherSpace; 2] = [DitherSpace::Lab, DitherSpace::LinearRgb];
+
+    fn black_and_white() -> Palette {
+        Palette::from_rgbs(&[[0, 0, 0], [255, 255, 255]])
+    }
+
+    fn image(width: usize, height: usize, rgb: [u8; 3]) -> Vec<u8> {
+        (0..width * height)
+            .flat_map(|_| rgb.iter().cloned())
+            .collect()
+    }
+
+    fn white_fraction(indices: &[usize]) -> f32 {
+        indices.iter().filter(|&&i| i == 1).count() as f32 / indices.len() as f32
+    }
+
+    #[test]
+    fn test_bayer_matrix() {
+        assert_eq!(bayer_matrix(2), [0, 2, 3, 1]);
+        assert_eq!(
+            bayer_matrix(4),
+            [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5]
+        );
+        let mut matrix = bayer_matrix(16);
+        matrix.sort();
+        assert_eq!(matrix, (0..256).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_palette_colors_are_kept() {
+        let palette = Palette::from_rgbs(&[[0, 0, 0], [255, 255, 255], [200, 30, 60]]);
+        let bytes = image(5, 3, [200, 30, 60]);
+        for &method in METHODS.iter() {
+            for &space in SPACES.iter() {
+                let indices = dither(&bytes, 5, 3, &palette, method, space);
+                assert_eq!(indices, [2; 15], "{:?} {:?}", method, space);
+            }
+        }
+    }
+
+    #[test]
+    fn test_grey_averages_out() {
+        let palette = black_and_white();
+        let (width, height) = (32, 32);
+        // Halfway between black and white in lightness, and in linear RGB.
+        let lab_grey = Lab {
+            l: 50.0,
+            a: 0.0,
+            b: 0.0,
+        }
+        .to_rgb();
+        for &(space, grey) in &[
+            (DitherSpace::Lab, lab_grey),
+            (DitherSpace::LinearRgb, [188; 3]),
+        ] {
+            let bytes = image(width, height, grey);
+            for &method in METHODS.iter() {
+                if let (DitherSpace::LinearRgb, Dither::Bayer { .. }) = (space, method) {
+                    // Bayer dithering always offsets L*.
+                    continue;
+                }
+                let indices = dither(&bytes, width, height, &palette, method, space);
+                // Error diffused past the edges of the image is lost, and
+                // Atkinson drops a quarter of it besides.
+                let tolerance = if method == Dither::Atkinson {
+                    0.15
+                } else {
+                    0.04
+                };
+                let fraction = white_fraction(&indices);
+                assert!(
+                    (fraction - 0.5).abs() < tolerance,
+                    "{:?} {:?} {}",
+                    method,
+                    space,
+                    fraction
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_dithering_bands() {
+        // Without dithering, a dark grey maps to black everywhere.
+        let palette = black_and_white();
+        let bytes = image(16, 16, [60, 60, 60]);
+        assert!(palette
+            .map_rgb_bytes(&bytes, ::DeltaE::Cie76)
+            .iter()
+            .all(|&i| i == 0));
+        for &method in METHODS.iter() {
+            let indices = dither(&bytes, 16, 16, &palette, method, DitherSpace::Lab);
+            let fraction = white_fraction(&indices);
+            assert!(
+                fraction > 0.1 && fraction < 0.4,
+                "{:?} {}",
+                method,
+                fraction
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "image length must be width * height * 3")]
+    fn test_length_mismatch() {
+        dither(
+            &[0; 10],
+            2,
+            2,
+            &black_and_white(),
+            Dither::FloydSteinberg,
+            DitherSpace::Lab,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "a Bayer matrix size must be a power of two from 2 to 16")]
+    fn test_bad_bayer_size() {
+        let bytes = image(2, 2, [0, 0, 0]);
+        dither(
+            &bytes,
+            2,
+            2,
+            &black_and_white(),
+            Dither::Bayer { size: 3 },
+            DitherSpace::Lab,
+        );
+    }
+}
@@ -0,0 +1,346 @@
+//! Finding the main colors of an image.
+
+use delta_e::DeltaE;
+use quantize::{quantize, Quantizer};
+use {rgb_bytes_to_labs, Lab};
+
+const WHITE: Lab = Lab {
+    l: 100.0,
+    a: 0.0,
+    b: 0.0,
+};
+const BLACK: Lab = Lab {
+    l: 0.0,
+    a: 0.0,
+    b: 0.0,
+};
+
+/// One of the main colors of an image
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct DominantColor {
+    /// The mean color of the pixels it stands for, with every pixel
+    /// counting the same even when ranking by chroma
+    pub lab: Lab,
+    /// The percentage of the image's pixels, not counting ignored ones, that
+    /// it stands for, from 0 to 100
+    pub coverage: f32,
+}
+
+/// Finds the main colors of an image
+///
+/// The image's colors are clustered with k-means, and then clusters closer
+/// together than a ΔE threshold are merged, so that an image with few colors
+/// doesn't have them split into several similar ones.
+///
+/// # Example
+/// ```
+/// # use lab::{DominantColors, Lab};
+/// // A red image on a white background.
+/// let mut pixels = vec![255; 100 * 3];
+/// for pixel in pixels.chunks_exact_mut(3).take(30) {
+///     pixel.copy_from_slice(&[200, 20, 30]);
+/// }
+/// let colors = DominantColors::new(4)
+///     .ignore_near_white_and_black(5.0)
+///     .extract(&pixels);
+/// assert_eq!(colors.len(), 1);
+/// assert_eq!(colors[0].lab.to_rgb(), [200, 20, 30]);
+/// assert_eq!(colors[0].coverage, 100.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DominantColors {
+    colors: usize,
+    delta_e: DeltaE,
+    merge_within: f32,
+    chroma_weighted: bool,
+    ignore_within: Option<f32>,
+    seed: u64,
+}
+
+impl DominantColors {
+    /// Finds at most `colors` colors
+    ///
+    /// By default, clusters within a ΔE2000 of 5 of each other are merged,
+    /// every pixel counts the same, and none are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `colors` is 0.
+    pub fn new(colors: usize) -> Self {
+        assert!(colors > 0, "a palette needs at least one color");
+        DominantColors {
+            colors,
+            delta_e: DeltaE::Cie2000,
+            merge_within: 5.0,
+            chroma_weighted: false,
+            ignore_within: None,
+            seed: 0,
+        }
+    }
+
+    /// Measures color differences with `delta_e` rather than ΔE2000
+    pub fn delta_e(mut self, delta_e: DeltaE) -> Self {
+        self.delta_e = delta_e;
+        self
+    }
+
+    /// Merges clusters whose colors are less than `threshold` apart
+    ///
+    /// A threshold of 0 doesn't merge any.
+    pub fn merge_within(mut self, threshold: f32) -> Self {
+        self.merge_within = threshold;
+        self
+    }
+
+    /// Ranks colors by their pixels' chroma as well as by their coverage
+    ///
+    /// Each pixel counts for 1 plus its chroma, so that vivid colors rank
+    /// above larger areas of dull ones. Coverage is still the share of
+    /// pixels, and each color still the plain mean of its pixels.
+    pub fn weight_by_chroma(mut self, chroma_weighted: bool) -> Self {
+        self.chroma_weighted = chroma_weighted;
+        self
+    }
+
+    /// Ignores pixels less than `threshold` from white or black, such as
+    /// those of a plain background
+    pub fn ignore_near_white_and_black(mut self, threshold: f32) -> Self {
+        self.ignore_within = Some(threshold);
+        self
+    }
+
+    /// Seeds the k-means clustering with `seed`
+    ///
+    /// The same seed always gives the same colors for the same image.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Finds the main colors of an image of RGB bytes, most dominant first
+    ///
+    /// Trailing bytes that don't make up a whole pixel are ignored. Returns
+    /// no colors if every pixel is ignored.
+    pub fn extract(&self, bytes: &[u8]) -> Vec<DominantColor> {
+        self.extract_labs(&rgb_bytes_to_labs(bytes))
+    }
+
+    /// Finds the main colors of an image of `Lab` pixels, most dominant
+    /// first
+    pub fn extract_labs(&self, labs: &[Lab]) -> Vec<DominantColor> {
+        let labs: Vec<Lab> = match self.ignore_within {
+            Some(threshold) => labs
+                .iter()
+                .filter(|lab| {
+                    self.delta_e.distance(lab, &WHITE) >= threshold
+                        && self.delta_e.distance(lab, &BLACK) >= threshold
+                })
+                .cloned()
+                .collect(),
+            None => labs.to_vec(),
+        };
+        if labs.is_empty() {
+            return Vec::new();
+        }
+        let quantized = quantize(
+            &labs,
+            self.colors,
+            Quantizer::KMeans {
+                seed: self.seed,
+                max_iterations: 16,
+            },
+        );
+
+        let mut clusters = vec![Cluster::default(); quantized.palette.len()];
+        for (lab, &i) in labs.iter().zip(&quantized.indices) {
+            let weight = if self.chroma_weighted {
+                1.0 + lab.chroma() as f64
+            } else {
+                1.0
+            };
+            clusters[i].add(lab, weight);
+        }
+        clusters.retain(|cluster| cluster.pixels > 0);
+        self.merge(&mut clusters);
+
+        clusters.sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap());
+        clusters
+            .iter()
+            .map(|cluster| DominantColor {
+                lab: cluster.mean(),
+                coverage: (cluster.pixels as f64 * 100.0 / labs.len() as f64) as f32,
+            })
+            .collect()
+    }
+
+    // Merges the closest two clusters until none are within the threshold.
+    fn merge(&self, clusters: &mut Vec<Cluster>) {
+        loop {
+            let mut closest: Option<(f32, usize, usize)> = None;
+            for i in 0..clusters.len() {
+                for j in i + 1..clusters.len() {
+                    let distance = self
+                        .delta_e
+                        .distance(&clusters[i].mean(), &clusters[j].mean());
+                    if distance < self.merge_within && closest.is_none_or(|(d, _, _)| distance < d)
+                    {
+                        closest = Some((distance, i, j));
+                    }
+                }
+            }
+            match closest {
+                Some((_, i, j)) => {
+                    let merged = clusters.swap_remove(j);
+                    clusters[i].merge(&merged);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+// A cluster's pixels, as sums for its mean color, and its weight for
+// ranking.
+#[derive(Debug, Default, Copy, Clone)]
+struct Cluster {
+    sum: [f64; 3],
+    weight: f64,
+    pixels: usize,
+}
+
+impl Cluster {
+    fn add(&mut self, lab: &Lab, weight: f64) {
+        self.sum[0] += lab.l as f64;
+        self.sum[1] += lab.a as f64;
+        self.sum[2] += lab.b as f64;
+        self.weight += weight;
+        self.pixels += 1;
+    }
+
+    fn merge(&mut self, other: &Cluster) {
+        for (sum, other) in self.sum.iter_mut().zip(&other.sum) {
+            *sum += other;
+        }
+        self.weight += other.weight;
+        self.pixels += other.pixels;
+    }
+
+    fn mean(&self) -> Lab {
+        let pixels = self.pixels as f64;
+        Lab {
+            l: (self.sum[0] / pixels) as f32,
+            a: (self.sum[1] / pixels) as f32,
+            b: (self.sum[2] / pixels) as f32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DominantColors;
+    use Lab;
+
+    // An image with `n` pixels of each color in turn.
+    fn image(colors: &[([u8; 3], usize)]) -> Vec<u8> {
+        colors
+            .iter()
+            .flat_map(|&(rgb, n)| (0..n).flat_map(move |_| rgb.to_vec()))
+            .collect()
+    }
+
+    fn rgbs_and_coverages(colors: &[super::DominantColor]) -> Vec<([u8; 3], f32)> {
+        colors
+            .iter()
+            .map(|color| (color.lab.to_rgb(), color.coverage))
+            .collect()
+    }
+
+    #[test]
+    fn test_coverage() {
+        let bytes = image(&[
+            ([200, 20, 30], 60),
+            ([20, 40, 200], 30),
+            ([255, 255, 255], 10),
+        ]);
+        let colors = DominantColors::new(8).extract(&bytes);
+        assert_eq!(
+            rgbs_and_coverages(&colors),
+            [
+                ([200, 20, 30], 60.0),
+                ([20, 40, 200], 30.0),
+                ([255, 255, 255], 10.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ignore_near_white_and_black() {
+        let bytes = image(&[
+            ([200, 20, 30], 30),
+            ([250, 252, 250], 50),
+            ([3, 2, 0], 10),
+            ([20, 40, 200], 10),
+        ]);
+        let colors = DominantColors::new(8)
+            .ignore_near_white_and_black(5.0)
+            .extract(&bytes);
+        assert_eq!(
+            rgbs_and_coverages(&colors),
+            [([200, 20, 30], 75.0), ([20, 40, 200], 25.0)]
+        );
+
+        let white = image(&[([255, 255, 255], 10)]);
+        let colors = DominantColors::new(8)
+            .ignore_near_white_and_black(5.0)
+            .extract(&white);
+        assert!(colors.is_empty());
+    }
+
+    #[test]
+    fn test_merge_within() {
+        // Two reds 2 or 3 apart, and a blue.
+        let bytes = image(&[
+            ([200, 20, 30], 40),
+            ([204, 22, 30], 20),
+            ([20, 40, 200], 40),
+        ]);
+        let merged = DominantColors::new(8).extract(&bytes);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].coverage, 60.0);
+        let reds = Lab::from_rgb(&[200, 20, 30]).lerp(&Lab::from_rgb(&[204, 22, 30]), 1.0 / 3.0);
+        assert!(merged[0].lab.squared_distance(&reds) < 1e-4);
+
+        let separate = DominantColors::new(8).merge_within(0.0).extract(&bytes);
+        assert_eq!(separate.len(), 3);
+    }
+
+    #[test]
+    fn test_weight_by_chroma() {
+        let bytes = image(&[([128, 128, 128], 70), ([200, 20, 30], 30)]);
+        let colors = DominantColors::new(4).extract(&bytes);
+        assert_eq!(colors[0].lab.to_rgb(), [128, 128, 128]);
+        let colors = DominantColors::new(4)
+            .weight_by_chroma(true)
+            .extract(&bytes);
+        assert_eq!(
+            rgbs_and_coverages(&colors),
+            [([200, 20, 30], 30.0), ([128, 128, 128], 70.0)]
+        );
+
+        // Merged colors are still their plain mean, not pulled towards the
+        // more vivid one.
+        let reds = image(&[([200, 20, 30], 20), ([180, 60, 60], 20)]);
+        let colors = DominantColors::new(4)
+            .merge_within(20.0)
+            .weight_by_chroma(true)
+            .extract(&reds);
+        assert_eq!(colors.len(), 1);
+        let mean = Lab::from_rgb(&[200, 20, 30]).lerp(&Lab::from_rgb(&[180, 60, 60]), 0.5);
+        assert!(colors[0].lab.squared_distance(&mean) < 1e-4);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(DominantColors::new(4).extract(&[]).is_empty());
+    }
+}
@@ -0,0 +1,467 @@
+//! Approximate batch conversions, for when speed matters more than accuracy.
+//!
+//! These replace the `powf` calls of the exact conversions with `exp2(y *
+//! log2(x))`, where `log2` and `exp2` are evaluated with short polynomials
+//! whose degree is picked by [`Precision`](enum.Precision.html). The code is
+//! branchless and works on 8 colors at a time so that the compiler can
+//! vectorize it for any target, and it multiplies by reciprocals instead of
+//! dividing.
+
+#[cfg(target_arch = "x86_64")]
+use simd::{kernel, Kernel};
+use {clip, Lab, CBRT_EPSILON, EPSILON, E_0_255, KAPPA, S_0, WHITE_X, WHITE_Z};
+
+/// How closely the `fast_` conversions approximate the exact ones
+///
+/// The maximum errors listed are the largest Euclidean distance in Lab space
+/// between the result of `fast_rgbs_to_labs` and that of
+/// [`Lab::from_rgb`](struct.Lab.html#method.from_rgb) over all 16,777,216 `u8`
+/// RGB colors. Converting those colors' exact Lab values back with
+/// `fast_labs_to_rgbs` gives the same colors as
+/// [`Lab::to_rgb`](struct.Lab.html#method.to_rgb) at every precision, though
+/// other Lab values may come out one off in a channel.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Precision {
+    /// Degree 3 polynomials. Maximum error 0.3.
+    Low,
+    /// Degree 4 polynomials. Maximum error 0.04.
+    Medium,
+    /// Degree 5 polynomials. Maximum error 0.006.
+    High,
+}
+
+// Coefficients, lowest order first, of polynomials p and q such that
+// log2(1 + t) ≈ t * p(t) and exp2(t) ≈ 1 + t * q(t) for t in [0, 1). They're
+// fitted to minimize the maximum absolute error.
+trait Polynomials {
+    const LOG2: &'static [f32];
+    const EXP2: &'static [f32];
+}
+
+struct Low;
+struct Medium;
+struct High;
+
+impl Polynomials for Low {
+    const LOG2: &'static [f32] = &[1.42459044, -0.589195604, 0.165375797];
+    const EXP2: &'static [f32] = &[0.695556927, 0.226173404, 0.0781456714];
+}
+
+impl Polynomials for Medium {
+    const LOG2: &'static [f32] = &[1.43901385, -0.679939066, 0.325587084, -0.0847641671];
+    const EXP2: &'static [f32] = &[0.693018524, 0.241445529, 0.0519505146, 0.013581263];
+}
+
+impl Polynomials for High {
+    const LOG2: &'static [f32] = &[
+        1.44196543,
+        -0.709661016,
+        0.417590351,
+        -0.196263171,
+        0.0463827045,
+    ];
+    const EXP2: &'static [f32] = &[
+        0.693152472,
+        0.240152805,
+        0.0558359323,
+        0.00897337324,
+        0.00188529934,
+    ];
+}
+
+const LANES: usize = 8;
+
+type Lanes = [f32; LANES];
+
+#[inline(always)]
+fn horner(coefficients: &[f32], t: f32) -> f32 {
+    coefficients.iter().rev().fold(0.0, |acc, &c| acc * t + c)
+}
+
+// Only valid for positive, normal `x`. Other values give garbage, which the
+// callers discard.
+#[inline(always)]
+fn log2<P: Polynomials>(x: f32) -> f32 {
+    let bits = x.to_bits();
+    let exponent = ((bits >> 23) as i32 - 127) as f32;
+    let t = f32::from_bits((bits & 0x007f_ffff) | 0x3f80_0000) - 1.0;
+    exponent + t * horner(P::LOG2, t)
+}
+
+#[inline(always)]
+// `clamp` would propagate NaN, which can't be converted to an integer below.
+#[allow(clippy::manual_clamp)]
+fn exp2<P: Polynomials>(x: f32) -> f32 {
+    let x = x.max(-126.0).min(127.0);
+    // `floor` would be a library call on targets without a rounding
+    // instruction, which would stop the loops from being vectorized.
+    // Safe because `x` was clamped to the range of `i32`.
+    let i: i32 = unsafe { x.to_int_unchecked() };
+    let i = if i as f32 > x { i - 1 } else { i };
+    let t = x - i as f32;
+    (1.0 + t * horner(P::EXP2, t)) * f32::from_bits(((i + 127) as u32) << 23)
+}
+
+#[inline(always)]
+fn powf<P: Polynomials>(x: f32, y: f32) -> f32 {
+    exp2::<P>(y * log2::<P>(x))
+}
+
+#[inline(always)]
+fn rgbs_to_labs_lanes<P: Polynomials>(r: &Lanes, g: &Lanes, b: &Lanes) -> [Lanes; 3] {
+    #[inline(always)]
+    fn rgb_to_xyz_map<P: Polynomials>(c: f32) -> f32 {
+        const A: f32 = 0.055 * 255.0;
+        const D: f32 = 1.0 / (1.055 * 255.0);
+        const D_LINEAR: f32 = 1.0 / (12.92 * 255.0);
+        let expanded = powf::<P>((c + A) * D, 2.4);
+        if c > E_0_255 {
+            expanded
+        } else {
+            c * D_LINEAR
+        }
+    }
+
+    #[inline(always)]
+    fn xyz_to_lab_map<P: Polynomials>(c: f32) -> f32 {
+        let cbrt = powf::<P>(c, 1.0 / 3.0);
+        if c > EPSILON {
+            cbrt
+        } else {
+            (KAPPA * c + 16.0) * (1.0 / 116.0)
+        }
+    }
+
+    let mut lab = [[0f32; LANES]; 3];
+    for i in 0..LANES {
+        let r = rgb_to_xyz_map::<P>(r[i]);
+        let g = rgb_to_xyz_map::<P>(g[i]);
+        let b = rgb_to_xyz_map::<P>(b[i]);
+
+        // Generated by srgb-matrices.py
+        let x = r * 0.4124108464885388 + g * 0.3575845678529519 + b * 0.18045380393360833;
+        let y = r * 0.21264934272065283 + g * 0.7151691357059038 + b * 0.07218152157344333;
+        let z = r * 0.019331758429150258 + g * 0.11919485595098397 + b * 0.9503900340503373;
+
+        let x = xyz_to_lab_map::<P>(x * (1.0 / WHITE_X));
+        let y = xyz_to_lab_map::<P>(y);
+        let z = xyz_to_lab_map::<P>(z * (1.0 / WHITE_Z));
+
+        lab[0][i] = (116.0 * y) - 16.0;
+        lab[1][i] = 500.0 * (x - y);
+        lab[2][i] = 200.0 * (y - z);
+    }
+    lab
+}
+
+#[inline(always)]
+fn labs_to_rgbs_lanes<P: Polynomials>(l: &Lanes, a: &Lanes, b: &Lanes) -> [[u8; LANES]; 3] {
+    #[inline(always)]
+    fn lab_to_xyz_map(f: f32) -> f32 {
+        if f > CBRT_EPSILON {
+            f * f * f
+        } else {
+            ((f * 116.0) - 16.0) * (1.0 / KAPPA)
+        }
+    }
+
+    #[inline(always)]
+    fn xyz_to_rgb_map<P: Polynomials>(c: f32) -> u8 {
+        let compressed = 1.055 * powf::<P>(c, 1.0 / 2.4) - 0.055;
+        let c = if c > S_0 { compressed } else { 12.92 * c };
+        // Rounds half up rather than away from zero, which is the same for
+        // non-negative numbers, to avoid a library call to `round`.
+        let c = clip(c) * 255.0 + 0.5;
+        // Safe because `c` was clamped to the range of `u8`.
+        unsafe { c.to_int_unchecked() }
+    }
+
+    let mut rgb = [[0u8; LANES]; 3];
+    for i in 0..LANES {
+        let fy = (l[i] + 16.0) * (1.0 / 116.0);
+        let fx = (a[i] * (1.0 / 500.0)) + fy;
+        let fz = fy - (b[i] * (1.0 / 200.0));
+        let x = lab_to_xyz_map(fx) * WHITE_X;
+        let y = if l[i] > EPSILON * KAPPA {
+            fy * fy * fy
+        } else {
+            l[i] * (1.0 / KAPPA)
+        };
+        let z = lab_to_xyz_map(fz) * WHITE_Z;
+
+        // Generated by srgb-matrices.py
+        let r = x * 3.240812398895283 + y * -1.5373084456298136 + z * -0.4985865229069666;
+        let g = x * -0.9692430170086407 + y * 1.8759663029085742 + z * 0.04155503085668564;
+        let b = x * 0.055638398436112804 + y * -0.20400746093241362 + z * 1.0571295702861434;
+
+        rgb[0][i] = xyz_to_rgb_map::<P>(r);
+        rgb[1][i] = xyz_to_rgb_map::<P>(g);
+        rgb[2][i] = xyz_to_rgb_map::<P>(b);
+    }
+    rgb
+}
+
+// Generic over where the colors come from so that both slices of arrays and
+// of bytes can be converted without copying them first.
+fn rgbs_to_labs<P: Polynomials, I: ExactSizeIterator<Item = [u8; 3]>>(rgbs: I) -> Vec<Lab> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if kernel() >= Kernel::Avx2 {
+            return unsafe { rgbs_to_labs_avx2::<P, I>(rgbs) };
+        }
+    }
+    rgbs_to_labs_inner::<P, I>(rgbs)
+}
+
+fn labs_to_rgbs<P: Polynomials>(labs: &[Lab]) -> Vec<[u8; 3]> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if kernel() >= Kernel::Avx2 {
+            return unsafe { labs_to_rgbs_avx2::<P>(labs) };
+        }
+    }
+    labs_to_rgbs_inner::<P>(labs)
+}
+
+// The same code compiled with AVX2 enabled, so that each group of 8 colors is
+// converted with one vector instead of two. FMA stays disabled, so the results
+// are the same as without AVX2.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn rgbs_to_labs_avx2<P: Polynomials, I: ExactSizeIterator<Item = [u8; 3]>>(
+    rgbs: I,
+) -> Vec<Lab> {
+    rgbs_to_labs_inner::<P, I>(rgbs)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn labs_to_rgbs_avx2<P: Polynomials>(labs: &[Lab]) -> Vec<[u8; 3]> {
+    labs_to_rgbs_inner::<P>(labs)
+}
+
+#[inline(always)]
+fn rgbs_to_labs_inner<P: Polynomials, I: ExactSizeIterator<Item = [u8; 3]>>(
+    mut rgbs: I,
+) -> Vec<Lab> {
+    let mut labs = Vec::with_capacity(rgbs.len());
+    while rgbs.len() > 0 {
+        let n = rgbs.len().min(LANES);
+        let mut channels = [[0f32; LANES]; 3];
+        for (i, rgb) in rgbs.by_ref().take(n).enumerate() {
+            channels[0][i] = rgb[0] as f32;
+            channels[1][i] = rgb[1] as f32;
+            channels[2][i] = rgb[2] as f32;
+        }
+        let [l, a, b] = rgbs_to_labs_lanes::<P>(&channels[0], &channels[1], &channels[2]);
+        labs.extend((0..n).map(|i| Lab {
+            l: l[i],
+            a: a[i],
+            b: b[i],
+        }));
+    }
+    labs
+}
+
+#[inline(always)]
+fn labs_to_rgbs_inner<P: Polynomials>(labs: &[Lab]) -> Vec<[u8; 3]> {
+    let mut rgbs = Vec::with_capacity(labs.len());
+    for chunk in labs.chunks(LANES) {
+        let mut channels = [[0f32; LANES]; 3];
+        for (i, lab) in chunk.iter().enumerate() {
+            channels[0][i] = lab.l;
+            channels[1][i] = lab.a;
+            channels[2][i] = lab.b;
+        }
+        let [r, g, b] = labs_to_rgbs_lanes::<P>(&channels[0], &channels[1], &channels[2]);
+        rgbs.extend((0..chunk.len()).map(|i| [r[i], g[i], b[i]]));
+    }
+    rgbs
+}
+
+/// Approximate version of [`rgbs_to_labs`](fn.rgbs_to_labs.html)
+///
+/// # Example
+/// ```
+/// # use lab::{Lab, Precision};
+/// let labs = lab::fast_rgbs_to_labs(&[[240, 33, 95]], Precision::Medium);
+/// assert!(labs[0].squared_distance(&Lab::from_rgb(&[240, 33, 95])) < 0.04 * 0.04);
+/// ```
+pub fn fast_rgbs_to_labs(rgbs: &[[u8; 3]], precision: Precision) -> Vec<Lab> {
+    match precision {
+        Precision::Low => rgbs_to_labs::<Low, _>(rgbs.iter().copied()),
+        Precision::Medium => rgbs_to_labs::<Medium, _>(rgbs.iter().copied()),
+        Precision::High => rgbs_to_labs::<High, _>(rgbs.iter().copied()),
+    }
+}
+
+/// Approximate version of [`rgb_bytes_to_labs`](fn.rgb_bytes_to_labs.html)
+pub fn fast_rgb_bytes_to_labs(bytes: &[u8], precision: Precision) -> Vec<Lab> {
+    let rgbs = bytes.chunks_exact(3).map(|rgb| [rgb[0], rgb[1], rgb[2]]);
+    match precision {
+        Precision::Low => rgbs_to_labs::<Low, _>(rgbs),
+        Precision::Medium => rgbs_to_labs::<Medium, _>(rgbs),
+        Precision::High => rgbs_to_labs::<High, _>(rgbs),
+    }
+}
+
+/// Approximate version of [`labs_to_rgbs`](fn.labs_to_rgbs.html)
+///
+/// # Example
+/// ```
+/// # use lab::{Lab, Precision};
+/// let pink = Lab { l: 66.637695, a: 52.250145, b: 14.858591 };
+/// assert_eq!(lab::fast_labs_to_rgbs(&[pink], Precision::Low), vec![[253, 120, 138]]);
+/// ```
+pub fn fast_labs_to_rgbs(labs: &[Lab], precision: Precision) -> Vec<[u8; 3]> {
+    match precision {
+        Precision::Low => labs_to_rgbs::<Low>(labs),
+        Precision::Medium => labs_to_rgbs::<Medium>(labs),
+        Precision::High => labs_to_rgbs::<High>(labs),
+    }
+}
+
+/// Approximate version of [`labs_to_rgb_bytes`](fn.labs_to_rgb_bytes.html)
+pub fn fast_labs_to_rgb_bytes(labs: &[Lab], precision: Precision) -> Vec<u8> {
+    let rgbs = fast_labs_to_rgbs(labs, precision);
+    rgbs.iter().flat_map(|rgb| rgb.iter().cloned()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        fast_labs_to_rgb_bytes, fast_labs_to_rgbs, fast_rgb_bytes_to_labs, fast_rgbs_to_labs,
+        Precision,
+    };
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+    use {labs_to_rgbs, Lab};
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    const PRECISIONS: [(Precision, f32); 3] = [
+        (Precision::Low, 0.3),
+        (Precision::Medium, 0.04),
+        (Precision::High, 0.006),
+    ];
+
+    fn assert_documented_max_error(rgbs: &[[u8; 3]]) {
+        let labs: Vec<Lab> = rgbs.iter().map(Lab::from_rgb).collect();
+        let exact_rgbs: Vec<[u8; 3]> = labs.iter().map(Lab::to_rgb).collect();
+        for &(precision, max_error) in &PRECISIONS {
+            let lab_error = fast_rgbs_to_labs(rgbs, precision)
+                .iter()
+                .zip(&labs)
+                .map(|(fast, exact)| fast.squared_distance(exact))
+                .fold(0.0, f32::max)
+                .sqrt();
+            assert!(lab_error <= max_error, "{:?}: {}", precision, lab_error);
+
+            assert_eq!(
+                fast_labs_to_rgbs(&labs, precision),
+                exact_rgbs,
+                "{:?}",
+                precision
+            );
+        }
+    }
+
+    #[test]
+    fn test_fast_documented_max_error() {
+        assert_documented_max_error(&RGBS);
+    }
+
+    #[test]
+    fn test_fast_documented_max_error_full_cube() {
+        // Every 5th value of each channel, which includes 0 and 255, so that
+        // the test is quick enough without optimizations.
+        let rgbs: Vec<[u8; 3]> = (0..=255)
+            .step_by(5)
+            .flat_map(|r| (0..=255).step_by(5).map(move |g| (r, g)))
+            .flat_map(|(r, g)| (0..=255).step_by(5).map(move |b| [r, g, b]))
+            .collect();
+        assert_documented_max_error(&rgbs);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_fast_documented_max_error_exhaustive() {
+        // Checks every color, which is too slow without optimizations:
+        // `cargo test --release -- --ignored`
+        let rgbs: Vec<[u8; 3]> = (0..1 << 24)
+            .map(|i: u32| [(i >> 16) as u8, (i >> 8) as u8, i as u8])
+            .collect();
+        assert_documented_max_error(&rgbs);
+    }
+
+    #[test]
+    fn test_fast_rgb_bytes_to_labs() {
+        let bytes: Vec<u8> = RGBS.iter().flat_map(|rgb| rgb.iter().cloned()).collect();
+        for &(precision, _) in &PRECISIONS {
+            assert_eq!(
+                fast_rgb_bytes_to_labs(&bytes, precision),
+                fast_rgbs_to_labs(&RGBS, precision)
+            );
+        }
+    }
+
+    #[test]
+    fn test_fast_labs_to_rgb_bytes() {
+        let labs: Vec<Lab> = RGBS.iter().map(Lab::from_rgb).collect();
+        for &(precision, _) in &PRECISIONS {
+            let bytes: Vec<u8> = fast_labs_to_rgbs(&labs, precision)
+                .iter()
+                .flat_map(|rgb| rgb.iter().cloned())
+                .collect();
+            assert_eq!(fast_labs_to_rgb_bytes(&labs, precision), bytes);
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn test_fast_avx2_matches_generic() {
+        use super::{labs_to_rgbs_avx2, labs_to_rgbs_inner, rgbs_to_labs_avx2, rgbs_to_labs_inner};
+        use super::{High, Low};
+        use simd::{kernel, Kernel};
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
+        let rgbs = &RGBS[..509];
+        let labs = unsafe { rgbs_to_labs_avx2::<Low, _>(rgbs.iter().copied()) };
+        assert_eq!(labs, rgbs_to_labs_inner::<Low, _>(rgbs.iter().copied()));
+        let rgbs = unsafe { labs_to_rgbs_avx2::<High>(&labs) };
+        assert_eq!(rgbs, labs_to_rgbs_inner::<High>(&labs));
+    }
+
+    #[test]
+    fn test_fast_labs_to_rgbs_out_of_gamut() {
+        let labs = [
+            Lab {
+                l: 100.0,
+                a: 100.0,
+                b: 100.0,
+            },
+            Lab {
+                l: -10.0,
+                a: 0.0,
+                b: 0.0,
+            },
+            Lab {
+                l: 50.0,
+                a: -128.0,
+                b: 128.0,
+            },
+        ];
+        for &(precision, _) in &PRECISIONS {
+            assert_eq!(fast_labs_to_rgbs(&labs, precision), labs_to_rgbs(&labs));
+        }
+    }
+}
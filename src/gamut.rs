@@ -0,0 +1,432 @@
+//! Checking whether Lab colors are inside of the sRGB gamut, and finding the
+//! gamut's boundary.
+//!
+//! `Lab::to_rgb` clips each channel to 0..255, so it can't tell a color that
+//! really is, say, pure red from a more saturated one that sRGB can't show.
+//! These functions do the same conversion but report the clipping.
+
+use core::f32::consts::TAU;
+use core::fmt;
+use {clip, lab_to_xyz, rgb_normalized_to_bytes, xyz_to_rgb_unclipped, LCh, Lab};
+
+/// The error returned by [`Lab::to_rgb_checked`](struct.Lab.html#method.to_rgb_checked)
+/// for a color outside of the sRGB gamut
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct OutOfGamut {
+    /// The color with its channels clipped to the gamut, as `Lab::to_rgb`
+    /// returns it
+    pub rgb: [u8; 3],
+    /// How far each of the red, green and blue channels was outside of the
+    /// range 0 to 1 before being clipped, negative for channels below 0 and
+    /// positive for those above 1. Channels that weren't clipped are 0.
+    pub excess: [f32; 3],
+}
+
+impl fmt::Display for OutOfGamut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "color is outside of the sRGB gamut by {:?}", self.excess)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for OutOfGamut {}
+
+// A channel is clipped when clipping it changes its `u8` value, which leaves
+// room for rounding errors: every color converted from RGB is in gamut.
+#[inline]
+fn excess(c: f32) -> f32 {
+    if c * 255.0 <= -0.5 {
+        c
+    } else if c * 255.0 >= 255.5 {
+        c - 1.0
+    } else if c.is_nan() {
+        c
+    } else {
+        0.0
+    }
+}
+
+impl Lab {
+    /// Returns the `Lab`'s color in RGB, or an error if it's outside of the
+    /// sRGB gamut
+    ///
+    /// A channel only counts as out of gamut if clipping it to 0..255 changes
+    /// its rounded value, so small rounding errors don't count and every
+    /// color converted from RGB is in gamut. The error holds the clipped
+    /// color that `to_rgb` would have returned, and how far out of gamut each
+    /// channel was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lab::Lab;
+    /// let lab = Lab::from_rgb(&[240, 33, 95]);
+    /// assert_eq!(lab.to_rgb_checked(), Ok([240, 33, 95]));
+    ///
+    /// let too_red = Lab { l: 55.0, a: 90.0, b: 70.0 };
+    /// let err = too_red.to_rgb_checked().unwrap_err();
+    /// assert_eq!(err.rgb, too_red.to_rgb());
+    /// assert!(err.excess[0] > 0.0 && err.excess[1] < 0.0);
+    /// ```
+    pub fn to_rgb_checked(&self) -> Result<[u8; 3], OutOfGamut> {
+        let rgb = xyz_to_rgb_unclipped(lab_to_xyz(self));
+        let excess = [excess(rgb[0]), excess(rgb[1]), excess(rgb[2])];
+        let clipped = rgb_normalized_to_bytes([clip(rgb[0]), clip(rgb[1]), clip(rgb[2])]);
+        if excess == [0.0; 3] {
+            Ok(clipped)
+        } else {
+            Err(OutOfGamut {
+                rgb: clipped,
+                excess,
+            })
+        }
+    }
+
+    /// Returns whether the `Lab`'s color is inside of the sRGB gamut, as
+    /// decided by [`to_rgb_checked`](#method.to_rgb_checked)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lab::Lab;
+    /// assert!(Lab::from_rgb(&[240, 33, 95]).is_in_gamut());
+    /// assert!(!Lab { l: 55.0, a: 90.0, b: 70.0 }.is_in_gamut());
+    /// ```
+    pub fn is_in_gamut(&self) -> bool {
+        let rgb = xyz_to_rgb_unclipped(lab_to_xyz(self));
+        rgb.iter().all(|&c| excess(c) == 0.0)
+    }
+}
+
+impl LCh {
+    /// Returns the `LCh`'s color in RGB, or an error if it's outside of the
+    /// sRGB gamut. See [`Lab::to_rgb_checked`](struct.Lab.html#method.to_rgb_checked).
+    pub fn to_rgb_checked(&self) -> Result<[u8; 3], OutOfGamut> {
+        self.to_lab().to_rgb_checked()
+    }
+
+    /// Returns whether the `LCh`'s color is inside of the sRGB gamut. See
+    /// [`Lab::is_in_gamut`](struct.Lab.html#method.is_in_gamut).
+    pub fn is_in_gamut(&self) -> bool {
+        self.to_lab().is_in_gamut()
+    }
+}
+
+/// Returns whether each of `labs` is inside of the sRGB gamut, as
+/// [`Lab::is_in_gamut`](struct.Lab.html#method.is_in_gamut)
+///
+/// # Example
+/// ```
+/// # use lab::{gamut_mask, Lab};
+/// let labs = &[Lab::from_rgb(&[240, 33, 95]), Lab { l: 55.0, a: 90.0, b: 70.0 }];
+/// assert_eq!(gamut_mask(labs), vec![true, false]);
+/// ```
+#[cfg(feature = "std")]
+pub fn gamut_mask(labs: &[Lab]) -> Vec<bool> {
+    labs.iter().map(Lab::is_in_gamut).collect()
+}
+
+/// Writes whether each of `labs` is inside of the sRGB gamut to `mask`
+///
+/// Unlike [`gamut_mask`](fn.gamut_mask.html) it doesn't allocate, so it's also
+/// available without the `std` feature.
+///
+/// # Panics
+///
+/// Panics if `mask` isn't the same length as `labs`.
+pub fn gamut_mask_into(labs: &[Lab], mask: &mut [bool]) {
+    assert_eq!(labs.len(), mask.len(), "output length must match input");
+    for (lab, in_gamut) in labs.iter().zip(mask.iter_mut()) {
+        *in_gamut = lab.is_in_gamut();
+    }
+}
+
+// Goes around the edges of the RGB cube that join the primaries and
+// secondaries, which hold the most saturated color of every hue: red at 0,
+// yellow at 1, then green, cyan, blue and magenta, and back to red at 6.
+fn hue_ring(u: f32) -> [f32; 3] {
+    let k = (u as usize).min(5);
+    let t = u - k as f32;
+    match k {
+        0 => [1.0, t, 0.0],
+        1 => [1.0 - t, 1.0, 0.0],
+        2 => [0.0, 1.0, t],
+        3 => [0.0, 1.0 - t, 1.0],
+        4 => [t, 0.0, 1.0],
+        _ => [1.0, 0.0, 1.0 - t],
+    }
+}
+
+/// Returns the most saturated color in sRGB with the hue `h`, in radians
+///
+/// The cusp is where the gamut's boundary at that hue turns from getting
+/// lighter to getting darker as chroma decreases. It always has an RGB
+/// channel at 0 and another at 255.
+///
+/// # Examples
+///
+/// ```
+/// # use lab::{cusp, LCh};
+/// let red = LCh::from_rgb(&[255, 0, 0]);
+/// let cusp = cusp(red.h);
+/// assert!((cusp.l - red.l).abs() < 0.01 && (cusp.c - red.c).abs() < 0.01);
+/// ```
+pub fn cusp(h: f32) -> LCh {
+    // Hues measured from red's increase monotonically around the ring.
+    let red = LCh::from_lab(Lab::from_rgb_normalized(&[1.0, 0.0, 0.0])).h;
+    let from_red = |h: f32| {
+        let d = (h - red) % TAU;
+        if d < 0.0 {
+            d + TAU
+        } else {
+            d
+        }
+    };
+    let target = from_red(h);
+    let (mut lo, mut hi) = (0.0, 6.0);
+    for _ in 0..32 {
+        let mid = (lo + hi) / 2.0;
+        let lch = LCh::from_lab(Lab::from_rgb_normalized(&hue_ring(mid)));
+        if from_red(lch.h) < target {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    LCh::from_lab(Lab::from_rgb_normalized(&hue_ring(lo)))
+}
+
+// Whether a color is inside of the gamut exactly, without `is_in_gamut`'s
+// allowance for rounding to `u8`.
+// Chroma in sRGB stays below 135, so 200 is always out of gamut.
+pub(crate) const MAX_CHROMA: f32 = 200.0;
+
+fn is_inside(lch: &LCh) -> bool {
+    let rgb = xyz_to_rgb_unclipped(lab_to_xyz(&lch.to_lab()));
+    rgb.iter().all(|c| (0.0..=1.0).contains(c))
+}
+
+/// Returns the highest chroma that a color with the lightness `l` and hue
+/// `h`, in radians, can have in sRGB
+///
+/// Colors with that chroma are on the boundary of the gamut. Lightness
+/// outside of 0..100 has no colors in gamut and gives 0.
+///
+/// # Examples
+///
+/// ```
+/// # use lab::{max_chroma, LCh};
+/// let lch = LCh { l: 50.0, c: max_chroma(50.0, 4.0), h: 4.0 };
+/// assert!(lch.is_in_gamut());
+/// assert!(!LCh { c: lch.c + 0.1, ..lch }.is_in_gamut());
+/// ```
+pub fn max_chroma(l: f32, h: f32) -> f32 {
+    let (mut lo, mut hi) = (0.0, MAX_CHROMA);
+    for _ in 0..24 {
+        let c = (lo + hi) / 2.0;
+        if is_inside(&LCh { l, c, h }) {
+            lo = c;
+        } else {
+            hi = c;
+        }
+    }
+    lo
+}
+
+/// Samples the boundary of the sRGB gamut
+///
+/// Returns `lightness_steps` rows of `hue_steps` colors each. Row `i` has
+/// the lightness `100 * i / (lightness_steps - 1)`, so that the first and
+/// last rows are black and white, and column `j` the hue `2π * j / hue_steps`.
+/// Each color has the highest chroma in gamut for its lightness and hue, as
+/// given by [`max_chroma`](fn.max_chroma.html).
+///
+/// # Panics
+///
+/// Panics if `lightness_steps` is less than 2.
+///
+/// # Example
+/// ```
+/// # use lab::gamut_boundary;
+/// let boundary = gamut_boundary(11, 36);
+/// assert_eq!(boundary.len(), 11 * 36);
+/// assert_eq!(boundary[5 * 36].l, 50.0);
+/// ```
+#[cfg(feature = "std")]
+pub fn gamut_boundary(lightness_steps: usize, hue_steps: usize) -> Vec<LCh> {
+    let mut boundary = vec![LCh::default(); lightness_steps * hue_steps];
+    gamut_boundary_into(lightness_steps, hue_steps, &mut boundary);
+    boundary
+}
+
+/// Samples the boundary of the sRGB gamut into `boundary`, as
+/// [`gamut_boundary`](fn.gamut_boundary.html)
+///
+/// # Panics
+///
+/// Panics if `lightness_steps` is less than 2, or if `boundary` isn't
+/// `lightness_steps * hue_steps` long.
+pub fn gamut_boundary_into(lightness_steps: usize, hue_steps: usize, boundary: &mut [LCh]) {
+    assert!(lightness_steps >= 2, "at least black and white are needed");
+    assert_eq!(
+        lightness_steps * hue_steps,
+        boundary.len(),
+        "output length must match the number of samples"
+    );
+    for (i, row) in boundary.chunks_exact_mut(hue_steps.max(1)).enumerate() {
+        let l = 100.0 * i as f32 / (lightness_steps - 1) as f32;
+        for (j, lch) in row.iter_mut().enumerate() {
+            let h = TAU * j as f32 / hue_steps as f32;
+            *lch = LCh {
+                l,
+                c: max_chroma(l, h),
+                h,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cusp, gamut_boundary_into, gamut_mask_into, is_inside, max_chroma, OutOfGamut};
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+    use {LCh, Lab};
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    #[test]
+    fn test_rgbs_are_in_gamut() {
+        let corners = [[0, 0, 0], [255, 255, 255], [255, 0, 0], [0, 255, 255]];
+        for rgb in RGBS.iter().chain(corners.iter()) {
+            let lab = Lab::from_rgb(rgb);
+            assert!(lab.is_in_gamut(), "{:?}", rgb);
+            assert_eq!(lab.to_rgb_checked(), Ok(*rgb));
+            assert_eq!(LCh::from_lab(lab).to_rgb_checked(), Ok(*rgb));
+        }
+    }
+
+    #[test]
+    fn test_out_of_gamut() {
+        // Brighter than white.
+        let lab = Lab {
+            l: 110.0,
+            a: 0.0,
+            b: 0.0,
+        };
+        let err = lab.to_rgb_checked().unwrap_err();
+        assert_eq!(err.rgb, [255, 255, 255]);
+        assert!(err.excess.iter().all(|&e| e > 0.0));
+
+        // More saturated than sRGB's green, which only clips red.
+        let lab = Lab {
+            l: 87.7,
+            a: -120.0,
+            b: 83.2,
+        };
+        let err = lab.to_rgb_checked().unwrap_err();
+        assert_eq!(err.rgb, lab.to_rgb());
+        assert!(err.excess[0] < 0.0);
+        assert!(!lab.is_in_gamut());
+        assert!(!LCh::from_lab(lab).is_in_gamut());
+    }
+
+    #[test]
+    fn test_nan_is_out_of_gamut() {
+        let lab = Lab {
+            l: f32::NAN,
+            a: 0.0,
+            b: 0.0,
+        };
+        assert!(!lab.is_in_gamut());
+        assert!(lab.to_rgb_checked().is_err());
+    }
+
+    #[test]
+    fn test_gamut_mask_into() {
+        let mut labs: Vec<Lab> = RGBS.iter().map(Lab::from_rgb).collect();
+        for lab in labs.iter_mut().step_by(3) {
+            lab.a += 150.0;
+        }
+        let mut mask = vec![false; labs.len()];
+        gamut_mask_into(&labs, &mut mask);
+        let expected: Vec<_> = labs.iter().map(Lab::is_in_gamut).collect();
+        assert_eq!(mask, expected);
+        assert!(mask.iter().any(|&m| m) && mask.iter().any(|&m| !m));
+    }
+
+    #[test]
+    fn test_cusp() {
+        // The cusps of the primaries and secondaries are the colors themselves.
+        for rgb in &[[255, 0, 0], [255, 255, 0], [0, 0, 255], [255, 0, 255]] {
+            let lch = LCh::from_rgb(rgb);
+            let cusp = cusp(lch.h);
+            assert!((cusp.l - lch.l).abs() < 0.01, "{:?}", rgb);
+            assert!((cusp.c - lch.c).abs() < 0.01, "{:?}", rgb);
+        }
+
+        // No color of the same hue in the RGB cube is more saturated.
+        for rgb in RGBS.iter() {
+            let lch = LCh::from_rgb(rgb);
+            if lch.c > 1.0 {
+                let cusp = cusp(lch.h);
+                assert!((cusp.h - lch.h).abs() < 0.001, "{:?}", rgb);
+                assert!(cusp.c >= lch.c, "{:?}", rgb);
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_chroma() {
+        // The primaries and secondaries are on the boundary.
+        for rgb in &[[255, 0, 0], [0, 255, 0], [0, 0, 255], [0, 255, 255]] {
+            let lch = LCh::from_rgb(rgb);
+            assert!((max_chroma(lch.l, lch.h) - lch.c).abs() < 0.01, "{:?}", rgb);
+        }
+
+        for rgb in RGBS.iter() {
+            let lch = LCh::from_rgb(rgb);
+            let c = max_chroma(lch.l, lch.h);
+            assert!(c >= lch.c - 0.01, "{:?}", rgb);
+            assert!(LCh { c, ..lch }.is_in_gamut(), "{:?}", rgb);
+            assert!(!is_inside(&LCh { c: c + 0.01, ..lch }), "{:?}", rgb);
+            assert!(cusp(lch.h).c >= c - 0.01, "{:?}", rgb);
+        }
+
+        assert_eq!(max_chroma(-1.0, 1.0), 0.0);
+        assert_eq!(max_chroma(101.0, 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_gamut_boundary_into() {
+        let mut boundary = vec![LCh::default(); 5 * 8];
+        gamut_boundary_into(5, 8, &mut boundary);
+        assert_eq!(boundary[0].l, 0.0);
+        assert_eq!(boundary[4 * 8].l, 100.0);
+        for lch in &boundary[8..4 * 8] {
+            assert_eq!(lch.c, max_chroma(lch.l, lch.h));
+            assert!(lch.c > 10.0);
+        }
+        assert_eq!(boundary[2 * 8 + 2].h, ::core::f32::consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn test_display() {
+        let err = OutOfGamut {
+            rgb: [255, 0, 0],
+            excess: [0.25, -0.5, 0.0],
+        };
+        assert_eq!(
+            err.to_string(),
+            "color is outside of the sRGB gamut by [0.25, -0.5, 0.0]"
+        );
+    }
+}
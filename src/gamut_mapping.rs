@@ -0,0 +1,355 @@
+//! Bringing colors outside of the sRGB gamut inside of it.
+//!
+//! `Lab::to_rgb` clips each RGB channel separately, which can visibly shift
+//! the hue of saturated colors. The other strategies here work in LCh and
+//! keep the hue, giving up chroma, and for `Cusp` lightness, instead.
+
+use gamut::{cusp, MAX_CHROMA};
+use math::sqrtf;
+use {lab_to_xyz, xyz_to_rgb_normalized, LCh, Lab};
+
+// Just noticeable difference, and how precisely to search for chroma, for
+// `GamutMapping::Css`. CSS Color 4 uses 0.02 and 0.0001 in OKLab, whose
+// lightness runs from 0 to 1 instead of 0 to 100.
+const JND: f32 = 2.0;
+const CHROMA_EPSILON: f32 = 0.01;
+
+/// A strategy for bringing colors outside of the sRGB gamut inside of it
+///
+/// Colors already inside of the gamut are left as they are by every
+/// strategy.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum GamutMapping {
+    /// Clips each RGB channel to 0..255, as `Lab::to_rgb` does. Fast, but it
+    /// shifts the hue of saturated colors.
+    Clip,
+    /// Reduces chroma at constant lightness and hue until the color is in
+    /// gamut. Lightness of 100 or more maps to white and of 0 or less to
+    /// black.
+    ReduceChroma,
+    /// The [CSS Color 4 algorithm](https://www.w3.org/TR/css-color-4/#binsearch):
+    /// like `ReduceChroma`, but stops as soon as clipping the color changes it
+    /// by less than a just noticeable difference, a ΔE of 2, which keeps more
+    /// of its chroma.
+    Css,
+    /// Moves the color towards the grey with the lightness of the most
+    /// saturated color of its hue, its cusp, until it's in gamut. This keeps
+    /// the hue and gives up some lightness for less of a loss of chroma,
+    /// which also brings colors lighter than white or darker than black into
+    /// gamut smoothly.
+    Cusp,
+}
+
+impl Lab {
+    /// Returns the `Lab`'s color brought inside of the sRGB gamut with the
+    /// given strategy
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lab::{GamutMapping, Lab};
+    /// let too_red = Lab { l: 55.0, a: 90.0, b: 70.0 };
+    /// let mapped = too_red.map_to_gamut(GamutMapping::ReduceChroma);
+    /// assert!(mapped.is_in_gamut());
+    /// assert_eq!(mapped.l, too_red.l);
+    /// ```
+    pub fn map_to_gamut(&self, mapping: GamutMapping) -> Lab {
+        match mapping {
+            GamutMapping::Clip => clip(self),
+            _ => LCh::from_lab(*self).map_to_gamut(mapping).to_lab(),
+        }
+    }
+
+    /// Returns the `Lab`'s color in RGB, brought inside of the sRGB gamut
+    /// with the given strategy
+    ///
+    /// `to_rgb_mapped(GamutMapping::Clip)` is the same as `to_rgb()`.
+    pub fn to_rgb_mapped(&self, mapping: GamutMapping) -> [u8; 3] {
+        match mapping {
+            GamutMapping::Clip => self.to_rgb(),
+            _ => self.map_to_gamut(mapping).to_rgb(),
+        }
+    }
+}
+
+impl LCh {
+    /// Returns the `LCh`'s color brought inside of the sRGB gamut with the
+    /// given strategy
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lab::{GamutMapping, LCh};
+    /// let too_green = LCh { l: 80.0, c: 130.0, h: 2.3 };
+    /// let mapped = too_green.map_to_gamut(GamutMapping::Css);
+    /// assert!(mapped.is_in_gamut());
+    /// assert!(mapped.c < too_green.c);
+    /// ```
+    pub fn map_to_gamut(&self, mapping: GamutMapping) -> LCh {
+        match mapping {
+            GamutMapping::Clip => LCh::from_lab(clip(&self.to_lab())),
+            GamutMapping::ReduceChroma => reduce_chroma(self),
+            GamutMapping::Css => css(self),
+            GamutMapping::Cusp => towards_cusp(self),
+        }
+    }
+
+    /// Returns the `LCh`'s color in RGB, brought inside of the sRGB gamut
+    /// with the given strategy
+    pub fn to_rgb_mapped(&self, mapping: GamutMapping) -> [u8; 3] {
+        self.to_lab().to_rgb_mapped(mapping)
+    }
+}
+
+fn clip(lab: &Lab) -> Lab {
+    Lab::from_rgb_normalized(&xyz_to_rgb_normalized(lab_to_xyz(lab)))
+}
+
+// White and black for colors that no amount of chroma reduction brings into
+// gamut.
+fn out_of_lightness(lch: &LCh) -> Option<LCh> {
+    if lch.l >= 100.0 {
+        Some(LCh {
+            l: 100.0,
+            c: 0.0,
+            h: lch.h,
+        })
+    } else if lch.l <= 0.0 {
+        Some(LCh {
+            l: 0.0,
+            c: 0.0,
+            h: lch.h,
+        })
+    } else {
+        None
+    }
+}
+
+fn reduce_chroma(lch: &LCh) -> LCh {
+    if lch.is_in_gamut() {
+        return *lch;
+    }
+    if let Some(lch) = out_of_lightness(lch) {
+        return lch;
+    }
+    let mut current = *lch;
+    // Capped so that the search ends for infinite chroma.
+    let (mut min, mut max) = (0.0, lch.c.min(MAX_CHROMA));
+    while max - min > CHROMA_EPSILON {
+        current.c = (min + max) / 2.0;
+        if current.is_in_gamut() {
+            min = current.c;
+        } else {
+            max = current.c;
+        }
+    }
+    current.c = min;
+    current
+}
+
+fn css(lch: &LCh) -> LCh {
+    if lch.is_in_gamut() {
+        return *lch;
+    }
+    if let Some(lch) = out_of_lightness(lch) {
+        return lch;
+    }
+    let delta_e = |lab: &Lab, other: &Lab| sqrtf(lab.squared_distance(other));
+
+    let mut current = *lch;
+    let mut clipped = clip(&current.to_lab());
+    if delta_e(&clipped, &current.to_lab()) < JND {
+        return LCh::from_lab(clipped);
+    }
+    let (mut min, mut max) = (0.0, lch.c.min(MAX_CHROMA));
+    let mut min_in_gamut = true;
+    while max - min > CHROMA_EPSILON {
+        current.c = (min + max) / 2.0;
+        if min_in_gamut && current.is_in_gamut() {
+            min = current.c;
+            continue;
+        }
+        let lab = current.to_lab();
+        clipped = clip(&lab);
+        let e = delta_e(&clipped, &lab);
+        if e < JND {
+            if JND - e < CHROMA_EPSILON {
+                break;
+            }
+            min_in_gamut = false;
+            min = current.c;
+        } else {
+            max = current.c;
+        }
+    }
+    LCh::from_lab(clipped)
+}
+
+fn towards_cusp(lch: &LCh) -> LCh {
+    if lch.is_in_gamut() {
+        return *lch;
+    }
+    // Towards infinite chroma, the line from the grey runs along the
+    // cusp's lightness and meets the gamut at the cusp.
+    if lch.c == f32::INFINITY {
+        return cusp(lch.h);
+    }
+    // The grey is in gamut, so the search is always between it and the
+    // color.
+    let grey = cusp(lch.h).l;
+    let at = |t: f32| LCh {
+        l: grey + (lch.l - grey) * t,
+        c: lch.c * t,
+        h: lch.h,
+    };
+    let (mut min, mut max) = (0.0, 1.0);
+    while max - min > CHROMA_EPSILON / 100.0 {
+        let t = (min + max) / 2.0;
+        if at(t).is_in_gamut() {
+            min = t;
+        } else {
+            max = t;
+        }
+    }
+    at(min)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{GamutMapping, JND};
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+    use {LCh, Lab};
+
+    const STRATEGIES: [GamutMapping; 4] = [
+        GamutMapping::Clip,
+        GamutMapping::ReduceChroma,
+        GamutMapping::Css,
+        GamutMapping::Cusp,
+    ];
+
+    lazy_static! {
+        // Colors with any lightness, hue and a lot of chroma, most of which
+        // are out of gamut.
+        static ref LCHS: Vec<LCh> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard)
+                .take(512)
+                .map(|(l, c, h): (f32, f32, f32)| LCh {
+                    l: l * 110.0 - 5.0,
+                    c: c * 180.0,
+                    h: h * 6.0 - 3.0,
+                })
+                .collect()
+        };
+    }
+
+    fn hue_difference(a: f32, b: f32) -> f32 {
+        let d = (a - b).abs() % ::core::f32::consts::TAU;
+        d.min(::core::f32::consts::TAU - d)
+    }
+
+    #[test]
+    fn test_in_gamut_colors_are_unchanged() {
+        for &rgb in &[[240, 33, 95], [0, 0, 0], [255, 255, 255], [0, 255, 0]] {
+            let lab = Lab::from_rgb(&rgb);
+            for &mapping in STRATEGIES.iter() {
+                assert_eq!(lab.to_rgb_mapped(mapping), rgb, "{:?}", mapping);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clip_matches_to_rgb() {
+        for lch in LCHS.iter() {
+            let lab = lch.to_lab();
+            assert_eq!(lab.to_rgb_mapped(GamutMapping::Clip), lab.to_rgb());
+            assert_eq!(lab.map_to_gamut(GamutMapping::Clip).to_rgb(), lab.to_rgb());
+        }
+    }
+
+    #[test]
+    fn test_mapped_colors_are_in_gamut() {
+        for lch in LCHS.iter() {
+            for &mapping in STRATEGIES.iter() {
+                let mapped = lch.map_to_gamut(mapping);
+                assert!(mapped.is_in_gamut(), "{:?} {:?}", mapping, lch);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reduce_chroma_keeps_lightness_and_hue() {
+        for lch in LCHS.iter().filter(|lch| lch.l > 0.0 && lch.l < 100.0) {
+            let mapped = lch.map_to_gamut(GamutMapping::ReduceChroma);
+            assert_eq!(mapped.l, lch.l);
+            assert_eq!(mapped.h, lch.h);
+            assert!(mapped.c <= lch.c);
+            // Any more chroma would be out of gamut.
+            if mapped.c < lch.c {
+                let more = LCh {
+                    c: mapped.c + 0.02,
+                    ..mapped
+                };
+                assert!(!more.is_in_gamut(), "{:?}", lch);
+            }
+        }
+    }
+
+    #[test]
+    fn test_css_keeps_more_chroma() {
+        // Stopping at a JND from the gamut keeps at least as much chroma as
+        // reducing it until the color is in gamut, give or take the clipping.
+        for lch in LCHS.iter().filter(|lch| lch.l > 0.0 && lch.l < 100.0) {
+            let css = lch.map_to_gamut(GamutMapping::Css);
+            let reduced = lch.map_to_gamut(GamutMapping::ReduceChroma);
+            assert!(css.c >= reduced.c - JND, "{:?}", lch);
+        }
+    }
+
+    #[test]
+    fn test_cusp_keeps_hue() {
+        for lch in LCHS.iter() {
+            let mapped = lch.map_to_gamut(GamutMapping::Cusp);
+            if mapped.c > 1.0 {
+                assert!(hue_difference(mapped.h, lch.h) < 0.001, "{:?}", lch);
+            }
+        }
+    }
+
+    #[test]
+    fn test_out_of_lightness() {
+        let lch = LCh {
+            l: 105.0,
+            c: 50.0,
+            h: 1.0,
+        };
+        assert_eq!(lch.to_rgb_mapped(GamutMapping::ReduceChroma), [255; 3]);
+        assert_eq!(lch.to_rgb_mapped(GamutMapping::Css), [255; 3]);
+        let lch = LCh { l: -5.0, ..lch };
+        assert_eq!(lch.to_rgb_mapped(GamutMapping::ReduceChroma), [0; 3]);
+        assert_eq!(lch.to_rgb_mapped(GamutMapping::Css), [0; 3]);
+    }
+
+    #[test]
+    fn test_infinite_chroma() {
+        let lch = LCh {
+            l: 50.0,
+            c: f32::INFINITY,
+            h: 1.0,
+        };
+        for &mapping in &STRATEGIES[1..] {
+            let mapped = lch.map_to_gamut(mapping);
+            assert!(mapped.is_in_gamut(), "{:?} {:?}", mapping, mapped);
+        }
+        let lab = Lab {
+            l: 50.0,
+            a: f32::INFINITY,
+            b: 0.0,
+        };
+        assert!(lab.map_to_gamut(GamutMapping::ReduceChroma).is_in_gamut());
+    }
+}
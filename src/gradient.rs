@@ -0,0 +1,494 @@
+//! Gradients with easing, Bézier curves, and perceptually even spacing.
+//!
+//! `sample_gradient` spaces stops evenly in `t`, but the colors between them
+//! don't change evenly to the eye: a step from black to dark grey looks much
+//! bigger than one of the same length from light grey to white in some
+//! gradients and smaller in others. A `Gradient` can instead space its
+//! samples by the color difference along the curve, so that every step is the
+//! same ΔE.
+
+use delta_e::DeltaE;
+use interpolate::{sample_gradient, InterpolationSpace};
+use math::{exp, log};
+use Lab;
+
+// How many pieces the curve is cut into to measure its length in ΔE. Fixed,
+// so that a `Gradient` doesn't allocate.
+const TABLE_STEPS: usize = 256;
+
+/// A function that reshapes how a gradient progresses from its first color
+/// to its last
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum Easing {
+    /// Progresses at a constant speed
+    #[default]
+    Linear,
+    /// Starts slowly and speeds up, t²
+    EaseIn,
+    /// Starts quickly and slows down, 1 - (1 - t)²
+    EaseOut,
+    /// Starts and ends slowly, the smoothstep 3t² - 2t³
+    EaseInOut,
+}
+
+impl Easing {
+    /// Returns how far along the gradient, from 0 to 1, the eased position
+    /// `t` is
+    ///
+    /// `t` is clamped to 0..1 first. Every easing maps 0 to 0 and 1 to 1.
+    ///
+    /// # Example
+    /// ```
+    /// # use lab::Easing;
+    /// assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+    /// assert_eq!(Easing::EaseOut.apply(0.5), 0.75);
+    /// ```
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Builds a [`Gradient`](struct.Gradient.html)
+///
+/// By default, the gradient goes through its stops in Lab, spaced evenly in
+/// `t` as [`sample_gradient`](fn.sample_gradient.html) does, with linear
+/// easing.
+///
+/// # Example
+/// ```
+/// # use lab::{DeltaE, Easing, GradientBuilder, Lab};
+/// let stops = [Lab::from_rgb(&[0, 0, 0]), Lab::from_rgb(&[255, 200, 0])];
+/// let gradient = GradientBuilder::new(&stops)
+///     .easing(Easing::EaseInOut)
+///     .uniform(DeltaE::Cie2000)
+///     .build();
+/// assert_eq!(gradient.sample(0.0).to_rgb(), [0, 0, 0]);
+/// assert_eq!(gradient.sample(1.0).to_rgb(), [255, 200, 0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct GradientBuilder<'a> {
+    stops: &'a [Lab],
+    space: InterpolationSpace,
+    bezier: bool,
+    easing: Easing,
+    uniform: Option<DeltaE>,
+}
+
+impl<'a> GradientBuilder<'a> {
+    /// Starts a gradient through `stops`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stops` is empty.
+    pub fn new(stops: &'a [Lab]) -> Self {
+        assert!(!stops.is_empty(), "a gradient needs at least one stop");
+        GradientBuilder {
+            stops,
+            space: InterpolationSpace::Lab,
+            bezier: false,
+            easing: Easing::Linear,
+            uniform: None,
+        }
+    }
+
+    /// Interpolates between neighboring stops in `space`
+    ///
+    /// Has no effect on a [`bezier`](#method.bezier) gradient.
+    pub fn space(mut self, space: InterpolationSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    /// Makes the gradient a Bézier curve in Lab, with the stops as its
+    /// control points
+    ///
+    /// The gradient starts at the first stop and ends at the last, and is
+    /// pulled towards the ones in between without going through them, which
+    /// smooths out the sharp turns a gradient makes at its stops.
+    pub fn bezier(mut self) -> Self {
+        self.bezier = true;
+        self
+    }
+
+    /// Reshapes how the gradient progresses with `easing`
+    ///
+    /// With [`uniform`](#method.uniform) spacing, the easing applies to the
+    /// distance along the gradient in ΔE.
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Spaces the gradient by color difference, measured with `delta_e`,
+    /// instead of evenly between its stops
+    ///
+    /// Evenly spaced values of `t` then give colors that are an equal ΔE
+    /// apart, regardless of how far apart the stops are. The gradient's
+    /// length is measured in 256 pieces, so with many stops, or stops that
+    /// are very far apart, the steps are only approximately equal.
+    pub fn uniform(mut self, delta_e: DeltaE) -> Self {
+        self.uniform = Some(delta_e);
+        self
+    }
+
+    /// Builds the gradient, measuring its length if it's uniform
+    pub fn build(self) -> Gradient<'a> {
+        let mut gradient = Gradient {
+            stops: self.stops,
+            space: self.space,
+            bezier: self.bezier,
+            easing: self.easing,
+            lengths: None,
+        };
+        if let Some(delta_e) = self.uniform {
+            let mut lengths = [0.0; TABLE_STEPS + 1];
+            let mut previous = gradient.curve(0.0);
+            for i in 1..=TABLE_STEPS {
+                let lab = gradient.curve(i as f32 / TABLE_STEPS as f32);
+                lengths[i] = lengths[i - 1] + delta_e.distance(&previous, &lab);
+                previous = lab;
+            }
+            gradient.lengths = Some(lengths);
+        }
+        gradient
+    }
+}
+
+/// A gradient through a slice of `Lab` colors, built with a
+/// [`GradientBuilder`](struct.GradientBuilder.html)
+#[derive(Debug, Clone)]
+pub struct Gradient<'a> {
+    stops: &'a [Lab],
+    space: InterpolationSpace,
+    bezier: bool,
+    easing: Easing,
+    // The cumulative ΔE along the curve at `TABLE_STEPS` evenly spaced
+    // points, for uniform gradients.
+    lengths: Option<[f32; TABLE_STEPS + 1]>,
+}
+
+impl<'a> Gradient<'a> {
+    /// Returns the color at `t` along the gradient
+    ///
+    /// `t` is clamped to 0..1. 0 gives the first stop, and 1 the last.
+    pub fn sample(&self, t: f32) -> Lab {
+        let u = self.easing.apply(t);
+        let s = match self.lengths {
+            Some(ref lengths) => reparameterize(lengths, u),
+            None => u,
+        };
+        self.curve(s)
+    }
+
+    /// Samples the gradient at evenly spaced points, one for each of `labs`,
+    /// from its first color to its last
+    pub fn samples_into(&self, labs: &mut [Lab]) {
+        let last = labs.len().saturating_sub(1).max(1) as f32;
+        for (i, lab) in labs.iter_mut().enumerate() {
+            *lab = self.sample(i as f32 / last);
+        }
+    }
+
+    /// Samples the gradient at evenly spaced points, one for each of `rgbs`,
+    /// from its first color to its last, and converts them to RGB
+    pub fn to_rgbs_into(&self, rgbs: &mut [[u8; 3]]) {
+        let last = rgbs.len().saturating_sub(1).max(1) as f32;
+        for (i, rgb) in rgbs.iter_mut().enumerate() {
+            *rgb = self.sample(i as f32 / last).to_rgb();
+        }
+    }
+
+    /// Samples the gradient at `samples` evenly spaced points, from its first
+    /// color to its last
+    ///
+    /// # Example
+    /// ```
+    /// # use lab::{DeltaE, GradientBuilder, Lab};
+    /// let stops = [
+    ///     Lab { l: 0.0, a: 0.0, b: 0.0 },
+    ///     Lab { l: 10.0, a: 0.0, b: 0.0 },
+    ///     Lab { l: 100.0, a: 0.0, b: 0.0 },
+    /// ];
+    /// let labs = GradientBuilder::new(&stops)
+    ///     .uniform(DeltaE::Cie76)
+    ///     .build()
+    ///     .samples(5);
+    /// // Evenly spaced in lightness, rather than with the middle sample at
+    /// // the second stop.
+    /// assert!((labs[2].l - 50.0).abs() < 0.01);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn samples(&self, samples: usize) -> Vec<Lab> {
+        let mut labs = vec![Lab::default(); samples];
+        self.samples_into(&mut labs);
+        labs
+    }
+
+    /// Samples the gradient at `samples` evenly spaced points, from its first
+    /// color to its last, and converts them to RGB
+    #[cfg(feature = "std")]
+    pub fn to_rgbs(&self, samples: usize) -> Vec<[u8; 3]> {
+        let mut rgbs = vec![[0; 3]; samples];
+        self.to_rgbs_into(&mut rgbs);
+        rgbs
+    }
+
+    // The color at `s` along the curve, before easing and reparameterizing.
+    fn curve(&self, s: f32) -> Lab {
+        if self.bezier {
+            bezier(self.stops, s)
+        } else {
+            sample_gradient(self.stops, self.space, s)
+        }
+    }
+}
+
+// Finds where along the curve the fraction `u` of its total length is.
+fn reparameterize(lengths: &[f32; TABLE_STEPS + 1], u: f32) -> f32 {
+    let total = lengths[TABLE_STEPS];
+    if total <= 0.0 {
+        return u;
+    }
+    let target = u * total;
+    // The first point at least `target` along, and the one before it.
+    let i = lengths
+        .partition_point(|&length| length < target)
+        .clamp(1, TABLE_STEPS);
+    let (before, after) = (lengths[i - 1], lengths[i]);
+    let fraction = if after > before {
+        (target - before) / (after - before)
+    } else {
+        0.0
+    };
+    ((i - 1) as f32 + fraction.clamp(0.0, 1.0)) / TABLE_STEPS as f32
+}
+
+// The Bézier curve with `points` as its control points, at `t`, in Bernstein
+// form, which unlike de Casteljau's algorithm doesn't need scratch space.
+// The weights are computed in log space, since with many points the
+// binomial coefficients overflow and the powers of `t` underflow.
+fn bezier(points: &[Lab], t: f32) -> Lab {
+    let n = points.len() - 1;
+    if t <= 0.0 {
+        return points[0];
+    } else if t >= 1.0 {
+        return points[n];
+    }
+    let (ln_t, ln_1_t) = (log(t as f64), log(1.0 - t as f64));
+    let mut sum = [0.0f64; 3];
+    // ln(n choose i)
+    let mut ln_binomial = 0.0;
+    for (i, point) in points.iter().enumerate() {
+        let weight = exp(ln_binomial + i as f64 * ln_t + (n - i) as f64 * ln_1_t);
+        sum[0] += weight * point.l as f64;
+        sum[1] += weight * point.a as f64;
+        sum[2] += weight * point.b as f64;
+        if i < n {
+            ln_binomial += log((n - i) as f64) - log((i + 1) as f64);
+        }
+    }
+    Lab {
+        l: sum[0] as f32,
+        a: sum[1] as f32,
+        b: sum[2] as f32,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Easing, Gradient, GradientBuilder};
+    use delta_e::DeltaE;
+    use interpolate::{HueInterpolation, InterpolationSpace};
+    use Lab;
+
+    const EASINGS: [Easing; 4] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+    ];
+
+    fn stops() -> [Lab; 3] {
+        [
+            Lab::from_rgb(&[0, 0, 40]),
+            Lab::from_rgb(&[250, 240, 0]),
+            Lab::from_rgb(&[255, 255, 255]),
+        ]
+    }
+
+    fn samples(gradient: &Gradient, samples: usize) -> Vec<Lab> {
+        let mut labs = vec![Lab::default(); samples];
+        gradient.samples_into(&mut labs);
+        labs
+    }
+
+    // The ΔE between each pair of neighboring samples.
+    fn steps(labs: &[Lab], delta_e: DeltaE) -> Vec<f32> {
+        labs.windows(2)
+            .map(|pair| delta_e.distance(&pair[0], &pair[1]))
+            .collect()
+    }
+
+    fn spread(steps: &[f32]) -> f32 {
+        let max = steps.iter().cloned().fold(0.0, f32::max);
+        let min = steps.iter().cloned().fold(f32::INFINITY, f32::min);
+        max / min
+    }
+
+    #[test]
+    fn test_easing() {
+        for &easing in EASINGS.iter() {
+            assert_eq!(easing.apply(0.0), 0.0, "{:?}", easing);
+            assert_eq!(easing.apply(1.0), 1.0, "{:?}", easing);
+            assert_eq!(easing.apply(-1.0), 0.0, "{:?}", easing);
+            assert_eq!(easing.apply(2.0), 1.0, "{:?}", easing);
+            for i in 0..100 {
+                let (t, next) = (i as f32 / 100.0, (i + 1) as f32 / 100.0);
+                assert!(easing.apply(t) < easing.apply(next), "{:?}", easing);
+            }
+        }
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn test_default_matches_sample_gradient() {
+        let stops = stops();
+        let space = InterpolationSpace::LCh(HueInterpolation::Shorter);
+        let gradient = GradientBuilder::new(&stops).space(space).build();
+        for i in 0..=10 {
+            let t = i as f32 / 10.0;
+            assert_eq!(gradient.sample(t), super::sample_gradient(&stops, space, t));
+        }
+    }
+
+    #[test]
+    fn test_uniform_cie76() {
+        // Uneven stops along a straight line through Lab, so the steps are
+        // even, give or take the table, once reparameterized.
+        let (start, end) = (Lab::from_rgb(&[0, 0, 40]), Lab::from_rgb(&[250, 240, 0]));
+        let stops = [start, start.lerp(&end, 0.1), end];
+        let even = samples(&GradientBuilder::new(&stops).build(), 11);
+        assert!(spread(&steps(&even, DeltaE::Cie76)) > 5.0);
+        let uniform = GradientBuilder::new(&stops).uniform(DeltaE::Cie76).build();
+        let uniform = samples(&uniform, 11);
+        assert!(spread(&steps(&uniform, DeltaE::Cie76)) < 1.001);
+        for (i, lab) in uniform.iter().enumerate() {
+            let expected = start.lerp(&end, i as f32 / 10.0);
+            assert!(lab.squared_distance(&expected) < 1e-3, "{:?}", lab);
+        }
+    }
+
+    #[test]
+    fn test_uniform_easing() {
+        let stops = stops();
+        for &easing in EASINGS.iter() {
+            let gradient = GradientBuilder::new(&stops)
+                .uniform(DeltaE::Cie2000)
+                .easing(easing)
+                .build();
+            assert_eq!(gradient.sample(0.0).to_rgb(), [0, 0, 40], "{:?}", easing);
+            assert_eq!(
+                gradient.sample(1.0).to_rgb(),
+                [255, 255, 255],
+                "{:?}",
+                easing
+            );
+        }
+    }
+
+    #[test]
+    fn test_uniform_cie2000() {
+        // ΔE2000 changes a lot along a straight line from dark blue to yellow.
+        let stops = &stops()[..2];
+        let even = samples(&GradientBuilder::new(stops).build(), 9);
+        let uniform = GradientBuilder::new(stops).uniform(DeltaE::Cie2000).build();
+        let uniform = samples(&uniform, 9);
+        let even_spread = spread(&steps(&even, DeltaE::Cie2000));
+        let uniform_spread = spread(&steps(&uniform, DeltaE::Cie2000));
+        assert!(even_spread > 1.5, "{}", even_spread);
+        // Not exact, since ΔE2000 isn't a distance in Lab, and the steps
+        // between samples are measured in shorter pieces.
+        assert!(
+            uniform_spread < 1.05,
+            "{:?}",
+            steps(&uniform, DeltaE::Cie2000)
+        );
+    }
+
+    #[test]
+    fn test_uniform_single_color() {
+        let stops = [Lab::from_rgb(&[10, 20, 30]); 2];
+        let gradient = GradientBuilder::new(&stops).uniform(DeltaE::Cie76).build();
+        assert_eq!(gradient.sample(0.5), stops[0]);
+        let gradient = GradientBuilder::new(&stops[..1])
+            .uniform(DeltaE::Cie76)
+            .build();
+        assert_eq!(gradient.sample(0.5), stops[0]);
+    }
+
+    #[test]
+    fn test_bezier() {
+        let stops = stops();
+        let gradient = GradientBuilder::new(&stops).bezier().build();
+        assert!(gradient.sample(0.0).squared_distance(&stops[0]) < 1e-6);
+        assert!(gradient.sample(1.0).squared_distance(&stops[2]) < 1e-6);
+        // Pulled towards the middle stop, not through it.
+        let mid = gradient.sample(0.5);
+        let expected = stops[0]
+            .lerp(&stops[1], 0.5)
+            .lerp(&stops[1].lerp(&stops[2], 0.5), 0.5);
+        assert!(mid.squared_distance(&expected) < 1e-6, "{:?}", mid);
+
+        // With two stops it's a line.
+        let gradient = GradientBuilder::new(&stops[..2]).bezier().build();
+        let line = stops[0].lerp(&stops[1], 0.3);
+        assert!(gradient.sample(0.3).squared_distance(&line) < 1e-6);
+    }
+
+    #[test]
+    fn test_bezier_many_stops() {
+        // Evenly spaced control points on a line give a curve along it at
+        // an even pace.
+        let stops: Vec<Lab> = (0..200)
+            .map(|i| Lab {
+                l: i as f32 / 199.0 * 100.0,
+                a: 20.0,
+                b: -10.0,
+            })
+            .collect();
+        let gradient = GradientBuilder::new(&stops).bezier().build();
+        assert_eq!(gradient.sample(0.0), stops[0]);
+        assert_eq!(gradient.sample(1.0), stops[199]);
+        let mid = gradient.sample(0.5);
+        assert!(mid.l.is_finite() && mid.a.is_finite() && mid.b.is_finite());
+        assert!((mid.l - 50.0).abs() < 1e-3, "{:?}", mid);
+        assert!((mid.a - 20.0).abs() < 1e-3, "{:?}", mid);
+
+        let uniform = GradientBuilder::new(&stops)
+            .bezier()
+            .uniform(DeltaE::Cie76)
+            .build();
+        assert!((uniform.sample(0.25).l - 25.0).abs() < 0.1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_into() {
+        let stops = stops();
+        let gradient = GradientBuilder::new(&stops)
+            .uniform(DeltaE::Cie2000)
+            .build();
+        let mut labs = [Lab::default(); 4];
+        gradient.samples_into(&mut labs);
+        assert_eq!(&labs[..], &gradient.samples(4)[..]);
+        let mut rgbs = [[0; 3]; 4];
+        gradient.to_rgbs_into(&mut rgbs);
+        assert_eq!(&rgbs[..], &gradient.to_rgbs(4)[..]);
+    }
+}
@@ -0,0 +1,293 @@
+//! Hue angles, in degrees or radians, with wrap-around arithmetic.
+
+use core::f32::consts::{PI, TAU};
+use math::{atan2f, cosf, hypotf, sinf};
+use {LCh, Lab};
+
+// How close to cancelling out hues have to be for their mean to be undefined.
+const MEAN_EPSILON: f32 = 1e-6;
+
+/// A hue angle
+///
+/// `LCh::h` is in radians, from -π to π as `atan2` gives it, while CSS and
+/// most color pickers give hues in degrees from 0 to 360. A `Hue` can be made
+/// from and turned into either, and wraps around the hue circle, so that
+/// 370° is the same hue as 10° and the difference between 350° and 10° is
+/// 20°.
+///
+/// # Examples
+///
+/// ```
+/// # use lab::Hue;
+/// let hue = Hue::from_degrees(370.0);
+/// assert!((hue.to_degrees() - 10.0).abs() < 0.001);
+/// let difference = Hue::from_degrees(350.0).difference_degrees(hue);
+/// assert!((difference - 20.0).abs() < 0.001);
+/// ```
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct Hue(f32);
+
+// Brings `radians` into -π..=π, excluding -π.
+fn wrap(radians: f32) -> f32 {
+    let h = radians % TAU;
+    if h > PI {
+        h - TAU
+    } else if h <= -PI {
+        h + TAU
+    } else {
+        h
+    }
+}
+
+impl Hue {
+    /// Makes a `Hue` from an angle in radians, any number of turns around
+    pub fn from_radians(radians: f32) -> Hue {
+        Hue(wrap(radians))
+    }
+
+    /// Makes a `Hue` from an angle in degrees, any number of turns around
+    pub fn from_degrees(degrees: f32) -> Hue {
+        Hue::from_radians(degrees.to_radians())
+    }
+
+    /// Returns the hue in radians, from -π to π, as `LCh::h`
+    ///
+    /// -π itself is returned as π.
+    pub fn to_radians(self) -> f32 {
+        self.0
+    }
+
+    /// Returns the hue in degrees, from 0 up to but excluding 360
+    pub fn to_degrees(self) -> f32 {
+        let degrees = self.0.to_degrees();
+        let degrees = if degrees < 0.0 {
+            degrees + 360.0
+        } else {
+            degrees
+        };
+        // A tiny negative angle can round up to a whole turn.
+        if degrees >= 360.0 {
+            0.0
+        } else {
+            degrees
+        }
+    }
+
+    /// Returns the hue turned by `radians`, counterclockwise if positive
+    pub fn rotate_radians(self, radians: f32) -> Hue {
+        Hue::from_radians(self.0 + radians)
+    }
+
+    /// Returns the hue turned by `degrees`, counterclockwise if positive
+    pub fn rotate_degrees(self, degrees: f32) -> Hue {
+        self.rotate_radians(degrees.to_radians())
+    }
+
+    /// Returns the angle in radians to turn the hue by the short way around
+    /// to get to `other`, from -π to π
+    ///
+    /// The result is positive if `other` is counterclockwise from the hue.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lab::Hue;
+    /// # use std::f32::consts::PI;
+    /// let red = Hue::from_radians(0.1);
+    /// let purple = Hue::from_radians(-0.5);
+    /// assert!((red.difference(purple) - -0.6).abs() < 0.0001);
+    /// assert!((purple.difference(red) - 0.6).abs() < 0.0001);
+    /// let cyan = Hue::from_radians(0.1 + PI - 0.2);
+    /// assert!((red.difference(cyan) - (PI - 0.2)).abs() < 0.0001);
+    /// ```
+    pub fn difference(self, other: Hue) -> f32 {
+        wrap(other.0 - self.0)
+    }
+
+    /// Returns the angle in degrees to turn the hue by the short way around
+    /// to get to `other`, from -180 to 180
+    pub fn difference_degrees(self, other: Hue) -> f32 {
+        self.difference(other).to_degrees()
+    }
+
+    /// Returns the circular mean of `hues`, the direction of the sum of unit
+    /// vectors pointing in each hue's direction
+    ///
+    /// Unlike the arithmetic mean of the angles, this gives 0° rather than
+    /// 180° for 350° and 10°. Returns `None` if `hues` is empty, or if the
+    /// hues cancel each other out, as opposite hues do, so that they have no
+    /// mean.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lab::Hue;
+    /// let hues = [Hue::from_degrees(350.0), Hue::from_degrees(10.0)];
+    /// let mean = Hue::mean(&hues).unwrap();
+    /// assert!(mean.to_degrees() < 0.001 || mean.to_degrees() > 359.999);
+    ///
+    /// let opposite = [Hue::from_degrees(90.0), Hue::from_degrees(270.0)];
+    /// assert_eq!(Hue::mean(&opposite), None);
+    /// ```
+    pub fn mean(hues: &[Hue]) -> Option<Hue> {
+        let (mut x, mut y) = (0.0, 0.0);
+        for hue in hues {
+            x += cosf(hue.0);
+            y += sinf(hue.0);
+        }
+        if hypotf(x, y) <= MEAN_EPSILON * hues.len() as f32 {
+            None
+        } else {
+            Some(Hue::from_radians(atan2f(y, x)))
+        }
+    }
+}
+
+impl Lab {
+    /// Returns the chroma of the `Lab`'s color, its distance from the grey
+    /// of the same lightness, as `LCh::c`
+    ///
+    /// # Example
+    /// ```
+    /// # use lab::Lab;
+    /// let lab = Lab { l: 50.0, a: 3.0, b: -4.0 };
+    /// assert_eq!(lab.chroma(), 5.0);
+    /// ```
+    pub fn chroma(&self) -> f32 {
+        hypotf(self.a, self.b)
+    }
+
+    /// Returns the hue of the `Lab`'s color, as `LCh::h`
+    ///
+    /// Greys, which have no chroma, have a hue of 0.
+    ///
+    /// # Example
+    /// ```
+    /// # use lab::Lab;
+    /// let lab = Lab { l: 50.0, a: 0.0, b: 20.0 };
+    /// assert_eq!(lab.hue().to_degrees(), 90.0);
+    /// ```
+    pub fn hue(&self) -> Hue {
+        Hue::from_radians(atan2f(self.b, self.a))
+    }
+}
+
+impl LCh {
+    /// Returns the `LCh`'s hue, brought into -π..π if it's outside
+    ///
+    /// # Example
+    /// ```
+    /// # use lab::LCh;
+    /// let lch = LCh { l: 50.0, c: 20.0, h: -std::f32::consts::FRAC_PI_2 };
+    /// assert_eq!(lch.hue().to_degrees(), 270.0);
+    /// ```
+    pub fn hue(&self) -> Hue {
+        Hue::from_radians(self.h)
+    }
+
+    /// Returns the `LCh` with its hue replaced by `hue`
+    ///
+    /// # Example
+    /// ```
+    /// # use lab::{Hue, LCh};
+    /// let red = LCh::from_rgb(&[255, 0, 0]);
+    /// let complement = red.with_hue(red.hue().rotate_degrees(180.0));
+    /// assert_eq!((complement.l, complement.c), (red.l, red.c));
+    /// ```
+    pub fn with_hue(&self, hue: Hue) -> LCh {
+        LCh {
+            h: hue.to_radians(),
+            ..*self
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Hue;
+    use core::f32::consts::PI;
+    use {LCh, Lab};
+
+    #[test]
+    fn test_degrees_and_radians() {
+        for &(degrees, radians) in &[
+            (0.0, 0.0),
+            (90.0, PI / 2.0),
+            (180.0, PI),
+            (270.0, -PI / 2.0),
+            (-90.0, -PI / 2.0),
+            (720.0 + 45.0, PI / 4.0),
+        ] {
+            let hue = Hue::from_degrees(degrees);
+            assert!((hue.to_radians() - radians).abs() < 0.0001, "{}", degrees);
+            let hue = Hue::from_radians(radians);
+            let expected = if degrees < 0.0 {
+                degrees + 360.0
+            } else {
+                degrees % 360.0
+            };
+            assert!((hue.to_degrees() - expected).abs() < 0.001, "{}", degrees);
+        }
+        assert_eq!(Hue::from_radians(-PI).to_radians(), PI);
+        assert_eq!(Hue::from_radians(-1e-9).to_degrees(), 0.0);
+    }
+
+    #[test]
+    fn test_difference() {
+        let eps = 0.001;
+        let hue = |degrees| Hue::from_degrees(degrees);
+        assert!((hue(350.0).difference_degrees(hue(10.0)) - 20.0).abs() < eps);
+        assert!((hue(10.0).difference_degrees(hue(350.0)) + 20.0).abs() < eps);
+        assert!((hue(30.0).difference_degrees(hue(200.0)) - 170.0).abs() < eps);
+        assert!((hue(30.0).difference_degrees(hue(220.0)) + 170.0).abs() < eps);
+        assert_eq!(hue(123.0).difference(hue(123.0)), 0.0);
+        for i in 0..36 {
+            let (a, b) = (hue(i as f32 * 10.0), hue(i as f32 * 37.0));
+            let d = a.difference(b);
+            assert!(d.abs() <= PI);
+            assert!((a.rotate_radians(d).difference(b)).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_mean() {
+        let hue = |degrees| Hue::from_degrees(degrees);
+        assert_eq!(Hue::mean(&[]), None);
+        let mean = Hue::mean(&[hue(20.0), hue(40.0), hue(60.0)]).unwrap();
+        assert!((mean.to_degrees() - 40.0).abs() < 0.001);
+        let mean = Hue::mean(&[hue(340.0), hue(350.0), hue(10.0), hue(20.0)]).unwrap();
+        assert!(
+            mean.difference_degrees(hue(0.0)).abs() < 0.001,
+            "{:?}",
+            mean
+        );
+        assert_eq!(Hue::mean(&[hue(0.0), hue(120.0), hue(240.0)]), None);
+    }
+
+    #[test]
+    fn test_lab_and_lch() {
+        for &rgb in &[[240, 33, 95], [0, 0, 255], [12, 200, 40], [128, 128, 128]] {
+            let lab = Lab::from_rgb(&rgb);
+            let lch = LCh::from_lab(lab);
+            assert_eq!(lab.chroma(), lch.c);
+            assert_eq!(lab.hue().to_radians(), lch.h);
+            assert_eq!(lch.hue(), lab.hue());
+            assert_eq!(lch.with_hue(lch.hue()), lch);
+        }
+        let lch = LCh {
+            l: 50.0,
+            c: 10.0,
+            h: 3.0 * PI,
+        };
+        assert!((lch.hue().to_radians() - PI).abs() < 0.0001);
+
+        // atan2 gives -π for a negative zero b.
+        let lab = Lab {
+            l: 50.0,
+            a: -10.0,
+            b: -0.0,
+        };
+        assert_eq!(lab.hue().to_radians(), PI);
+        assert_eq!(lab.hue(), Hue::from_radians(-PI));
+    }
+}
@@ -0,0 +1,335 @@
+//! Interpolating between colors, and sampling gradients.
+
+use core::f32::consts::{PI, TAU};
+use {LCh, Lab};
+
+/// Which way around the hue circle to interpolate between two hues
+///
+/// These are the hue interpolation methods of
+/// [CSS Color 4](https://www.w3.org/TR/css-color-4/#hue-interpolation).
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub enum HueInterpolation {
+    /// Takes the shorter of the two arcs between the hues
+    #[default]
+    Shorter,
+    /// Takes the longer of the two arcs between the hues
+    Longer,
+    /// Goes counterclockwise, towards increasing hue
+    Increasing,
+    /// Goes clockwise, towards decreasing hue
+    Decreasing,
+}
+
+/// The color space to interpolate between colors in
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum InterpolationSpace {
+    /// Interpolates L\*, a\* and b\* linearly, going in a straight line
+    /// through Lab space
+    Lab,
+    /// Interpolates lightness, chroma and hue linearly, going around the hue
+    /// circle in the given direction
+    LCh(HueInterpolation),
+}
+
+// Brings `h` into 0..2π.
+fn normalize(h: f32) -> f32 {
+    let h = h % TAU;
+    if h < 0.0 {
+        h + TAU
+    } else {
+        h
+    }
+}
+
+impl Lab {
+    /// Interpolates linearly between the `Lab` and an `other`
+    ///
+    /// `t` of 0 gives the `Lab` back and 1 gives `other`. Values outside of
+    /// 0..1 extrapolate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lab::Lab;
+    /// let black = Lab { l: 0.0, a: 0.0, b: 0.0 };
+    /// let white = Lab { l: 100.0, a: 0.0, b: 0.0 };
+    /// assert_eq!(black.lerp(&white, 0.25), Lab { l: 25.0, a: 0.0, b: 0.0 });
+    /// ```
+    pub fn lerp(&self, other: &Lab, t: f32) -> Lab {
+        Lab {
+            l: self.l + (other.l - self.l) * t,
+            a: self.a + (other.a - self.a) * t,
+            b: self.b + (other.b - self.b) * t,
+        }
+    }
+}
+
+impl LCh {
+    /// Interpolates linearly between the `LCh` and an `other`, going around
+    /// the hue circle as `hue` says
+    ///
+    /// `t` of 0 gives the `LCh` back and 1 gives `other`, though possibly
+    /// with its hue a turn away. Values outside of 0..1 extrapolate.
+    ///
+    /// As in CSS, the hue of a grey, which has no chroma, doesn't count: the
+    /// other color's hue is used for both, so that the gradient from a grey
+    /// to a color only changes in lightness and chroma.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use lab::{HueInterpolation, LCh};
+    /// # use std::f32::consts::PI;
+    /// let red = LCh { l: 50.0, c: 60.0, h: 0.1 };
+    /// let purple = LCh { l: 50.0, c: 60.0, h: -0.5 };
+    /// let mid = red.lerp(&purple, 0.5, HueInterpolation::Shorter);
+    /// assert!((mid.h - (-0.2)).abs() < 0.0001);
+    /// let mid = red.lerp(&purple, 0.5, HueInterpolation::Longer);
+    /// assert!((mid.h - (PI - 0.2)).abs() < 0.0001);
+    /// ```
+    pub fn lerp(&self, other: &LCh, t: f32, hue: HueInterpolation) -> LCh {
+        let h = if self.c == 0.0 {
+            normalize(other.h)
+        } else if other.c == 0.0 {
+            normalize(self.h)
+        } else {
+            let (mut h1, mut h2) = (normalize(self.h), normalize(other.h));
+            let delta = h2 - h1;
+            match hue {
+                HueInterpolation::Shorter if delta > PI => h1 += TAU,
+                HueInterpolation::Shorter if delta < -PI => h2 += TAU,
+                HueInterpolation::Longer if 0.0 < delta && delta < PI => h1 += TAU,
+                HueInterpolation::Longer if -PI < delta && delta <= 0.0 => h2 += TAU,
+                HueInterpolation::Increasing if h2 < h1 => h2 += TAU,
+                HueInterpolation::Decreasing if h1 < h2 => h1 += TAU,
+                _ => {}
+            }
+            normalize(h1 + (h2 - h1) * t)
+        };
+        LCh {
+            l: self.l + (other.l - self.l) * t,
+            c: self.c + (other.c - self.c) * t,
+            h: if h > PI { h - TAU } else { h },
+        }
+    }
+}
+
+/// Returns the color at `t` along a gradient through `stops`
+///
+/// The stops are spaced evenly, the first at 0 and the last at 1, and
+/// neighboring stops are interpolated in `space`. `t` is clamped to 0..1.
+///
+/// # Panics
+///
+/// Panics if `stops` is empty.
+///
+/// # Example
+/// ```
+/// # use lab::{sample_gradient, InterpolationSpace, Lab};
+/// let stops = [
+///     Lab { l: 0.0, a: 0.0, b: 0.0 },
+///     Lab { l: 50.0, a: 20.0, b: 0.0 },
+///     Lab { l: 100.0, a: 0.0, b: 0.0 },
+/// ];
+/// let lab = sample_gradient(&stops, InterpolationSpace::Lab, 0.75);
+/// assert_eq!(lab, Lab { l: 75.0, a: 10.0, b: 0.0 });
+/// ```
+pub fn sample_gradient(stops: &[Lab], space: InterpolationSpace, t: f32) -> Lab {
+    assert!(!stops.is_empty(), "a gradient needs at least one stop");
+    let segments = stops.len() - 1;
+    if segments == 0 {
+        return stops[0];
+    }
+    let position = t.clamp(0.0, 1.0) * segments as f32;
+    let i = (position as usize).min(segments - 1);
+    let t = position - i as f32;
+    match space {
+        InterpolationSpace::Lab => stops[i].lerp(&stops[i + 1], t),
+        InterpolationSpace::LCh(hue) => LCh::from_lab(stops[i])
+            .lerp(&LCh::from_lab(stops[i + 1]), t, hue)
+            .to_lab(),
+    }
+}
+
+/// Samples a gradient through `stops` at `samples` evenly spaced points,
+/// from the first stop to the last, and converts them to RGB
+///
+/// See [`sample_gradient`](fn.sample_gradient.html) for how the gradient is
+/// interpolated.
+///
+/// # Panics
+///
+/// Panics if `stops` is empty.
+///
+/// # Example
+/// ```
+/// # use lab::{gradient, HueInterpolation, InterpolationSpace, Lab};
+/// let stops = [Lab::from_rgb(&[255, 0, 0]), Lab::from_rgb(&[0, 0, 255])];
+/// let rgbs = gradient(&stops, InterpolationSpace::LCh(HueInterpolation::Shorter), 5);
+/// assert_eq!(rgbs.len(), 5);
+/// assert_eq!(rgbs[0], [255, 0, 0]);
+/// assert_eq!(rgbs[4], [0, 0, 255]);
+/// ```
+#[cfg(feature = "std")]
+pub fn gradient(stops: &[Lab], space: InterpolationSpace, samples: usize) -> Vec<[u8; 3]> {
+    let mut rgbs = vec![[0; 3]; samples];
+    gradient_into(stops, space, &mut rgbs);
+    rgbs
+}
+
+/// Samples a gradient through `stops` at evenly spaced points, one for each
+/// of `rgbs`, as [`gradient`](fn.gradient.html)
+///
+/// # Panics
+///
+/// Panics if `stops` is empty.
+pub fn gradient_into(stops: &[Lab], space: InterpolationSpace, rgbs: &mut [[u8; 3]]) {
+    let last = rgbs.len().saturating_sub(1).max(1) as f32;
+    for (i, rgb) in rgbs.iter_mut().enumerate() {
+        *rgb = sample_gradient(stops, space, i as f32 / last).to_rgb();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{gradient_into, sample_gradient, HueInterpolation, InterpolationSpace};
+    use core::f32::consts::PI;
+    use {LCh, Lab};
+
+    fn lch(h: f32) -> LCh {
+        LCh {
+            l: 50.0,
+            c: 40.0,
+            h,
+        }
+    }
+
+    fn hue_at(h1: f32, h2: f32, hue: HueInterpolation) -> f32 {
+        lch(h1).lerp(&lch(h2), 0.5, hue).h
+    }
+
+    #[test]
+    fn test_lab_lerp() {
+        let a = Lab {
+            l: 20.0,
+            a: -10.0,
+            b: 30.0,
+        };
+        let b = Lab {
+            l: 60.0,
+            a: 30.0,
+            b: -10.0,
+        };
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(
+            a.lerp(&b, 0.5),
+            Lab {
+                l: 40.0,
+                a: 10.0,
+                b: 10.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_hue_interpolation() {
+        // From 20° to 340°, and back.
+        let (h1, h2) = (PI / 9.0, -PI / 9.0);
+        let eps = 0.0001;
+        assert!(hue_at(h1, h2, HueInterpolation::Shorter).abs() < eps);
+        assert!((hue_at(h1, h2, HueInterpolation::Longer) - PI).abs() < eps);
+        assert!((hue_at(h1, h2, HueInterpolation::Increasing) - PI).abs() < eps);
+        assert!(hue_at(h1, h2, HueInterpolation::Decreasing).abs() < eps);
+
+        assert!(hue_at(h2, h1, HueInterpolation::Shorter).abs() < eps);
+        assert!((hue_at(h2, h1, HueInterpolation::Longer) - PI).abs() < eps);
+        assert!(hue_at(h2, h1, HueInterpolation::Increasing).abs() < eps);
+        assert!((hue_at(h2, h1, HueInterpolation::Decreasing) - PI).abs() < eps);
+    }
+
+    #[test]
+    fn test_lch_lerp_ends() {
+        let a = lch(3.0);
+        let b = LCh {
+            l: 70.0,
+            c: 10.0,
+            h: -2.0,
+        };
+        for &hue in &[
+            HueInterpolation::Shorter,
+            HueInterpolation::Longer,
+            HueInterpolation::Increasing,
+            HueInterpolation::Decreasing,
+        ] {
+            let start = a.lerp(&b, 0.0, hue).to_lab();
+            let end = a.lerp(&b, 1.0, hue).to_lab();
+            assert!(start.squared_distance(&a.to_lab()) < 1e-6, "{:?}", hue);
+            assert!(end.squared_distance(&b.to_lab()) < 1e-6, "{:?}", hue);
+            let mid = a.lerp(&b, 0.5, hue);
+            assert_eq!((mid.l, mid.c), (60.0, 25.0), "{:?}", hue);
+        }
+    }
+
+    #[test]
+    fn test_grey_has_no_hue() {
+        let grey = LCh {
+            l: 50.0,
+            c: 0.0,
+            h: 0.0,
+        };
+        let blue = LCh::from_rgb(&[0, 0, 255]);
+        for &t in &[0.25, 0.5, 0.75] {
+            let lch = grey.lerp(&blue, t, HueInterpolation::Longer);
+            assert!((lch.h - blue.h).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn test_sample_gradient() {
+        let stops = [
+            Lab::from_rgb(&[255, 0, 0]),
+            Lab::from_rgb(&[0, 255, 0]),
+            Lab::from_rgb(&[0, 0, 255]),
+        ];
+        for &space in &[
+            InterpolationSpace::Lab,
+            InterpolationSpace::LCh(HueInterpolation::Shorter),
+        ] {
+            assert_eq!(sample_gradient(&stops, space, 0.0).to_rgb(), [255, 0, 0]);
+            assert_eq!(sample_gradient(&stops, space, 0.5).to_rgb(), [0, 255, 0]);
+            assert_eq!(sample_gradient(&stops, space, 1.0).to_rgb(), [0, 0, 255]);
+            assert_eq!(sample_gradient(&stops, space, 2.0).to_rgb(), [0, 0, 255]);
+        }
+        assert_eq!(
+            sample_gradient(&stops, InterpolationSpace::Lab, 0.25),
+            stops[0].lerp(&stops[1], 0.5)
+        );
+        assert_eq!(
+            sample_gradient(&stops[..1], InterpolationSpace::Lab, 0.5),
+            stops[0]
+        );
+    }
+
+    #[test]
+    fn test_gradient_into() {
+        let stops = [Lab::from_rgb(&[0, 0, 0]), Lab::from_rgb(&[255, 255, 255])];
+        let mut rgbs = [[0; 3]; 3];
+        gradient_into(&stops, InterpolationSpace::Lab, &mut rgbs);
+        assert_eq!(rgbs[0], [0, 0, 0]);
+        assert_eq!(
+            rgbs[1],
+            Lab {
+                l: 50.0,
+                a: 0.0,
+                b: 0.0
+            }
+            .to_rgb()
+        );
+        assert_eq!(rgbs[2], [255, 255, 255]);
+
+        let mut one = [[0; 3]; 1];
+        gradient_into(&stops, InterpolationSpace::Lab, &mut one);
+        assert_eq!(one[0], [0, 0, 0]);
+    }
+}
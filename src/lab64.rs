@@ -0,0 +1,435 @@
+//! Double precision versions of `Lab` and `LCh`.
+//!
+//! `Lab64` and `LCh64` are computed entirely in `f64`, including the
+//! conversion constants, for uses like instrument calibration where the
+//! roughly seven significant digits of `f32` aren't enough. They're meant for
+//! single values; the batch conversions and their SIMD kernels only work with
+//! `Lab`.
+
+use math::{atan2, cbrt, cos, hypot, pow, round, sin};
+use {clip64, LCh, Lab};
+
+// κ and ε parameters used in conversion between XYZ and La*b*. See the f32
+// versions in lib.rs.
+const KAPPA: f64 = 24389.0 / 27.0;
+const EPSILON: f64 = 216.0 / 24389.0;
+const CBRT_EPSILON: f64 = 6.0 / 29.0;
+
+// S₀ and E₀ thresholds used in sRGB gamma. The latter is scaled for encoded
+// value in the range 0..255.
+const S_0: f64 = 0.003130668442500564;
+const E_0_255: f64 = 3294.6 * S_0;
+
+// Generated by srgb-matrices.py
+const WHITE_X: f64 = 0.9504492182750991;
+const WHITE_Z: f64 = 1.0889166484304715;
+
+/// Struct representing a color in CIALab, a.k.a. L\*a\*b\*, color space, with
+/// `f64` components
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct Lab64 {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+/// Struct representing a color in cylindrical CIELCh color space, with `f64`
+/// components
+#[derive(Debug, PartialEq, Copy, Clone, Default)]
+pub struct LCh64 {
+    pub l: f64,
+    pub c: f64,
+    pub h: f64,
+}
+
+#[inline(always)]
+fn mul3(a0: f64, a1: f64, a2: f64, b0: f64, b1: f64, b2: f64) -> f64 {
+    a0 * b0 + a1 * b1 + a2 * b2
+}
+
+fn rgb_to_xyz(r: f64, g: f64, b: f64) -> [f64; 3] {
+    #[inline]
+    fn rgb_to_xyz_map(c: f64) -> f64 {
+        if c > E_0_255 {
+            const A: f64 = 0.055 * 255.0;
+            const D: f64 = 1.055 * 255.0;
+            pow((c + A) / D, 2.4)
+        } else {
+            const D: f64 = 12.92 * 255.0;
+            c / D
+        }
+    }
+
+    let r = rgb_to_xyz_map(r);
+    let g = rgb_to_xyz_map(g);
+    let b = rgb_to_xyz_map(b);
+
+    // Generated by srgb-matrices.py
+    let x = mul3(
+        r,
+        g,
+        b,
+        0.4124108464885388,
+        0.3575845678529519,
+        0.18045380393360833,
+    );
+    let y = mul3(
+        r,
+        g,
+        b,
+        0.21264934272065283,
+        0.7151691357059038,
+        0.07218152157344333,
+    );
+    let z = mul3(
+        r,
+        g,
+        b,
+        0.019331758429150258,
+        0.11919485595098397,
+        0.9503900340503373,
+    );
+
+    [x, y, z]
+}
+
+fn xyz_to_lab(xyz: [f64; 3]) -> Lab64 {
+    #[inline]
+    fn xyz_to_lab_map(c: f64) -> f64 {
+        if c > EPSILON {
+            cbrt(c)
+        } else {
+            (KAPPA * c + 16.0) / 116.0
+        }
+    }
+
+    let x = xyz_to_lab_map(xyz[0] / WHITE_X);
+    let y = xyz_to_lab_map(xyz[1]);
+    let z = xyz_to_lab_map(xyz[2] / WHITE_Z);
+
+    Lab64 {
+        l: (116.0 * y) - 16.0,
+        a: 500.0 * (x - y),
+        b: 200.0 * (y - z),
+    }
+}
+
+fn lab_to_xyz(lab: &Lab64) -> [f64; 3] {
+    let fy = (lab.l + 16.0) / 116.0;
+    let fx = (lab.a / 500.0) + fy;
+    let fz = fy - (lab.b / 200.0);
+    let xr = if fx > CBRT_EPSILON {
+        fx * fx * fx
+    } else {
+        ((fx * 116.0) - 16.0) / KAPPA
+    };
+    let yr = if lab.l > EPSILON * KAPPA {
+        fy * fy * fy
+    } else {
+        lab.l / KAPPA
+    };
+    let zr = if fz > CBRT_EPSILON {
+        fz * fz * fz
+    } else {
+        ((fz * 116.0) - 16.0) / KAPPA
+    };
+
+    [xr * WHITE_X, yr, zr * WHITE_Z]
+}
+
+fn xyz_to_rgb_normalized(xyz: [f64; 3]) -> [f64; 3] {
+    let x = xyz[0];
+    let y = xyz[1];
+    let z = xyz[2];
+
+    // Generated by srgb-matrices.py
+    let r = mul3(
+        x,
+        y,
+        z,
+        3.240812398895283,
+        -1.5373084456298136,
+        -0.4985865229069666,
+    );
+    let g = mul3(
+        x,
+        y,
+        z,
+        -0.9692430170086407,
+        1.8759663029085742,
+        0.04155503085668564,
+    );
+    let b = mul3(
+        x,
+        y,
+        z,
+        0.055638398436112804,
+        -0.20400746093241362,
+        1.0571295702861434,
+    );
+
+    #[inline]
+    fn xyz_to_rgb_map(c: f64) -> f64 {
+        clip64(if c > S_0 {
+            1.055 * pow(c, 1.0 / 2.4) - 0.055
+        } else {
+            12.92 * c
+        })
+    }
+
+    [xyz_to_rgb_map(r), xyz_to_rgb_map(g), xyz_to_rgb_map(b)]
+}
+
+impl Lab64 {
+    /// Constructs a new `Lab64` from a three-element array of `u8`s
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let lab = lab::Lab64::from_rgb(&[240, 33, 95]);
+    /// assert_eq!(lab::Lab64 { l: 52.33469322956179, a: 75.55156625865966, b: 19.995689130357853 }, lab);
+    /// ```
+    pub fn from_rgb(rgb: &[u8; 3]) -> Self {
+        xyz_to_lab(rgb_to_xyz(rgb[0] as f64, rgb[1] as f64, rgb[2] as f64))
+    }
+
+    /// Constructs a new `Lab64` from a three-element array of RGB components
+    /// in the range 0.0 to 1.0
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let lab = lab::Lab64::from_rgb_normalized(&[240.0 / 255.0, 33.0 / 255.0, 95.0 / 255.0]);
+    /// assert_eq!(lab::Lab64::from_rgb(&[240, 33, 95]), lab);
+    /// ```
+    pub fn from_rgb_normalized(rgb: &[f64; 3]) -> Self {
+        xyz_to_lab(rgb_to_xyz(rgb[0] * 255.0, rgb[1] * 255.0, rgb[2] * 255.0))
+    }
+
+    /// Constructs a new `Lab64` from a four-element array of `u8`s
+    ///
+    /// The `Lab64` struct does not store alpha channel information, so the
+    /// last `u8` representing alpha is discarded.
+    pub fn from_rgba(rgba: &[u8; 4]) -> Self {
+        Lab64::from_rgb(&[rgba[0], rgba[1], rgba[2]])
+    }
+
+    /// Returns the `Lab64`'s color in RGB, in a 3-element array.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let lab = lab::Lab64 { l: 52.33469322956179, a: 75.55156625865966, b: 19.995689130357853 };
+    /// assert_eq!([240, 33, 95], lab.to_rgb());
+    /// ```
+    pub fn to_rgb(&self) -> [u8; 3] {
+        let rgb = self.to_rgb_normalized();
+        [
+            round(rgb[0] * 255.0) as u8,
+            round(rgb[1] * 255.0) as u8,
+            round(rgb[2] * 255.0) as u8,
+        ]
+    }
+
+    /// Returns the `Lab64`'s color in RGB, with components in the range 0.0
+    /// to 1.0
+    ///
+    /// Colors outside of the sRGB gamut are clamped to it.
+    pub fn to_rgb_normalized(&self) -> [f64; 3] {
+        xyz_to_rgb_normalized(lab_to_xyz(self))
+    }
+
+    /// Measures the perceptual distance between the colors of one `Lab64`
+    /// and an `other`.
+    pub fn squared_distance(&self, other: &Lab64) -> f64 {
+        let (dl, da, db) = (self.l - other.l, self.a - other.a, self.b - other.b);
+        dl * dl + da * da + db * db
+    }
+}
+
+impl From<Lab> for Lab64 {
+    fn from(lab: Lab) -> Self {
+        Lab64 {
+            l: lab.l as f64,
+            a: lab.a as f64,
+            b: lab.b as f64,
+        }
+    }
+}
+
+/// Rounds each component to the nearest `f32`
+impl From<Lab64> for Lab {
+    fn from(lab: Lab64) -> Self {
+        Lab {
+            l: lab.l as f32,
+            a: lab.a as f32,
+            b: lab.b as f32,
+        }
+    }
+}
+
+impl LCh64 {
+    /// Constructs a new `LCh64` from a three-element array of `u8`s
+    pub fn from_rgb(rgb: &[u8; 3]) -> Self {
+        LCh64::from_lab(Lab64::from_rgb(rgb))
+    }
+
+    /// Constructs a new `LCh64` from a four-element array of `u8`s
+    ///
+    /// The `LCh64` struct does not store alpha channel information, so the
+    /// last `u8` representing alpha is discarded.
+    pub fn from_rgba(rgba: &[u8; 4]) -> Self {
+        LCh64::from_lab(Lab64::from_rgba(rgba))
+    }
+
+    /// Constructs a new `LCh64` from a `Lab64`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let lab = lab::Lab64 { l: 52.33686, a: 0.0, b: 0.0 };
+    /// let lch = lab::LCh64::from_lab(lab);
+    /// assert_eq!(lab::LCh64 { l: 52.33686, c: 0.0, h: 0.0 }, lch);
+    /// ```
+    pub fn from_lab(lab: Lab64) -> Self {
+        LCh64 {
+            l: lab.l,
+            c: hypot(lab.a, lab.b),
+            h: atan2(lab.b, lab.a),
+        }
+    }
+
+    /// Returns the `LCh64`'s color in RGB, in a 3-element array
+    pub fn to_rgb(&self) -> [u8; 3] {
+        self.to_lab().to_rgb()
+    }
+
+    /// Returns the `LCh64`'s color in `Lab64`
+    pub fn to_lab(&self) -> Lab64 {
+        Lab64 {
+            l: self.l,
+            a: self.c * cos(self.h),
+            b: self.c * sin(self.h),
+        }
+    }
+}
+
+impl From<LCh> for LCh64 {
+    fn from(lch: LCh) -> Self {
+        LCh64 {
+            l: lch.l as f64,
+            c: lch.c as f64,
+            h: lch.h as f64,
+        }
+    }
+}
+
+/// Rounds each component to the nearest `f32`
+impl From<LCh64> for LCh {
+    fn from(lch: LCh64) -> Self {
+        LCh {
+            l: lch.l as f32,
+            c: lch.c as f32,
+            h: lch.h as f32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LCh64, Lab64};
+    use approx::assert_relative_eq;
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+    use {LCh, Lab};
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    #[test]
+    fn test_lab64_matches_lab() {
+        for rgb in RGBS.iter() {
+            let lab = Lab::from(Lab64::from_rgb(rgb));
+            assert_relative_eq!(
+                lab,
+                Lab::from_rgb(rgb),
+                epsilon = 0.0001,
+                max_relative = 0.00005
+            );
+        }
+    }
+
+    #[test]
+    fn test_lch64_matches_lch() {
+        for rgb in RGBS.iter() {
+            let lch = LCh::from(LCh64::from_rgb(rgb));
+            assert_relative_eq!(
+                lch,
+                LCh::from_rgb(rgb),
+                epsilon = 0.0001,
+                max_relative = 0.00005
+            );
+        }
+    }
+
+    #[test]
+    fn test_lab64_to_rgb() {
+        for rgb in RGBS.iter() {
+            assert_eq!(Lab64::from_rgb(rgb).to_rgb(), *rgb);
+            assert_eq!(LCh64::from_rgb(rgb).to_rgb(), *rgb);
+        }
+    }
+
+    #[test]
+    fn test_lab64_normalized_round_trip() {
+        for rgb in RGBS.iter() {
+            let normalized = [
+                rgb[0] as f64 / 255.0,
+                rgb[1] as f64 / 255.0,
+                rgb[2] as f64 / 255.0,
+            ];
+            let back = Lab64::from_rgb_normalized(&normalized).to_rgb_normalized();
+            for i in 0..3 {
+                assert_relative_eq!(back[i], normalized[i], epsilon = 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lch64_round_trip() {
+        for rgb in RGBS.iter() {
+            let lab = Lab64::from_rgb(rgb);
+            let back = LCh64::from_lab(lab).to_lab();
+            assert_relative_eq!(back.l, lab.l);
+            assert_relative_eq!(back.a, lab.a, epsilon = 1e-12);
+            assert_relative_eq!(back.b, lab.b, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_grey_error() {
+        // As in the f32 test, greys should have a* and b* of zero. In f64 the
+        // error is some seven orders of magnitude smaller.
+        let mut error: f64 = 0.0;
+        for i in 0..=255_u8 {
+            let lab = Lab64::from_rgb(&[i, i, i]);
+            error += lab.a * lab.a + lab.b * lab.b;
+        }
+        assert!(error < 1e-20, "{}", error);
+    }
+
+    #[test]
+    fn test_white() {
+        let white = Lab64::from_rgb(&[255, 255, 255]);
+        assert_relative_eq!(white.l, 100.0, epsilon = 1e-12);
+        assert_relative_eq!(white.a, 0.0, epsilon = 1e-12);
+        assert_relative_eq!(white.b, 0.0, epsilon = 1e-12);
+    }
+}
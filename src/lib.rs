@@ -1,4 +1,10 @@
 #![doc(html_root_url = "https://docs.rs/lab")]
+// Matrix coefficients and white point are generated at f64 precision by
+// srgb-matrices.py and are deliberately kept verbatim.
+#![allow(clippy::excessive_precision)]
+#![cfg_attr(feature = "portable_simd", feature(portable_simd))]
+// Tests always link std, which they use freely.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
 /*!
 
@@ -10,6 +16,9 @@ RGB colors, for this crate at least, are considered to be composed of `u8`
 values from 0 to 255, while L\*a\*b\* colors are represented by its own struct
 that uses `f32` values.
 
+`Lab64` and `LCh64` are `f64` versions of `Lab` and `LCh` for when `f32`
+isn't precise enough. They only convert single values.
+
 # Usage
 
 ## Converting single values
@@ -73,14 +82,121 @@ let rgbs = vec![
 let labs = rgb_bytes_to_labs(&rgbs);
 ```
 
-These functions will use x86_64 AVX2 instructions if compiled to a supported target.
+On x86_64 these functions will use AVX-512F or AVX2 instructions when the CPU
+they run on supports them, and SSE4.1 or SSE2 instructions otherwise. Support is
+detected at runtime, so binaries built for a generic x86_64 target still get the
+fast path. On AArch64 they use NEON instructions. On wasm32 they use SIMD128
+instructions if the crate is compiled with `-C target-feature=+simd128`, which
+WebAssembly has no way to detect at runtime.
+
+On other targets, enabling the `portable_simd` feature vectorizes them with
+[`std::simd`](https://doc.rust-lang.org/nightly/std/simd/index.html). The
+feature requires a nightly compiler.
+
+With the `rayon` feature enabled, `par_rgbs_to_labs`, `par_rgb_bytes_to_labs`,
+`par_labs_to_rgbs` and `par_labs_to_rgb_bytes` split the work across rayon's
+thread pool. Their output is identical to that of the serial functions.
+
+`RgbToLabLut` converts `u8` colors with lookup tables instead. `LutSize::Exact`
+gives the same results as `Lab::from_rgb`; the other sizes interpolate between
+precomputed colors, trading accuracy for speed.
+
+For previews and other uses where speed matters more than accuracy,
+`fast_rgbs_to_labs`, `fast_labs_to_rgbs` and their byte slice counterparts
+approximate the conversions with polynomials, to a bounded error picked with
+`Precision`.
+
+`reproducible_rgbs_to_labs`, `reproducible_labs_to_rgbs` and their byte slice
+counterparts give bit for bit the same results on every platform and CPU, for
+uses like golden image tests that compare results across machines.
+
+`Lab::to_rgb_checked` and `Lab::is_in_gamut` tell whether a color is inside of
+the sRGB gamut, which `to_rgb` silently clips colors to, and `gamut_mask` checks
+a whole slice.
+
+`Lab::map_to_gamut` and `LCh::map_to_gamut` bring colors into the gamut in
+other ways than clipping, picked with `GamutMapping`: reducing chroma at
+constant lightness and hue, the CSS Color 4 algorithm, or moving towards the
+cusp of the color's hue. `to_rgb_mapped` converts the result to RGB.
+
+`max_chroma` gives the highest chroma in sRGB for a lightness and hue, `cusp`
+the most saturated color of a hue, and `gamut_boundary` samples the whole
+boundary of the gamut as a grid of `LCh` colors.
+
+`Lab::lerp` and `LCh::lerp` interpolate between two colors, the latter going
+around the hue circle in one of CSS Color 4's `HueInterpolation` directions.
+`gradient` samples a gradient through any number of evenly spaced stops and
+returns RGB colors.
+
+`Lab::delta_e_2000` measures the difference between two colors with the
+CIEDE2000 formula. `GradientBuilder` builds a `Gradient` with `Easing`, or as a
+Bézier curve with its stops as control points, and can space its samples
+evenly by `DeltaE` instead of evenly between the stops, so that every step
+looks as big.
+
+`Hue` handles hue angles in degrees or radians, and wraps around the hue
+circle for differences between hues and their mean. `Lab::chroma`,
+`Lab::hue` and `LCh::hue` return a color's chroma and hue, and
+`LCh::with_hue` replaces its hue.
+
+`Palette` finds the closest colors to a color out of a set of colors, by CIE76
+or ΔE2000, with a k-d tree instead of comparing every one, and maps whole
+slices of `Lab` colors or RGB bytes to palette indices.
+
+`quantize` picks a palette of a few colors for an image, clustering its colors
+in Lab with k-means++, median cut or Wu's criterion picked with `Quantizer`,
+and maps each pixel to a palette color.
+
+`dither` maps an image to a `Palette` with Floyd–Steinberg, Atkinson or Sierra
+error diffusion in Lab or linear RGB, or with ordered Bayer dithering, to avoid
+the banding of mapping each pixel to its closest palette color.
+
+`DominantColors` finds the main colors of an image and how much of it each
+covers, merging colors within a ΔE of each other, optionally ranking vivid
+colors higher and ignoring near-white and near-black backgrounds.
+
+`LabStats` summarizes an image's colors with the mean, standard deviation,
+percentiles and range of L\*, a\*, b\* and chroma, a chroma-weighted circular
+mean hue, and a hue histogram; `LabHistogram` counts colors in a 3D grid over
+Lab.
+
+`ImageDiff` compares two images by the ΔE76 or ΔE2000 of each pair of
+pixels, with their mean, maximum and percentiles, the number of pixels over a
+threshold, and a heatmap image of where they differ.
+
+`scielab` converts an image to Lab as it is seen at a given number of pixels
+per degree of the field of view, blurring detail too fine to see as S-CIELAB
+does, and `ImageDiff::scielab` compares images that way, so that a halftone
+matches its average color.
+
+## Without std
+
+The crate has a default `std` feature. Built with `default-features = false`
+and the `libm` feature it's `no_std` and uses
+[libm](https://crates.io/crates/libm) for floating point math, so results can
+differ from those with `std` in the last bits. Only the single value
+conversions and `rgbs_to_labs_into`, `rgb_bytes_to_labs_into`,
+`labs_to_rgbs_into` and `labs_to_rgb_bytes_into`, which write to a slice
+instead of allocating, are available then.
 
 ## Minimum Rust version
 
+The unreleased version of Lab requires Rust >= 1.89.0 for the [AVX-512](https://doc.rust-lang.org/std/arch/x86_64/fn._mm512_add_ps.html) intrinsics
+
 Lab 0.7.0 requires Rust >= 1.31.0 for the [chunks_exact](https://doc.rust-lang.org/std/primitive.slice.html#method.chunks_exact) slice method
 
 */
 
+// `no_std` builds already have `core` at the crate root.
+#[cfg(any(feature = "std", test))]
+extern crate core;
+#[cfg(all(not(feature = "std"), feature = "libm"))]
+extern crate libm;
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("without the `std` feature, the `libm` feature is needed for floating point math");
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
 #[cfg(test)]
 #[macro_use]
 extern crate pretty_assertions;
@@ -94,9 +210,125 @@ extern crate rand;
 #[cfg(test)]
 mod approx_impl;
 
-#[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+#[cfg(all(
+    feature = "std",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )
+))]
 mod simd;
 
+// Public so that the kernels can be tested and benchmarked against the others
+// on targets that have intrinsics kernels too.
+#[cfg(feature = "portable_simd")]
+#[doc(hidden)]
+pub mod portable;
+
+mod delta_e;
+#[cfg(feature = "std")]
+mod diff;
+#[cfg(feature = "std")]
+mod dither;
+#[cfg(feature = "std")]
+mod dominant;
+#[cfg(feature = "std")]
+mod fast;
+mod gamut;
+mod gamut_mapping;
+mod gradient;
+mod hue;
+mod interpolate;
+mod lab64;
+#[cfg(feature = "std")]
+mod lut;
+mod math;
+#[cfg(feature = "std")]
+mod palette;
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "std")]
+mod quantize;
+#[cfg(feature = "std")]
+mod reproducible;
+#[cfg(feature = "std")]
+mod scielab;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "rayon")]
+pub use par::{par_labs_to_rgb_bytes, par_labs_to_rgbs, par_rgb_bytes_to_labs, par_rgbs_to_labs};
+
+pub use delta_e::DeltaE;
+#[cfg(feature = "std")]
+pub use diff::ImageDiff;
+#[cfg(feature = "std")]
+pub use dither::{dither, dither_into, Dither, DitherSpace};
+#[cfg(feature = "std")]
+pub use dominant::{DominantColor, DominantColors};
+#[cfg(feature = "std")]
+pub use fast::{
+    fast_labs_to_rgb_bytes, fast_labs_to_rgbs, fast_rgb_bytes_to_labs, fast_rgbs_to_labs, Precision,
+};
+pub use gamut::{cusp, gamut_boundary_into, gamut_mask_into, max_chroma, OutOfGamut};
+#[cfg(feature = "std")]
+pub use gamut::{gamut_boundary, gamut_mask};
+pub use gamut_mapping::GamutMapping;
+pub use gradient::{Easing, Gradient, GradientBuilder};
+pub use hue::Hue;
+#[cfg(feature = "std")]
+pub use interpolate::gradient;
+pub use interpolate::{gradient_into, sample_gradient, HueInterpolation, InterpolationSpace};
+pub use lab64::{LCh64, Lab64};
+#[cfg(feature = "std")]
+pub use lut::{LutSize, RgbToLabLut};
+#[cfg(feature = "std")]
+pub use palette::Palette;
+#[cfg(feature = "std")]
+pub use quantize::{quantize, Quantized, Quantizer};
+#[cfg(feature = "std")]
+pub use reproducible::{
+    reproducible_labs_to_rgb_bytes, reproducible_labs_to_rgbs, reproducible_rgb_bytes_to_labs,
+    reproducible_rgbs_to_labs,
+};
+#[cfg(feature = "std")]
+pub use scielab::{scielab, scielab_into};
+#[cfg(feature = "std")]
+pub use stats::{Distribution, HueStats, LabHistogram, LabStats};
+
+// The batch conversions use the kernels in `simd` on targets that have them,
+// then the portable SIMD kernels if they're enabled, then the scalar code.
+#[cfg(all(
+    feature = "std",
+    not(any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )),
+    not(feature = "portable_simd")
+))]
+use __scalar as batch;
+#[cfg(all(
+    not(any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )),
+    feature = "portable_simd"
+))]
+use portable as batch;
+#[cfg(all(
+    feature = "std",
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    )
+))]
+use simd as batch;
+
+use math::{atan2f, cosf, hypotf, powf, roundf, sinf};
+
 /// Struct representing a color in CIALab, a.k.a. L\*a\*b\*, color space
 #[derive(Debug, PartialEq, Copy, Clone, Default)]
 pub struct Lab {
@@ -144,7 +376,7 @@ fn rgb_to_xyz_normalized(rgb: &[f32; 3]) -> [f32; 3] {
 #[inline(always)]
 #[cfg(any(target_feature = "fma", test))]
 fn mul3(a0: f32, a1: f32, a2: f32, b0: f32, b1: f32, b2: f32) -> f32 {
-    a2.mul_add(b2, a1.mul_add(b1, a0 * b0))
+    math::fmaf(a2, b2, math::fmaf(a1, b1, a0 * b0))
 }
 
 #[inline(always)]
@@ -155,22 +387,23 @@ fn mul3(a0: f32, a1: f32, a2: f32, b0: f32, b1: f32, b2: f32) -> f32 {
 
 #[inline]
 fn rgb_to_xyz_inner(r: f32, g: f32, b: f32) -> [f32; 3] {
-    #[inline]
-    fn rgb_to_xyz_map(c: f32) -> f32 {
-        if c > E_0_255 {
-            const A: f32 = 0.055 * 255.0;
-            const D: f32 = 1.055 * 255.0;
-            ((c + A) / D).powf(2.4)
-        } else {
-            const D: f32 = 12.92 * 255.0;
-            c / D
-        }
-    }
+    linear_rgb_to_xyz(rgb_to_xyz_map(r), rgb_to_xyz_map(g), rgb_to_xyz_map(b))
+}
 
-    let r = rgb_to_xyz_map(r);
-    let g = rgb_to_xyz_map(g);
-    let b = rgb_to_xyz_map(b);
+#[inline]
+fn rgb_to_xyz_map(c: f32) -> f32 {
+    if c > E_0_255 {
+        const A: f32 = 0.055 * 255.0;
+        const D: f32 = 1.055 * 255.0;
+        powf((c + A) / D, 2.4)
+    } else {
+        const D: f32 = 12.92 * 255.0;
+        c / D
+    }
+}
 
+#[inline]
+fn linear_rgb_to_xyz(r: f32, g: f32, b: f32) -> [f32; 3] {
     // Generated by srgb-matrices.py
     let x = mul3(
         r,
@@ -204,7 +437,7 @@ fn xyz_to_lab(xyz: [f32; 3]) -> Lab {
     #[inline]
     fn xyz_to_lab_map(c: f32) -> f32 {
         if c > EPSILON {
-            c.powf(1.0 / 3.0)
+            powf(c, 1.0 / 3.0)
         } else {
             (KAPPA * c + 16.0) / 116.0
         }
@@ -228,17 +461,17 @@ fn lab_to_xyz(lab: &Lab) -> [f32; 3] {
     let fx = (lab.a / 500.0) + fy;
     let fz = fy - (lab.b / 200.0);
     let xr = if fx > CBRT_EPSILON {
-        fx.powi(3)
+        fx * fx * fx
     } else {
         ((fx * 116.0) - 16.0) / KAPPA
     };
     let yr = if lab.l > EPSILON * KAPPA {
-        fy.powi(3)
+        fy * fy * fy
     } else {
         lab.l / KAPPA
     };
     let zr = if fz > CBRT_EPSILON {
-        fz.powi(3)
+        fz * fz * fz
     } else {
         ((fz * 116.0) - 16.0) / KAPPA
     };
@@ -247,15 +480,56 @@ fn lab_to_xyz(lab: &Lab) -> [f32; 3] {
 }
 
 fn xyz_to_rgb(xyz: [f32; 3]) -> [u8; 3] {
-    let rgb = xyz_to_rgb_normalized(xyz);
+    rgb_normalized_to_bytes(xyz_to_rgb_normalized(xyz))
+}
+
+#[inline]
+fn rgb_normalized_to_bytes(rgb: [f32; 3]) -> [u8; 3] {
     [
-        (rgb[0] * 255.0).round() as u8,
-        (rgb[1] * 255.0).round() as u8,
-        (rgb[2] * 255.0).round() as u8,
+        roundf(rgb[0] * 255.0) as u8,
+        roundf(rgb[1] * 255.0) as u8,
+        roundf(rgb[2] * 255.0) as u8,
     ]
 }
 
 fn xyz_to_rgb_normalized(xyz: [f32; 3]) -> [f32; 3] {
+    let rgb = xyz_to_rgb_unclipped(xyz);
+    [clip(rgb[0]), clip(rgb[1]), clip(rgb[2])]
+}
+
+#[inline]
+// `clamp` would propagate NaN while min/max turn it into 1.0.
+#[allow(clippy::manual_clamp)]
+fn clip(c: f32) -> f32 {
+    c.min(1.0).max(0.0)
+}
+
+#[inline]
+// `clip` for `f64`s.
+#[allow(clippy::manual_clamp)]
+fn clip64(c: f64) -> f64 {
+    c.min(1.0).max(0.0)
+}
+
+// Like `xyz_to_rgb_normalized`, but colors outside of the sRGB gamut are left
+// outside of 0..1 instead of being clipped.
+#[inline]
+fn xyz_to_rgb_unclipped(xyz: [f32; 3]) -> [f32; 3] {
+    #[inline]
+    fn xyz_to_rgb_map(c: f32) -> f32 {
+        if c > S_0 {
+            1.055 * powf(c, 1.0 / 2.4) - 0.055
+        } else {
+            12.92 * c
+        }
+    }
+
+    let [r, g, b] = xyz_to_linear_rgb(xyz);
+    [xyz_to_rgb_map(r), xyz_to_rgb_map(g), xyz_to_rgb_map(b)]
+}
+
+#[inline]
+fn xyz_to_linear_rgb(xyz: [f32; 3]) -> [f32; 3] {
     let x = xyz[0];
     let y = xyz[1];
     let z = xyz[2];
@@ -286,18 +560,7 @@ fn xyz_to_rgb_normalized(xyz: [f32; 3]) -> [f32; 3] {
         1.0571295702861434,
     );
 
-    #[inline]
-    fn xyz_to_rgb_map(c: f32) -> f32 {
-        (if c > S_0 {
-            1.055 * c.powf(1.0 / 2.4) - 0.055
-        } else {
-            12.92 * c
-        })
-        .min(1.0)
-        .max(0.0)
-    }
-
-    [xyz_to_rgb_map(r), xyz_to_rgb_map(g), xyz_to_rgb_map(b)]
+    [r, g, b]
 }
 
 /// Convenience function to map a slice of RGB values to Lab values in serial
@@ -315,14 +578,9 @@ fn xyz_to_rgb_normalized(xyz: [f32; 3]) -> [f32; 3] {
 /// ]);
 /// ```
 #[inline]
+#[cfg(feature = "std")]
 pub fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
-    #[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
-    let labs = simd::rgbs_to_labs(rgbs);
-
-    #[cfg(not(all(target_arch = "x86_64", target_feature = "avx2")))]
-    let labs = __scalar::rgbs_to_labs(rgbs);
-
-    labs
+    batch::rgbs_to_labs(rgbs)
 }
 
 /// RGB to Lab conversion that operates on a flat `&[u8]` of consecutive RGB triples.
@@ -339,14 +597,9 @@ pub fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
 ///     Lab { l: 91.11428, a: -48.08274, b: -14.12958 }
 /// ]);
 /// ```
+#[cfg(feature = "std")]
 pub fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
-    #[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
-    let labs = simd::rgb_bytes_to_labs(bytes);
-
-    #[cfg(not(all(target_arch = "x86_64", target_feature = "avx2")))]
-    let labs = __scalar::rgb_bytes_to_labs(bytes);
-
-    labs
+    batch::rgb_bytes_to_labs(bytes)
 }
 
 /// Convenience function to map a slice of Lab values to RGB values in serial
@@ -364,14 +617,9 @@ pub fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
 /// assert_eq!(rgbs, vec![[0u8, 255, 255], [255, 0, 255], [255, 255, 0]]);
 /// ```
 #[inline]
+#[cfg(feature = "std")]
 pub fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
-    #[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
-    let rgbs = simd::labs_to_rgbs(labs);
-
-    #[cfg(not(all(target_arch = "x86_64", target_feature = "avx2")))]
-    let rgbs = __scalar::labs_to_rgbs(labs);
-
-    rgbs
+    batch::labs_to_rgbs(labs)
 }
 
 /// Lab to RGB conversion that returns RGB triples flattened into a `Vec<u8>`
@@ -389,16 +637,89 @@ pub fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
 /// assert_eq!(rgb_bytes, vec![0, 255, 255, 255, 0, 255, 255, 255, 0]);
 /// ```
 #[inline]
+#[cfg(feature = "std")]
 pub fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
-    #[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
-    let bytes = simd::labs_to_rgb_bytes(labs);
+    batch::labs_to_rgb_bytes(labs)
+}
 
-    #[cfg(not(all(target_arch = "x86_64", target_feature = "avx2")))]
-    let bytes = __scalar::labs_to_rgb_bytes(labs);
+/// Converts a slice of RGB values to Lab values, writing them to `labs`
+///
+/// Unlike [`rgbs_to_labs`](fn.rgbs_to_labs.html) it doesn't allocate, so it's
+/// also available without the `std` feature. Colors are converted one at a
+/// time, exactly as by `Lab::from_rgb`.
+///
+/// # Panics
+///
+/// Panics if `labs` isn't the same length as `rgbs`.
+///
+/// # Example
+/// ```
+/// # extern crate lab;
+/// # use lab::{Lab, rgbs_to_labs_into};
+/// let rgbs = &[[255u8, 0, 0], [255, 0, 255], [0, 255, 255]];
+/// let mut labs = [Lab::default(); 3];
+/// rgbs_to_labs_into(rgbs, &mut labs);
+/// assert_eq!(labs[0], Lab::from_rgb(&[255, 0, 0]));
+/// ```
+pub fn rgbs_to_labs_into(rgbs: &[[u8; 3]], labs: &mut [Lab]) {
+    assert_eq!(rgbs.len(), labs.len(), "output length must match input");
+    for (rgb, lab) in rgbs.iter().zip(labs.iter_mut()) {
+        *lab = Lab::from_rgb(rgb);
+    }
+}
+
+/// Converts a flat `&[u8]` of consecutive RGB triples to Lab values, writing
+/// them to `labs`
+///
+/// Like [`rgb_bytes_to_labs`](fn.rgb_bytes_to_labs.html), trailing bytes that
+/// don't make up a whole triple are ignored.
+///
+/// # Panics
+///
+/// Panics if `labs` isn't one third the length of `bytes`, rounded down.
+pub fn rgb_bytes_to_labs_into(bytes: &[u8], labs: &mut [Lab]) {
+    assert_eq!(
+        bytes.len() / 3,
+        labs.len(),
+        "output length must match input"
+    );
+    for (rgb, lab) in bytes.chunks_exact(3).zip(labs.iter_mut()) {
+        *lab = rgb_to_lab(rgb[0], rgb[1], rgb[2]);
+    }
+}
 
-    bytes
+/// Converts a slice of Lab values to RGB values, writing them to `rgbs`
+///
+/// Unlike [`labs_to_rgbs`](fn.labs_to_rgbs.html) it doesn't allocate, so it's
+/// also available without the `std` feature.
+///
+/// # Panics
+///
+/// Panics if `rgbs` isn't the same length as `labs`.
+pub fn labs_to_rgbs_into(labs: &[Lab], rgbs: &mut [[u8; 3]]) {
+    assert_eq!(labs.len(), rgbs.len(), "output length must match input");
+    for (lab, rgb) in labs.iter().zip(rgbs.iter_mut()) {
+        *rgb = lab.to_rgb();
+    }
+}
+
+/// Converts a slice of Lab values to RGB triples flattened into `bytes`
+///
+/// # Panics
+///
+/// Panics if `bytes` isn't three times the length of `labs`.
+pub fn labs_to_rgb_bytes_into(labs: &[Lab], bytes: &mut [u8]) {
+    assert_eq!(
+        labs.len() * 3,
+        bytes.len(),
+        "output length must match input"
+    );
+    for (lab, rgb) in labs.iter().zip(bytes.chunks_exact_mut(3)) {
+        rgb.copy_from_slice(&lab.to_rgb());
+    }
 }
 
+#[cfg(feature = "std")]
 #[doc(hidden)]
 pub mod __scalar {
     use rgb_to_lab;
@@ -502,7 +823,8 @@ impl Lab {
     /// assert_eq!(254.23636, pink.squared_distance(&websafe_pink));
     /// ```
     pub fn squared_distance(&self, other: &Lab) -> f32 {
-        (self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2)
+        let (dl, da, db) = (self.l - other.l, self.a - other.a, self.b - other.b);
+        dl * dl + da * da + db * db
     }
 }
 
@@ -551,8 +873,8 @@ impl LCh {
     pub fn from_lab(lab: Lab) -> Self {
         LCh {
             l: lab.l,
-            c: lab.a.hypot(lab.b),
-            h: lab.b.atan2(lab.a),
+            c: hypotf(lab.a, lab.b),
+            h: atan2f(lab.b, lab.a),
         }
     }
 
@@ -597,15 +919,20 @@ impl LCh {
     pub fn to_lab(&self) -> Lab {
         Lab {
             l: self.l,
-            a: self.c * self.h.cos(),
-            b: self.c * self.h.sin(),
+            a: self.c * cosf(self.h),
+            b: self.c * sinf(self.h),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{labs_to_rgbs, rgbs_to_labs, LCh, Lab};
+    use super::{
+        labs_to_rgb_bytes_into, labs_to_rgbs_into, rgb_bytes_to_labs_into, rgbs_to_labs_into, LCh,
+        Lab,
+    };
+    #[cfg(feature = "std")]
+    use super::{labs_to_rgbs, rgbs_to_labs};
     use approx::assert_relative_eq;
     use rand::Rng;
 
@@ -673,6 +1000,7 @@ mod tests {
     ];
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_lab_from_rgb() {
         let expected: Vec<_> = COLOURS.iter().map(|(_, lab, _)| *lab).collect();
         let actual: Vec<_> = COLOURS
@@ -690,6 +1018,18 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "std"))]
+    fn test_libm_from_rgb() {
+        // libm's `powf` and `atan2f` can round differently from std's, so
+        // without std the expected values only hold to about four decimals.
+        for (rgb, lab, lch) in COLOURS.iter() {
+            assert_relative_eq!(*lab, Lab::from_rgb(rgb), epsilon = 0.0001);
+            assert_relative_eq!(*lch, LCh::from_rgb(rgb), epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
     fn test_lch_from_rgb() {
         let expected: Vec<_> = COLOURS.iter().map(|(_, _, lch)| *lch).collect();
         let actual: Vec<_> = COLOURS
@@ -725,7 +1065,7 @@ mod tests {
         // perhaps imprecision of trig functions) the Lab→LCh→Lab conversion
         // produces slightly different colour than what the source.  Round a*
         // and b* to four decimal places to work around this.
-        fn round(vec: &mut Vec<Lab>) {
+        fn round(vec: &mut [Lab]) {
             for lab in vec.iter_mut() {
                 lab.a = (lab.a * 100000.0).round() / 100000.0;
                 lab.b = (lab.b * 100000.0).round() / 100000.0;
@@ -760,6 +1100,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_rgb_to_lab_to_rgb() {
         let rgbs: Vec<[u8; 3]> = {
             let rand_seed = [1u8; 32];
@@ -774,6 +1115,51 @@ mod tests {
     }
 
     #[test]
+    fn test_into() {
+        let rgbs: Vec<[u8; 3]> = {
+            let rand_seed = [1u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&rand::distributions::Standard)
+                .take(2048)
+                .collect()
+        };
+        let bytes: Vec<u8> = rgbs.iter().flat_map(|rgb| rgb.iter().cloned()).collect();
+
+        let mut labs = vec![Lab::default(); rgbs.len()];
+        rgbs_to_labs_into(&rgbs, &mut labs);
+        let expected: Vec<_> = rgbs.iter().map(Lab::from_rgb).collect();
+        assert_eq!(expected, labs);
+
+        let mut labs_from_bytes = vec![Lab::default(); rgbs.len()];
+        rgb_bytes_to_labs_into(&bytes, &mut labs_from_bytes);
+        assert_eq!(labs, labs_from_bytes);
+
+        let mut rgbs2 = vec![[0u8; 3]; labs.len()];
+        labs_to_rgbs_into(&labs, &mut rgbs2);
+        assert_eq!(rgbs, rgbs2);
+
+        let mut bytes2 = vec![0u8; labs.len() * 3];
+        labs_to_rgb_bytes_into(&labs, &mut bytes2);
+        assert_eq!(bytes, bytes2);
+    }
+
+    #[test]
+    fn test_rgb_bytes_to_labs_into_ignores_trailing_bytes() {
+        let mut labs = [Lab::default(); 2];
+        rgb_bytes_to_labs_into(&[253, 120, 138, 253, 120, 138, 7], &mut labs);
+        let pink = Lab::from_rgb(&[253, 120, 138]);
+        assert_eq!([pink, pink], labs);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_into_length_mismatch() {
+        let mut labs = [Lab::default(); 2];
+        rgbs_to_labs_into(&[[253, 120, 138]], &mut labs);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
     fn test_grey_error() {
         // Grey colours have a* and b* components equal to zero.  This test goes
         // through all 8-bit greys and calculates squared error.  If it goes up,
@@ -0,0 +1,243 @@
+//! Lookup table accelerated RGB to Lab conversion for `u8` input.
+
+use {linear_rgb_to_xyz, rgb_to_xyz_inner, rgb_to_xyz_map, xyz_to_lab, Lab};
+
+/// The size, and so the accuracy, of a [`RgbToLabLut`](struct.RgbToLabLut.html)
+///
+/// Except for `Exact`, each size samples the RGB cube with a grid of Lab
+/// values and interpolates between the eight grid points around each color.
+/// The maximum errors listed are the largest Euclidean distance in Lab space
+/// between the table's result and [`Lab::from_rgb`](struct.Lab.html#method.from_rgb)'s
+/// over all 16,777,216 colors.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum LutSize {
+    /// A 256-entry table that replaces only the sRGB gamma expansion, 1 KiB.
+    /// Results are identical to `Lab::from_rgb`'s.
+    Exact,
+    /// 86×86×86 grid, 7.6 MB. Maximum error 0.07.
+    Large,
+    /// 52×52×52 grid, 1.7 MB. Maximum error 0.21.
+    Medium,
+    /// 18×18×18 grid, 70 KB. Maximum error 1.15.
+    Small,
+}
+
+impl LutSize {
+    // Distance between grid points. Each one divides 255 so that the grid
+    // points fall on whole RGB values and the last one is exactly 255.
+    fn step(self) -> usize {
+        match self {
+            LutSize::Exact => 1,
+            LutSize::Large => 3,
+            LutSize::Medium => 5,
+            LutSize::Small => 15,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Table {
+    Linear(Box<[f32; 256]>),
+    // `cells` maps each channel value to the grid point at or below it and
+    // how far past that point it is, to save a division per channel.
+    Grid {
+        len: usize,
+        cells: Box<[(usize, f32); 256]>,
+        labs: Vec<Lab>,
+    },
+}
+
+/// A precomputed table for converting `u8` RGB colors to Lab
+///
+/// Building the table costs about as much as converting as many colors as it
+/// has entries, so it only pays off when it's reused for many conversions.
+///
+/// # Example
+///
+/// ```
+/// # use lab::{Lab, LutSize, RgbToLabLut};
+/// let lut = RgbToLabLut::new(LutSize::Medium);
+/// let lab = lut.rgb_to_lab(&[240, 33, 95]);
+/// assert!(lab.squared_distance(&Lab::from_rgb(&[240, 33, 95])) < 0.21 * 0.21);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RgbToLabLut {
+    size: LutSize,
+    table: Table,
+}
+
+impl RgbToLabLut {
+    /// Builds a table of the given size
+    pub fn new(size: LutSize) -> Self {
+        let table = match size {
+            LutSize::Exact => {
+                let mut linear = Box::new([0f32; 256]);
+                for (c, v) in linear.iter_mut().enumerate() {
+                    *v = rgb_to_xyz_map(c as f32);
+                }
+                Table::Linear(linear)
+            }
+            _ => {
+                let step = size.step();
+                let len = 255 / step + 1;
+                let mut labs = Vec::with_capacity(len * len * len);
+                for r in 0..len {
+                    for g in 0..len {
+                        for b in 0..len {
+                            let xyz = rgb_to_xyz_inner(
+                                (r * step) as f32,
+                                (g * step) as f32,
+                                (b * step) as f32,
+                            );
+                            labs.push(xyz_to_lab(xyz));
+                        }
+                    }
+                }
+                // 255 is on the last grid point, which has no neighbor after
+                // it, so it's treated as the far end of the last cell instead.
+                let mut cells = Box::new([(0, 0.0); 256]);
+                for (c, cell) in cells.iter_mut().enumerate() {
+                    let i = (c / step).min(len - 2);
+                    *cell = (i, (c - i * step) as f32 / step as f32);
+                }
+                Table::Grid { len, cells, labs }
+            }
+        };
+        RgbToLabLut { size, table }
+    }
+
+    /// Returns the size the table was built with
+    pub fn size(&self) -> LutSize {
+        self.size
+    }
+
+    /// Converts one RGB color to Lab
+    pub fn rgb_to_lab(&self, rgb: &[u8; 3]) -> Lab {
+        match self.table {
+            Table::Linear(ref linear) => xyz_to_lab(linear_rgb_to_xyz(
+                linear[rgb[0] as usize],
+                linear[rgb[1] as usize],
+                linear[rgb[2] as usize],
+            )),
+            Table::Grid {
+                len,
+                ref cells,
+                ref labs,
+            } => interpolate(labs, len, cells, rgb),
+        }
+    }
+
+    /// Converts a slice of RGB colors to Lab, like [`rgbs_to_labs`](fn.rgbs_to_labs.html)
+    pub fn rgbs_to_labs(&self, rgbs: &[[u8; 3]]) -> Vec<Lab> {
+        rgbs.iter().map(|rgb| self.rgb_to_lab(rgb)).collect()
+    }
+
+    /// Converts a flat slice of RGB triples to Lab, like
+    /// [`rgb_bytes_to_labs`](fn.rgb_bytes_to_labs.html)
+    pub fn rgb_bytes_to_labs(&self, bytes: &[u8]) -> Vec<Lab> {
+        bytes
+            .chunks_exact(3)
+            .map(|rgb| self.rgb_to_lab(&[rgb[0], rgb[1], rgb[2]]))
+            .collect()
+    }
+}
+
+#[inline]
+fn interpolate(labs: &[Lab], len: usize, cells: &[(usize, f32); 256], rgb: &[u8; 3]) -> Lab {
+    let (ri, rf) = cells[rgb[0] as usize];
+    let (gi, gf) = cells[rgb[1] as usize];
+    let (bi, bf) = cells[rgb[2] as usize];
+
+    let at = |r: usize, g: usize, b: usize| labs[((ri + r) * len + gi + g) * len + bi + b];
+    let lerp = |p: Lab, q: Lab, t: f32| Lab {
+        l: p.l + (q.l - p.l) * t,
+        a: p.a + (q.a - p.a) * t,
+        b: p.b + (q.b - p.b) * t,
+    };
+
+    let c00 = lerp(at(0, 0, 0), at(0, 0, 1), bf);
+    let c01 = lerp(at(0, 1, 0), at(0, 1, 1), bf);
+    let c10 = lerp(at(1, 0, 0), at(1, 0, 1), bf);
+    let c11 = lerp(at(1, 1, 0), at(1, 1, 1), bf);
+    let c0 = lerp(c00, c01, gf);
+    let c1 = lerp(c10, c11, gf);
+    lerp(c0, c1, rf)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LutSize, RgbToLabLut};
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+    use {Lab, __scalar};
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    fn max_error(size: LutSize, rgbs: &[[u8; 3]]) -> f32 {
+        let lut = RgbToLabLut::new(size);
+        rgbs.iter()
+            .map(|rgb| lut.rgb_to_lab(rgb).squared_distance(&Lab::from_rgb(rgb)))
+            .fold(0.0, f32::max)
+            .sqrt()
+    }
+
+    #[test]
+    fn test_exact_lut() {
+        let lut = RgbToLabLut::new(LutSize::Exact);
+        for rgb in RGBS.iter() {
+            assert_eq!(lut.rgb_to_lab(rgb), Lab::from_rgb(rgb));
+        }
+    }
+
+    #[test]
+    fn test_grid_points() {
+        // Colors on the grid are looked up rather than interpolated.
+        let lut = RgbToLabLut::new(LutSize::Small);
+        for &rgb in &[[0, 0, 0], [255, 255, 255], [15, 240, 255], [255, 0, 135]] {
+            assert_eq!(lut.rgb_to_lab(&rgb), Lab::from_rgb(&rgb));
+        }
+    }
+
+    #[test]
+    fn test_documented_max_error() {
+        assert!(max_error(LutSize::Large, &RGBS) <= 0.07);
+        assert!(max_error(LutSize::Medium, &RGBS) <= 0.21);
+        assert!(max_error(LutSize::Small, &RGBS) <= 1.15);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_documented_max_error_exhaustive() {
+        // Checks every color, which is too slow without optimizations:
+        // `cargo test --release -- --ignored`
+        let rgbs: Vec<[u8; 3]> = (0..1 << 24)
+            .map(|i: u32| [(i >> 16) as u8, (i >> 8) as u8, i as u8])
+            .collect();
+        for &(size, error) in &[
+            (LutSize::Large, 0.07),
+            (LutSize::Medium, 0.21),
+            (LutSize::Small, 1.15),
+        ] {
+            assert!(max_error(size, &rgbs) <= error, "{:?}", size);
+        }
+    }
+
+    #[test]
+    fn test_lut_rgb_bytes_to_labs() {
+        let lut = RgbToLabLut::new(LutSize::Exact);
+        let bytes: Vec<u8> = RGBS.iter().flat_map(|rgb| rgb.iter().cloned()).collect();
+        assert_eq!(lut.rgb_bytes_to_labs(&bytes), lut.rgbs_to_labs(&RGBS));
+        assert_eq!(
+            lut.rgb_bytes_to_labs(&bytes),
+            __scalar::rgb_bytes_to_labs(&bytes)
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Floating point functions that `core` doesn't provide.
+//!
+//! With the `std` feature these call the inherent methods; without it they're
+//! libm's. They're named after libm's functions and are called as functions
+//! rather than methods so that the single value conversions always use the
+//! implementation picked by the feature, even in tests, which link std either
+//! way.
+
+#[cfg(all(not(feature = "std"), any(target_feature = "fma", test)))]
+pub(crate) use libm::fmaf;
+#[cfg(not(feature = "std"))]
+pub(crate) use libm::{
+    atan2, atan2f, cbrt, cos, cosf, exp, hypot, hypotf, log, pow, powf, round, roundf, sin, sinf,
+    sqrt, sqrtf,
+};
+
+#[cfg(feature = "std")]
+mod imp {
+    #[inline(always)]
+    pub fn atan2(y: f64, x: f64) -> f64 {
+        y.atan2(x)
+    }
+
+    #[inline(always)]
+    pub fn atan2f(y: f32, x: f32) -> f32 {
+        y.atan2(x)
+    }
+
+    #[inline(always)]
+    pub fn cbrt(x: f64) -> f64 {
+        x.cbrt()
+    }
+
+    #[inline(always)]
+    pub fn cos(x: f64) -> f64 {
+        x.cos()
+    }
+
+    #[inline(always)]
+    pub fn cosf(x: f32) -> f32 {
+        x.cos()
+    }
+
+    #[inline(always)]
+    pub fn exp(x: f64) -> f64 {
+        x.exp()
+    }
+
+    #[inline(always)]
+    #[cfg(any(target_feature = "fma", test))]
+    pub fn fmaf(x: f32, y: f32, z: f32) -> f32 {
+        x.mul_add(y, z)
+    }
+
+    #[inline(always)]
+    pub fn hypot(x: f64, y: f64) -> f64 {
+        x.hypot(y)
+    }
+
+    #[inline(always)]
+    pub fn hypotf(x: f32, y: f32) -> f32 {
+        x.hypot(y)
+    }
+
+    #[inline(always)]
+    pub fn log(x: f64) -> f64 {
+        x.ln()
+    }
+
+    #[inline(always)]
+    pub fn pow(x: f64, y: f64) -> f64 {
+        x.powf(y)
+    }
+
+    #[inline(always)]
+    pub fn powf(x: f32, y: f32) -> f32 {
+        x.powf(y)
+    }
+
+    #[inline(always)]
+    pub fn round(x: f64) -> f64 {
+        x.round()
+    }
+
+    #[inline(always)]
+    pub fn roundf(x: f32) -> f32 {
+        x.round()
+    }
+
+    #[inline(always)]
+    pub fn sin(x: f64) -> f64 {
+        x.sin()
+    }
+
+    #[inline(always)]
+    pub fn sinf(x: f32) -> f32 {
+        x.sin()
+    }
+
+    #[inline(always)]
+    pub fn sqrt(x: f64) -> f64 {
+        x.sqrt()
+    }
+
+    #[inline(always)]
+    pub fn sqrtf(x: f32) -> f32 {
+        x.sqrt()
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) use self::imp::*;
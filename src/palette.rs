@@ -0,0 +1,445 @@
+//! Finding the closest colors in a palette.
+//!
+//! The palette's colors are kept in a k-d tree in Lab space, splitting on
+//! L\*, a\* and b\* in turn, so a query only measures the distance to the
+//! colors in the parts of the tree that could hold a closer one.
+//!
+//! CIEDE2000 isn't a distance in Lab space, so the tree can't rule out the
+//! far side of a split on a\* or b\* for it. A difference in L\* alone does
+//! bound it from below, though, so ΔE2000 queries still skip the far side
+//! of splits on L\* and give exactly the same results as comparing every
+//! color.
+
+use delta_e::DeltaE;
+use {rgb_to_lab, Lab};
+
+/// A set of colors to find the closest matches to other colors in
+///
+/// # Example
+/// ```
+/// # use lab::{DeltaE, Lab, Palette};
+/// let palette = Palette::from_rgbs(&[[0, 0, 0], [255, 255, 255], [255, 0, 0]]);
+/// let pink = Lab::from_rgb(&[240, 33, 95]);
+/// assert_eq!(palette.nearest(&pink, DeltaE::Cie2000), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Palette {
+    colors: Vec<Lab>,
+    // Indices into `colors`, ordered as an implicit k-d tree: the median of
+    // a range, along the axis for its depth, is at its middle, with the
+    // colors below it before and above it after.
+    tree: Vec<usize>,
+    // The lowest and highest L* in the palette.
+    lightness: (f32, f32),
+}
+
+// The best matches found so far, closest first, by the distance as `key`
+// measures it and then by index.
+struct Candidates {
+    k: usize,
+    found: Vec<(f32, usize)>,
+}
+
+impl Candidates {
+    fn new(k: usize) -> Self {
+        Candidates {
+            k,
+            found: Vec::with_capacity(k),
+        }
+    }
+
+    // The distance a color has to be within to be one of the matches.
+    fn worst(&self) -> f32 {
+        if self.found.len() < self.k {
+            f32::INFINITY
+        } else {
+            self.found[self.k - 1].0
+        }
+    }
+
+    fn insert(&mut self, key: f32, index: usize) {
+        let closer = |&(k, i): &(f32, usize)| k < key || (k == key && i < index);
+        let position = self.found.iter().take_while(|c| closer(c)).count();
+        if position < self.k {
+            if self.found.len() == self.k {
+                self.found.pop();
+            }
+            self.found.insert(position, (key, index));
+        }
+    }
+}
+
+// The distance used to order colors: the squared distance for CIE76, which
+// orders them the same way and saves a square root, and ΔE2000 itself.
+fn key(delta_e: DeltaE, a: &Lab, b: &Lab) -> f32 {
+    match delta_e {
+        DeltaE::Cie76 => a.squared_distance(b),
+        DeltaE::Cie2000 => a.delta_e_2000(b),
+    }
+}
+
+fn key_to_delta_e(delta_e: DeltaE, key: f32) -> f32 {
+    match delta_e {
+        DeltaE::Cie76 => key.sqrt(),
+        DeltaE::Cie2000 => key,
+    }
+}
+
+pub(crate) fn coordinate(lab: &Lab, axis: usize) -> f32 {
+    match axis {
+        0 => lab.l,
+        1 => lab.a,
+        _ => lab.b,
+    }
+}
+
+// CIEDE2000's lightness weighting, S_L, for the mean lightness of two colors.
+fn lightness_weight(l: f32) -> f32 {
+    let l_50 = (l - 50.0) * (l - 50.0);
+    1.0 + 0.015 * l_50 / (20.0 + l_50).sqrt()
+}
+
+fn build(colors: &[Lab], tree: &mut [usize], depth: usize) {
+    if tree.len() <= 1 {
+        return;
+    }
+    let axis = depth % 3;
+    let middle = tree.len() / 2;
+    tree.select_nth_unstable_by(middle, |&i, &j| {
+        coordinate(&colors[i], axis)
+            .partial_cmp(&coordinate(&colors[j], axis))
+            .unwrap_or(core::cmp::Ordering::Equal)
+            .then(i.cmp(&j))
+    });
+    let (below, rest) = tree.split_at_mut(middle);
+    build(colors, below, depth + 1);
+    build(colors, &mut rest[1..], depth + 1);
+}
+
+impl Palette {
+    /// Makes a palette of `colors`
+    ///
+    /// Matches are given as indices into `colors`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `colors` is empty.
+    pub fn new(colors: &[Lab]) -> Self {
+        assert!(!colors.is_empty(), "a palette needs at least one color");
+        let mut palette = Palette {
+            colors: colors.to_vec(),
+            tree: (0..colors.len()).collect(),
+            lightness: (0.0, 0.0),
+        };
+        palette.build();
+        palette
+    }
+
+    // Replaces the colors with as many others, reusing the palette's memory.
+    pub(crate) fn set_colors(&mut self, colors: &[Lab]) {
+        self.colors.copy_from_slice(colors);
+        for (i, node) in self.tree.iter_mut().enumerate() {
+            *node = i;
+        }
+        self.build();
+    }
+
+    fn build(&mut self) {
+        build(&self.colors, &mut self.tree, 0);
+        self.lightness = self
+            .colors
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), lab| {
+                (min.min(lab.l), max.max(lab.l))
+            });
+    }
+
+    /// Makes a palette of RGB `colors`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `colors` is empty.
+    pub fn from_rgbs(colors: &[[u8; 3]]) -> Self {
+        let labs: Vec<Lab> = colors.iter().map(Lab::from_rgb).collect();
+        Palette::new(&labs)
+    }
+
+    /// Returns the palette's colors, in the order they were given in
+    pub fn colors(&self) -> &[Lab] {
+        &self.colors
+    }
+
+    /// Returns how many colors the palette has
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Returns `false`; a palette always has at least one color
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Returns the index of the palette color closest to `lab`, as measured
+    /// by `delta_e`
+    ///
+    /// Of equally close colors, the one given first wins.
+    pub fn nearest(&self, lab: &Lab, delta_e: DeltaE) -> usize {
+        let mut candidates = Candidates::new(1);
+        self.search(lab, delta_e, &mut candidates);
+        candidates.found[0].1
+    }
+
+    /// Returns the indices of the `k` palette colors closest to `lab`, as
+    /// measured by `delta_e`, closest first, each with its ΔE from `lab`
+    ///
+    /// Of equally close colors, the one given first comes first. Returns
+    /// every color if the palette has fewer than `k`.
+    ///
+    /// # Example
+    /// ```
+    /// # use lab::{DeltaE, Lab, Palette};
+    /// let palette = Palette::from_rgbs(&[[0, 0, 0], [128, 128, 128], [255, 255, 255]]);
+    /// let grey = Lab::from_rgb(&[100, 100, 100]);
+    /// let nearest = palette.k_nearest(&grey, 2, DeltaE::Cie76);
+    /// assert_eq!(nearest.iter().map(|&(i, _)| i).collect::<Vec<_>>(), [1, 0]);
+    /// ```
+    pub fn k_nearest(&self, lab: &Lab, k: usize, delta_e: DeltaE) -> Vec<(usize, f32)> {
+        let mut candidates = Candidates::new(k.min(self.len()));
+        if candidates.k > 0 {
+            self.search(lab, delta_e, &mut candidates);
+        }
+        candidates
+            .found
+            .into_iter()
+            .map(|(key, i)| (i, key_to_delta_e(delta_e, key)))
+            .collect()
+    }
+
+    /// Returns the index of the closest palette color to each of `labs`
+    ///
+    /// # Example
+    /// ```
+    /// # use lab::{DeltaE, Lab, Palette};
+    /// let palette = Palette::from_rgbs(&[[0, 0, 0], [255, 255, 255]]);
+    /// let labs = [Lab::from_rgb(&[20, 30, 40]), Lab::from_rgb(&[200, 210, 220])];
+    /// assert_eq!(palette.map_labs(&labs, DeltaE::Cie76), [0, 1]);
+    /// ```
+    pub fn map_labs(&self, labs: &[Lab], delta_e: DeltaE) -> Vec<usize> {
+        let mut indices = vec![0; labs.len()];
+        self.map_labs_into(labs, delta_e, &mut indices);
+        indices
+    }
+
+    /// Writes the index of the closest palette color to each of `labs` to
+    /// `indices`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `indices` isn't the same length as `labs`.
+    pub fn map_labs_into(&self, labs: &[Lab], delta_e: DeltaE, indices: &mut [usize]) {
+        assert_eq!(labs.len(), indices.len(), "output length must match input");
+        let mut candidates = Candidates::new(1);
+        for (lab, index) in labs.iter().zip(indices.iter_mut()) {
+            candidates.found.clear();
+            self.search(lab, delta_e, &mut candidates);
+            *index = candidates.found[0].1;
+        }
+    }
+
+    /// Returns the index of the closest palette color to each RGB triple in
+    /// a flat `&[u8]`
+    ///
+    /// Like [`rgb_bytes_to_labs`](fn.rgb_bytes_to_labs.html), trailing bytes
+    /// that don't make up a whole triple are ignored.
+    pub fn map_rgb_bytes(&self, bytes: &[u8], delta_e: DeltaE) -> Vec<usize> {
+        let mut indices = vec![0; bytes.len() / 3];
+        self.map_rgb_bytes_into(bytes, delta_e, &mut indices);
+        indices
+    }
+
+    /// Writes the index of the closest palette color to each RGB triple in a
+    /// flat `&[u8]` to `indices`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `indices` isn't one third the length of `bytes`, rounded
+    /// down.
+    pub fn map_rgb_bytes_into(&self, bytes: &[u8], delta_e: DeltaE, indices: &mut [usize]) {
+        assert_eq!(
+            bytes.len() / 3,
+            indices.len(),
+            "output length must match input"
+        );
+        let mut candidates = Candidates::new(1);
+        for (rgb, index) in bytes.chunks_exact(3).zip(indices.iter_mut()) {
+            candidates.found.clear();
+            self.search(
+                &rgb_to_lab(rgb[0], rgb[1], rgb[2]),
+                delta_e,
+                &mut candidates,
+            );
+            *index = candidates.found[0].1;
+        }
+    }
+
+    fn search(&self, lab: &Lab, delta_e: DeltaE, candidates: &mut Candidates) {
+        // The mean lightness of `lab` and any palette color is between these,
+        // and S_L is largest at whichever is further from 50.
+        let (min, max) = (self.lightness.0.min(lab.l), self.lightness.1.max(lab.l));
+        let lightness_weight = lightness_weight(min).max(lightness_weight(max));
+        let bound = |axis: usize, gap: f32| match delta_e {
+            DeltaE::Cie76 => gap * gap,
+            // The lightness term of ΔE2000 alone. The rest of it can't be
+            // negative, since |R_T| < 2.
+            DeltaE::Cie2000 if axis == 0 => gap / lightness_weight,
+            DeltaE::Cie2000 => 0.0,
+        };
+        self.search_range(lab, delta_e, &bound, 0, self.tree.len(), 0, candidates);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search_range<F: Fn(usize, f32) -> f32>(
+        &self,
+        lab: &Lab,
+        delta_e: DeltaE,
+        bound: &F,
+        start: usize,
+        end: usize,
+        depth: usize,
+        candidates: &mut Candidates,
+    ) {
+        if start >= end {
+            return;
+        }
+        let middle = start + (end - start) / 2;
+        let index = self.tree[middle];
+        let color = &self.colors[index];
+        candidates.insert(key(delta_e, lab, color), index);
+
+        let axis = depth % 3;
+        let gap = coordinate(lab, axis) - coordinate(color, axis);
+        let (near, far) = if gap < 0.0 {
+            ((start, middle), (middle + 1, end))
+        } else {
+            ((middle + 1, end), (start, middle))
+        };
+        self.search_range(lab, delta_e, bound, near.0, near.1, depth + 1, candidates);
+        // Ties go to the lower index, which could be on the far side.
+        if bound(axis, gap.abs()) <= candidates.worst() {
+            self.search_range(lab, delta_e, bound, far.0, far.1, depth + 1, candidates);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Palette;
+    use delta_e::DeltaE;
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+    use Lab;
+
+    const METRICS: [DeltaE; 2] = [DeltaE::Cie76, DeltaE::Cie2000];
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(768).collect()
+        };
+    }
+
+    // Every color's distance to `lab`, closest first.
+    fn brute_force(palette: &Palette, lab: &Lab, delta_e: DeltaE) -> Vec<(usize, f32)> {
+        let mut distances: Vec<(usize, f32)> = palette
+            .colors()
+            .iter()
+            .map(|color| delta_e.distance(lab, color))
+            .enumerate()
+            .collect();
+        distances.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(a.0.cmp(&b.0)));
+        distances
+    }
+
+    #[test]
+    fn test_nearest_matches_brute_force() {
+        let palette = Palette::from_rgbs(&RGBS[..256]);
+        for rgb in RGBS[256..].iter() {
+            let lab = Lab::from_rgb(rgb);
+            for &delta_e in METRICS.iter() {
+                let expected = brute_force(&palette, &lab, delta_e);
+                assert_eq!(palette.nearest(&lab, delta_e), expected[0].0, "{:?}", rgb);
+            }
+        }
+    }
+
+    #[test]
+    fn test_k_nearest_matches_brute_force() {
+        let palette = Palette::from_rgbs(&RGBS[..100]);
+        for rgb in RGBS[100..200].iter() {
+            let lab = Lab::from_rgb(rgb);
+            for &delta_e in METRICS.iter() {
+                let expected = brute_force(&palette, &lab, delta_e);
+                let nearest = palette.k_nearest(&lab, 5, delta_e);
+                assert_eq!(nearest.len(), 5);
+                for (&(i, d), &(j, e)) in nearest.iter().zip(expected.iter()) {
+                    assert_eq!(i, j, "{:?} {:?}", rgb, delta_e);
+                    assert!((d - e).abs() < 0.001);
+                }
+            }
+        }
+        let lab = Lab::from_rgb(&RGBS[0]);
+        assert_eq!(palette.k_nearest(&lab, 0, DeltaE::Cie76), []);
+        assert_eq!(palette.k_nearest(&lab, 1000, DeltaE::Cie76).len(), 100);
+    }
+
+    #[test]
+    fn test_ties_go_to_first_color() {
+        let colors = [[10, 20, 30], [255, 0, 0], [10, 20, 30], [10, 20, 30]];
+        let palette = Palette::from_rgbs(&colors);
+        for &delta_e in METRICS.iter() {
+            assert_eq!(palette.nearest(&Lab::from_rgb(&[10, 20, 30]), delta_e), 0);
+            let nearest = palette.k_nearest(&Lab::from_rgb(&[12, 20, 30]), 3, delta_e);
+            assert_eq!(
+                nearest.iter().map(|&(i, _)| i).collect::<Vec<_>>(),
+                [0, 2, 3]
+            );
+        }
+    }
+
+    #[test]
+    fn test_map() {
+        let palette = Palette::from_rgbs(&RGBS[..64]);
+        let labs: Vec<Lab> = RGBS[64..].iter().map(Lab::from_rgb).collect();
+        let bytes: Vec<u8> = RGBS[64..]
+            .iter()
+            .flat_map(|rgb| rgb.iter().cloned())
+            .collect();
+        for &delta_e in METRICS.iter() {
+            let expected: Vec<usize> = labs
+                .iter()
+                .map(|lab| palette.nearest(lab, delta_e))
+                .collect();
+            assert_eq!(palette.map_labs(&labs, delta_e), expected);
+            assert_eq!(palette.map_rgb_bytes(&bytes, delta_e), expected);
+            // Trailing bytes are ignored.
+            assert_eq!(palette.map_rgb_bytes(&bytes[..7], delta_e), &expected[..2]);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "output length must match input")]
+    fn test_map_into_length_mismatch() {
+        let palette = Palette::from_rgbs(&RGBS[..4]);
+        let mut indices = [0; 2];
+        palette.map_rgb_bytes_into(&[0; 9], DeltaE::Cie76, &mut indices);
+    }
+
+    #[test]
+    #[should_panic(expected = "a palette needs at least one color")]
+    fn test_empty_palette() {
+        Palette::new(&[]);
+    }
+}
@@ -0,0 +1,106 @@
+//! Parallel versions of the batch conversions, using rayon.
+//!
+//! Each function splits its input into chunks of `CHUNK_LEN` colors and
+//! converts the chunks on rayon's thread pool with the serial batch function.
+//! `CHUNK_LEN` is a multiple of every SIMD kernel's width, so only the last
+//! chunk can end in a partial SIMD vector, exactly as when the whole slice is
+//! converted serially. The output is identical to the serial functions'.
+
+use rayon::prelude::*;
+use {labs_to_rgb_bytes, labs_to_rgbs, rgb_bytes_to_labs, rgbs_to_labs, Lab};
+
+// 4096 colors is a multiple of 16, the widest kernel, and big enough that
+// scheduling overhead is negligible next to the conversion itself.
+const CHUNK_LEN: usize = 4096;
+
+/// Parallel version of [`rgbs_to_labs`](fn.rgbs_to_labs.html)
+///
+/// # Example
+/// ```
+/// # extern crate lab;
+/// let rgbs = vec![[253u8, 120, 138]; 10_000];
+/// assert_eq!(lab::par_rgbs_to_labs(&rgbs), lab::rgbs_to_labs(&rgbs));
+/// ```
+pub fn par_rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
+    rgbs.par_chunks(CHUNK_LEN)
+        .flat_map_iter(rgbs_to_labs)
+        .collect()
+}
+
+/// Parallel version of [`rgb_bytes_to_labs`](fn.rgb_bytes_to_labs.html)
+pub fn par_rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
+    // Trailing bytes that don't make up a whole triple are ignored, as they
+    // are by the serial function.
+    let bytes = &bytes[..bytes.len() - bytes.len() % 3];
+    bytes
+        .par_chunks(CHUNK_LEN * 3)
+        .flat_map_iter(rgb_bytes_to_labs)
+        .collect()
+}
+
+/// Parallel version of [`labs_to_rgbs`](fn.labs_to_rgbs.html)
+pub fn par_labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
+    labs.par_chunks(CHUNK_LEN)
+        .flat_map_iter(labs_to_rgbs)
+        .collect()
+}
+
+/// Parallel version of [`labs_to_rgb_bytes`](fn.labs_to_rgb_bytes.html)
+pub fn par_labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
+    labs.par_chunks(CHUNK_LEN)
+        .flat_map_iter(labs_to_rgb_bytes)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::CHUNK_LEN;
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+    use {
+        labs_to_rgb_bytes, labs_to_rgbs, par_labs_to_rgb_bytes, par_labs_to_rgbs,
+        par_rgb_bytes_to_labs, par_rgbs_to_labs, rgb_bytes_to_labs, rgbs_to_labs,
+    };
+
+    lazy_static! {
+        // Several chunks and a partial one, which itself ends in a partial
+        // SIMD vector.
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(CHUNK_LEN * 3 + 509).collect()
+        };
+    }
+
+    #[test]
+    fn test_par_rgbs_to_labs() {
+        assert_eq!(par_rgbs_to_labs(&RGBS), rgbs_to_labs(&RGBS));
+    }
+
+    #[test]
+    fn test_par_rgb_bytes_to_labs() {
+        let mut bytes: Vec<u8> = RGBS.iter().flat_map(|rgb| rgb.iter().cloned()).collect();
+        bytes.push(7);
+        assert_eq!(par_rgb_bytes_to_labs(&bytes), rgb_bytes_to_labs(&bytes));
+    }
+
+    #[test]
+    fn test_par_labs_to_rgbs() {
+        let labs = rgbs_to_labs(&RGBS);
+        assert_eq!(par_labs_to_rgbs(&labs), labs_to_rgbs(&labs));
+    }
+
+    #[test]
+    fn test_par_labs_to_rgb_bytes() {
+        let labs = rgbs_to_labs(&RGBS);
+        assert_eq!(par_labs_to_rgb_bytes(&labs), labs_to_rgb_bytes(&labs));
+    }
+
+    #[test]
+    fn test_par_empty() {
+        assert!(par_rgbs_to_labs(&[]).is_empty());
+        assert!(par_labs_to_rgb_bytes(&[]).is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::portable::math::powf;
+use crate::portable::LANES;
+use crate::{Lab, CBRT_EPSILON, EPSILON, KAPPA, S_0};
+use std::simd::cmp::SimdPartialOrd;
+use std::simd::num::SimdFloat;
+use std::simd::{f32x8, Select};
+use std::{f32, iter};
+
+static BLANK_LAB: Lab = Lab {
+    l: f32::NAN,
+    a: f32::NAN,
+    b: f32::NAN,
+};
+
+pub fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
+    let chunks = labs.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(labs.len()), |mut v, labs| {
+        let rgbs = slice_labs_to_slice_rgbs(labs);
+        v.extend_from_slice(&rgbs);
+        v
+    });
+
+    // As with the other kernels, pad the remainder rather than handing it to
+    // the scalar code so that every item is computed by the same algorithm.
+    if !remainder.is_empty() {
+        let labs: Vec<Lab> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_LAB))
+            .take(LANES)
+            .collect();
+
+        let rgbs = slice_labs_to_slice_rgbs(&labs);
+        vs.extend_from_slice(&rgbs[..remainder.len()]);
+    }
+
+    vs
+}
+
+pub fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
+    let chunks = labs.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(labs.len() * 3), |mut v, labs| {
+        let rgbs = slice_labs_to_slice_rgbs(labs);
+        v.extend(rgbs.iter().flatten());
+        v
+    });
+
+    if !remainder.is_empty() {
+        let labs: Vec<Lab> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_LAB))
+            .take(LANES)
+            .collect();
+
+        let rgbs = slice_labs_to_slice_rgbs(&labs);
+        vs.extend(rgbs[..remainder.len()].iter().flatten());
+    }
+
+    vs
+}
+
+#[inline]
+fn slice_labs_to_slice_rgbs(labs: &[Lab]) -> [[u8; 3]; LANES] {
+    let mut l = [0f32; LANES];
+    let mut a = [0f32; LANES];
+    let mut b = [0f32; LANES];
+    for (i, lab) in labs[..LANES].iter().enumerate() {
+        l[i] = lab.l;
+        a[i] = lab.a;
+        b[i] = lab.b;
+    }
+    let (l, a, b) = (
+        f32x8::from_array(l),
+        f32x8::from_array(a),
+        f32x8::from_array(b),
+    );
+    let (x, y, z) = labs_to_xyzs(l, a, b);
+    let (r, g, b) = xyzs_to_rgbs(x, y, z);
+    let (r, g, b) = (clamp_round(r), clamp_round(g), clamp_round(b));
+
+    let mut rgbs = [[0u8; 3]; LANES];
+    for (i, rgb) in rgbs.iter_mut().enumerate() {
+        *rgb = [r[i], g[i], b[i]];
+    }
+    rgbs
+}
+
+/// Converts L\*, a\* and b\* to XYZ
+#[inline]
+pub fn labs_to_xyzs(l: f32x8, a: f32x8, b: f32x8) -> (f32x8, f32x8, f32x8) {
+    let fy = (l + f32x8::splat(16.0)) / f32x8::splat(116.0);
+    let fx = a / f32x8::splat(500.0) + fy;
+    let fz = fy - b / f32x8::splat(200.0);
+
+    let xr = labs_to_xyzs_map(fx);
+
+    let yr = {
+        let mask = l.simd_gt(f32x8::splat(EPSILON * KAPPA));
+        mask.select(fy * fy * fy, l / f32x8::splat(KAPPA))
+    };
+
+    let zr = labs_to_xyzs_map(fz);
+
+    (
+        xr * f32x8::splat(0.9504492182750991),
+        yr,
+        zr * f32x8::splat(1.0889166484304715),
+    )
+}
+
+#[inline]
+fn labs_to_xyzs_map(f: f32x8) -> f32x8 {
+    let mask = f.simd_gt(f32x8::splat(CBRT_EPSILON));
+    let false_branch = (f * f32x8::splat(116.0) - f32x8::splat(16.0)) / f32x8::splat(KAPPA);
+    mask.select(f * f * f, false_branch)
+}
+
+/// Converts XYZ to gamma-encoded sRGB channels in the range 0..=255
+///
+/// Values are neither rounded nor clamped, so out of gamut colors produce
+/// channels outside of that range.
+#[inline]
+pub fn xyzs_to_rgbs(x: f32x8, y: f32x8, z: f32x8) -> (f32x8, f32x8, f32x8) {
+    let r = x * f32x8::splat(3.240812398895283)
+        + y * f32x8::splat(-1.5373084456298136)
+        + z * f32x8::splat(-0.4985865229069666);
+    let g = x * f32x8::splat(-0.9692430170086407)
+        + y * f32x8::splat(1.8759663029085742)
+        + z * f32x8::splat(0.04155503085668564);
+    let b = x * f32x8::splat(0.055638398436112804)
+        + y * f32x8::splat(-0.20400746093241362)
+        + z * f32x8::splat(1.0571295702861434);
+
+    (
+        xyzs_to_rgbs_map(r),
+        xyzs_to_rgbs_map(g),
+        xyzs_to_rgbs_map(b),
+    )
+}
+
+#[inline]
+fn xyzs_to_rgbs_map(c: f32x8) -> f32x8 {
+    let mask = c.simd_gt(f32x8::splat(S_0));
+    let false_branch = c * f32x8::splat(12.92);
+    let true_branch = powf(c, f32x8::splat(1.0 / 2.4)) * f32x8::splat(1.055) - f32x8::splat(0.055);
+    mask.select(true_branch, false_branch) * f32x8::splat(255.0)
+}
+
+/// Clamps to 0..=255 and rounds to nearest, ties to even; NaN lanes become 0.
+#[inline]
+fn clamp_round(c: f32x8) -> [u8; LANES] {
+    // `simd_max` returns the number when one operand is NaN.
+    let clamped = c.simd_max(f32x8::splat(0.0)).simd_min(f32x8::splat(255.0));
+    // Adding and subtracting 2²³ leaves no bits for a fraction, so for values
+    // this small it rounds with the default ties-to-even mode. `StdFloat::round`
+    // rounds ties away from zero instead.
+    let magic = f32x8::splat(8388608.0);
+    ((clamped + magic) - magic).cast::<u8>().to_array()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{labs_to_xyzs, xyzs_to_rgbs};
+    use crate::{Lab, __scalar, lab_to_xyz, portable};
+    use approx::assert_relative_eq;
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+    use std::simd::f32x8;
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    #[test]
+    fn test_portable_labs_to_xyzs() {
+        let labs = __scalar::rgbs_to_labs(&RGBS);
+        for labs in labs.chunks_exact(8) {
+            let l = f32x8::from_array([0, 1, 2, 3, 4, 5, 6, 7].map(|i| labs[i].l));
+            let a = f32x8::from_array([0, 1, 2, 3, 4, 5, 6, 7].map(|i| labs[i].a));
+            let b = f32x8::from_array([0, 1, 2, 3, 4, 5, 6, 7].map(|i| labs[i].b));
+            let (x, y, z) = labs_to_xyzs(l, a, b);
+            let (x, y, z) = (x.to_array(), y.to_array(), z.to_array());
+            for (i, lab) in labs.iter().enumerate() {
+                let xyz = lab_to_xyz(lab);
+                assert_relative_eq!(&[x[i], y[i], z[i]][..], &xyz[..], max_relative = 0.00001);
+            }
+        }
+    }
+
+    #[test]
+    fn test_portable_xyzs_to_rgbs() {
+        let labs = __scalar::rgbs_to_labs(&RGBS);
+        let xyzs: Vec<[f32; 3]> = labs.iter().map(lab_to_xyz).collect();
+        for (xyzs, rgbs) in xyzs.chunks_exact(8).zip(RGBS.chunks_exact(8)) {
+            let channel =
+                |i: usize| f32x8::from_array([0, 1, 2, 3, 4, 5, 6, 7].map(|j| xyzs[j][i]));
+            let (r, g, b) = xyzs_to_rgbs(channel(0), channel(1), channel(2));
+            let (r, g, b) = (r.to_array(), g.to_array(), b.to_array());
+            for (i, rgb) in rgbs.iter().enumerate() {
+                let expected = rgb.map(|c| c as f32);
+                assert_relative_eq!(&[r[i], g[i], b[i]][..], &expected[..], epsilon = 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn test_portable_labs_to_rgbs() {
+        let labs = portable::rgbs_to_labs(&RGBS);
+        let rgbs = portable::labs_to_rgbs(&labs);
+        assert_eq!(rgbs.as_slice(), RGBS.as_slice());
+    }
+
+    #[test]
+    fn test_portable_labs_to_rgb_bytes() {
+        // Assert that returning a single slice of bytes returns the same values as
+        // returning them in rgb triples, including for a trailing partial chunk.
+        let labs = __scalar::rgbs_to_labs(&RGBS[..15]);
+        let rgbs = portable::labs_to_rgbs(&labs).iter().fold(
+            Vec::with_capacity(labs.len() * 3),
+            |mut acc, rgb| {
+                acc.extend_from_slice(rgb);
+                acc
+            },
+        );
+        let bytes = portable::labs_to_rgb_bytes(&labs);
+        assert_eq!(rgbs, bytes);
+    }
+
+    #[test]
+    fn test_portable_labs_to_rgbs_out_of_gamut() {
+        #[rustfmt::skip]
+        let labs = vec![
+            Lab { l: 200.0, a: 0.0, b: 0.0 },
+            Lab { l: -50.0, a: 0.0, b: 0.0 },
+            Lab { l: 1.0e9, a: -1.0e9, b: 1.0e9 },
+            Lab { l: 50.0, a: 0.0, b: 300.0 },
+        ];
+        let rgbs_non_simd = __scalar::labs_to_rgbs(&labs);
+        let rgbs_simd = portable::labs_to_rgbs(&labs);
+        assert_eq!(rgbs_simd, rgbs_non_simd);
+    }
+}
@@ -0,0 +1,225 @@
+/*
+A transliteration to portable SIMD of the SSE2-optimized log() and exp()
+functions from Simple SSE and SSE2 optimized sin, cos, log, and exp by Julien
+Pommier, available at http://gruntthepeon.free.fr/ssemath/
+
+Copyright (C) 2007  Julien Pommier
+
+Provided under the zlib license:
+
+This software is provided 'as-is', without any express or implied
+warranty.  In no event will the authors be held liable for any damages
+arising from the use of this software.
+
+Permission is granted to anyone to use this software for any purpose,
+including commercial applications, and to alter it and redistribute it
+freely, subject to the following restrictions:
+
+1. The origin of this software must not be misrepresented; you must not
+ claim that you wrote the original software. If you use this software
+ in a product, an acknowledgment in the product documentation would be
+ appreciated but is not required.
+2. Altered source versions must be plainly marked as such, and must not be
+ misrepresented as being the original software.
+3. This notice may not be removed or altered from any source distribution.
+
+The denormal and invalid argument handling follows the SSE and AVX versions
+used by the x86_64 kernels, so that all paths agree on edge cases. `simd_min`
+and `simd_max` return the other operand when one is NaN, like `minps` and
+`maxps` do for their second operand.
+*/
+
+// Constants are kept as written in the original C sources.
+#![allow(clippy::approx_constant)]
+
+use std::simd::cmp::SimdPartialOrd;
+use std::simd::num::{SimdFloat, SimdInt, SimdUint};
+use std::simd::{f32x8, i32x8, u32x8, Select, StdFloat};
+
+static X7F: i32 = 0x7f;
+static SQRTHF: f32 = 0.70710678118654752;
+static LOG_P0: f32 = 7.0376836292E-2;
+static LOG_P1: f32 = -1.1514610310E-1;
+static LOG_P2: f32 = 1.1676998740E-1;
+static LOG_P3: f32 = -1.2420140846E-1;
+static LOG_P4: f32 = 1.4249322787E-1;
+static LOG_P5: f32 = -1.6668057665E-1;
+static LOG_P6: f32 = 2.0000714765E-1;
+static LOG_P7: f32 = -2.4999993993E-1;
+static LOG_P8: f32 = 3.3333331174E-1;
+static LOG_Q1: f32 = -2.12194440e-4;
+static LOG_Q2: f32 = 0.693359375;
+
+#[inline]
+pub fn log(x: f32x8) -> f32x8 {
+    let zero = f32x8::splat(0.0);
+    let one = f32x8::splat(1.0);
+    let p5 = f32x8::splat(0.5);
+
+    let invalid_mask = x.simd_le(zero);
+
+    let min_norm_pos = f32x8::from_bits(u32x8::splat(0x00800000));
+    let mut x = x.simd_max(min_norm_pos); /* cut off denormalized stuff */
+
+    let mut emm0 = (x.to_bits() >> u32x8::splat(23)).cast::<i32>();
+
+    /* keep only the fractional part */
+    x = f32x8::from_bits((x.to_bits() & u32x8::splat(!0x7f800000)) | p5.to_bits());
+
+    emm0 -= i32x8::splat(X7F);
+    let mut e = emm0.cast::<f32>();
+
+    e += one;
+
+    let mask = x.simd_lt(f32x8::splat(SQRTHF));
+    let mut tmp = mask.select(x, zero);
+    x -= one;
+    e -= mask.select(one, zero);
+    x += tmp;
+
+    let z = x * x;
+
+    let mut y = f32x8::splat(LOG_P0);
+    y *= x;
+    y += f32x8::splat(LOG_P1);
+    y *= x;
+    y += f32x8::splat(LOG_P2);
+    y *= x;
+    y += f32x8::splat(LOG_P3);
+    y *= x;
+    y += f32x8::splat(LOG_P4);
+    y *= x;
+    y += f32x8::splat(LOG_P5);
+    y *= x;
+    y += f32x8::splat(LOG_P6);
+    y *= x;
+    y += f32x8::splat(LOG_P7);
+    y *= x;
+    y += f32x8::splat(LOG_P8);
+    y *= x;
+
+    y *= z;
+
+    tmp = e * f32x8::splat(LOG_Q1);
+    y += tmp;
+
+    tmp = z * p5;
+    y -= tmp;
+
+    tmp = e * f32x8::splat(LOG_Q2);
+    x += y;
+    x += tmp;
+    // negative arg will be NAN
+    invalid_mask.select(f32x8::splat(f32::NAN), x)
+}
+
+static EXP_HI: f32 = 88.3762626647949;
+static EXP_LO: f32 = -88.3762626647949;
+static LOG2EF: f32 = 1.44269504088896341;
+static EXP_C1: f32 = 0.693359375;
+static EXP_C2: f32 = -2.12194440e-4;
+static EXP_P0: f32 = 1.9875691500E-4;
+static EXP_P1: f32 = 1.3981999507E-3;
+static EXP_P2: f32 = 8.3334519073E-3;
+static EXP_P3: f32 = 4.1665795894E-2;
+static EXP_P4: f32 = 1.6666665459E-1;
+static EXP_P5: f32 = 5.0000001201E-1;
+
+#[inline]
+pub fn exp(x: f32x8) -> f32x8 {
+    let zero = f32x8::splat(0.0);
+    let one = f32x8::splat(1.0);
+
+    let mut x = x.simd_min(f32x8::splat(EXP_HI));
+    x = x.simd_max(f32x8::splat(EXP_LO));
+
+    /* express exp(x) as exp(g + n*log(2)) */
+    let mut fx = x * f32x8::splat(LOG2EF);
+    fx += f32x8::splat(0.5);
+
+    let tmp = fx.floor();
+
+    /* if greater, substract 1 */
+    let mask = tmp.simd_gt(fx);
+    fx = tmp - mask.select(one, zero);
+
+    let tmp = fx * f32x8::splat(EXP_C1);
+    let mut z = fx * f32x8::splat(EXP_C2);
+    x -= tmp;
+    x -= z;
+
+    z = x * x;
+
+    let mut y = f32x8::splat(EXP_P0);
+    y *= x;
+    y += f32x8::splat(EXP_P1);
+    y *= x;
+    y += f32x8::splat(EXP_P2);
+    y *= x;
+    y += f32x8::splat(EXP_P3);
+    y *= x;
+    y += f32x8::splat(EXP_P4);
+    y *= x;
+    y += f32x8::splat(EXP_P5);
+    y *= z;
+    y += x;
+    y += one;
+
+    /* build 2^n */
+    let mut emm0 = fx.cast::<i32>();
+    emm0 += i32x8::splat(X7F);
+    emm0 <<= i32x8::splat(23);
+    y * f32x8::from_bits(emm0.cast::<u32>())
+}
+
+#[inline]
+pub fn powf(x: f32x8, y: f32x8) -> f32x8 {
+    let invalid_mask = x.simd_le(f32x8::splat(0.0));
+    let result = exp(y * log(x));
+    invalid_mask.select(f32x8::splat(f32::NAN), result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{exp, log, powf};
+    use approx::assert_relative_eq;
+    use std::f32;
+    use std::simd::f32x8;
+
+    #[test]
+    fn test_log() {
+        let vals = [0.25f32, 0.5, 1.0, 2.0, 4.0, 5.0, 6.0, 10.0];
+        let scalar_result: Vec<_> = vals.iter().copied().map(f32::ln).collect();
+        let simd_result = log(f32x8::from_array(vals)).to_array();
+
+        assert_relative_eq!(scalar_result.as_slice(), &simd_result[..])
+    }
+
+    #[test]
+    fn test_negative_log_returns_nan() {
+        let vals = [-0.25, 0.5, -1.0, 2.0, -4.0, 5.0, -6.0, 10.0];
+        let simd_result = log(f32x8::from_array(vals)).to_array();
+        for (i, v) in simd_result.iter().enumerate() {
+            assert_eq!(i % 2 == 0, v.is_nan());
+        }
+    }
+
+    #[test]
+    fn test_exp() {
+        let vals = [-1.5, -0.5, 0.0, 0.5, 1.0, 2.0, 4.0, 10.0];
+        let scalar_result: Vec<_> = vals.iter().copied().map(f32::exp).collect();
+        let simd_result = exp(f32x8::from_array(vals)).to_array();
+
+        assert_relative_eq!(scalar_result.as_slice(), &simd_result[..])
+    }
+
+    #[test]
+    fn test_powf() {
+        let exponent = 4.0f32;
+        let vals = [0.25f32, 0.5, 1.0, 2.0, 4.0, 5.0, 6.0, 10.0];
+        let scalar_result: Vec<_> = vals.iter().map(|&n| n.powf(exponent)).collect();
+        let simd_result = powf(f32x8::from_array(vals), f32x8::splat(exponent)).to_array();
+
+        assert_relative_eq!(scalar_result.as_slice(), &simd_result[..])
+    }
+}
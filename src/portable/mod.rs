@@ -0,0 +1,15 @@
+//! 8-wide conversion kernels using portable SIMD (`std::simd`)
+//!
+//! Only compiled with the `portable_simd` feature, which needs a nightly
+//! compiler. The batch conversions use these kernels on targets that `simd`
+//! has no hand-written kernels for. They follow the same algorithm as the
+//! other kernels, including their log, exp and pow approximations.
+
+mod labs_to_rgbs;
+mod math;
+mod rgbs_to_labs;
+
+pub use self::labs_to_rgbs::{labs_to_rgb_bytes, labs_to_rgbs, labs_to_xyzs, xyzs_to_rgbs};
+pub use self::rgbs_to_labs::{rgb_bytes_to_labs, rgbs_to_labs, rgbs_to_xyzs, xyzs_to_labs};
+
+const LANES: usize = 8;
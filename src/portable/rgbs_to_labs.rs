@@ -0,0 +1,255 @@
+use crate::portable::math::powf;
+use crate::portable::LANES;
+use crate::{Lab, EPSILON, E_0_255, KAPPA};
+use std::iter;
+use std::simd::cmp::SimdPartialOrd;
+use std::simd::{f32x8, Select};
+
+static BLANK_RGB: [u8; 3] = [0u8; 3];
+
+pub fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
+    let chunks = rgbs.chunks_exact(LANES);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(rgbs.len()), |mut v, rgbs| {
+        let labs = slice_rgbs_to_slice_labs(rgbs);
+        v.extend_from_slice(&labs);
+        v
+    });
+
+    // As with the other kernels, pad the remainder rather than handing it to
+    // the scalar code so that every item is computed by the same algorithm.
+    if !remainder.is_empty() {
+        let rgbs: Vec<[u8; 3]> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_RGB))
+            .take(LANES)
+            .collect();
+        let labs = slice_rgbs_to_slice_labs(&rgbs);
+        vs.extend_from_slice(&labs[..remainder.len()]);
+    }
+
+    vs
+}
+
+pub fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
+    let chunks = bytes.chunks_exact(LANES * 3);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(bytes.len() / 3), |mut v, bytes| {
+        let labs = slice_bytes_to_slice_labs(bytes);
+        v.extend_from_slice(&labs);
+        v
+    });
+
+    if !remainder.is_empty() {
+        let bytes: Vec<u8> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(0u8))
+            .take(LANES * 3)
+            .collect();
+        let labs = slice_bytes_to_slice_labs(&bytes);
+        vs.extend_from_slice(&labs[..remainder.len() / 3]);
+    }
+
+    vs
+}
+
+#[inline]
+fn slice_rgbs_to_slice_labs(rgbs: &[[u8; 3]]) -> [Lab; LANES] {
+    let mut r = [0f32; LANES];
+    let mut g = [0f32; LANES];
+    let mut b = [0f32; LANES];
+    for (i, rgb) in rgbs[..LANES].iter().enumerate() {
+        r[i] = rgb[0] as f32;
+        g[i] = rgb[1] as f32;
+        b[i] = rgb[2] as f32;
+    }
+    let (r, g, b) = (
+        f32x8::from_array(r),
+        f32x8::from_array(g),
+        f32x8::from_array(b),
+    );
+    let (x, y, z) = rgbs_to_xyzs(r, g, b);
+    let (l, a, b) = xyzs_to_labs(x, y, z);
+    simd_to_lab_array(l, a, b)
+}
+
+#[inline]
+fn slice_bytes_to_slice_labs(bytes: &[u8]) -> [Lab; LANES] {
+    let mut r = [0f32; LANES];
+    let mut g = [0f32; LANES];
+    let mut b = [0f32; LANES];
+    for (i, rgb) in bytes[..LANES * 3].chunks_exact(3).enumerate() {
+        r[i] = rgb[0] as f32;
+        g[i] = rgb[1] as f32;
+        b[i] = rgb[2] as f32;
+    }
+    let (r, g, b) = (
+        f32x8::from_array(r),
+        f32x8::from_array(g),
+        f32x8::from_array(b),
+    );
+    let (x, y, z) = rgbs_to_xyzs(r, g, b);
+    let (l, a, b) = xyzs_to_labs(x, y, z);
+    simd_to_lab_array(l, a, b)
+}
+
+/// Converts gamma-encoded sRGB channels in the range 0..=255 to XYZ
+#[inline]
+pub fn rgbs_to_xyzs(r: f32x8, g: f32x8, b: f32x8) -> (f32x8, f32x8, f32x8) {
+    let r = rgbs_to_xyzs_map(r);
+    let g = rgbs_to_xyzs_map(g);
+    let b = rgbs_to_xyzs_map(b);
+
+    let x = r * f32x8::splat(0.4124108464885388)
+        + g * f32x8::splat(0.3575845678529519)
+        + b * f32x8::splat(0.18045380393360833);
+    let y = r * f32x8::splat(0.21264934272065283)
+        + g * f32x8::splat(0.7151691357059038)
+        + b * f32x8::splat(0.07218152157344333);
+    let z = r * f32x8::splat(0.019331758429150258)
+        + g * f32x8::splat(0.11919485595098397)
+        + b * f32x8::splat(0.9503900340503373);
+
+    (x, y, z)
+}
+
+#[inline]
+fn rgbs_to_xyzs_map(c: f32x8) -> f32x8 {
+    let mask = c.simd_gt(f32x8::splat(E_0_255));
+    let true_branch = {
+        const A: f32 = 0.055 * 255.0;
+        const D: f32 = 1.055 * 255.0;
+        powf((c + f32x8::splat(A)) / f32x8::splat(D), f32x8::splat(2.4))
+    };
+    let false_branch = {
+        const D: f32 = 12.92 * 255.0;
+        c / f32x8::splat(D)
+    };
+    mask.select(true_branch, false_branch)
+}
+
+/// Converts XYZ to L\*, a\* and b\*
+#[inline]
+pub fn xyzs_to_labs(x: f32x8, y: f32x8, z: f32x8) -> (f32x8, f32x8, f32x8) {
+    let x = xyzs_to_labs_map(x / f32x8::splat(0.9504492182750991));
+    let y = xyzs_to_labs_map(y);
+    let z = xyzs_to_labs_map(z / f32x8::splat(1.0889166484304715));
+
+    let l = y * f32x8::splat(116.0) + f32x8::splat(-16.0);
+    let a = (x - y) * f32x8::splat(500.0);
+    let b = (y - z) * f32x8::splat(200.0);
+
+    (l, a, b)
+}
+
+#[inline]
+fn xyzs_to_labs_map(c: f32x8) -> f32x8 {
+    let mask = c.simd_gt(f32x8::splat(EPSILON));
+    let false_branch = (c * f32x8::splat(KAPPA) + f32x8::splat(16.0)) / f32x8::splat(116.0);
+    let true_branch = powf(c, f32x8::splat(1.0 / 3.0));
+    mask.select(true_branch, false_branch)
+}
+
+#[inline]
+fn simd_to_lab_array(l: f32x8, a: f32x8, b: f32x8) -> [Lab; LANES] {
+    let (ls, as_, bs) = (l.to_array(), a.to_array(), b.to_array());
+
+    let mut labs = [Lab::default(); LANES];
+    for (i, lab) in labs.iter_mut().enumerate() {
+        *lab = Lab {
+            l: ls[i],
+            a: as_[i],
+            b: bs[i],
+        };
+    }
+    labs
+}
+
+#[cfg(test)]
+mod test {
+    use super::{rgbs_to_xyzs, xyzs_to_labs};
+    use crate::{Lab, __scalar, portable, rgb_to_xyz, xyz_to_lab};
+    use approx::assert_relative_eq;
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+    use std::simd::f32x8;
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    #[test]
+    fn test_portable_rgbs_to_xyzs() {
+        for rgbs in RGBS.chunks_exact(8) {
+            let channel =
+                |i: usize| f32x8::from_array([0, 1, 2, 3, 4, 5, 6, 7].map(|j| rgbs[j][i] as f32));
+            let (x, y, z) = rgbs_to_xyzs(channel(0), channel(1), channel(2));
+            let (x, y, z) = (x.to_array(), y.to_array(), z.to_array());
+            for (i, rgb) in rgbs.iter().enumerate() {
+                let xyz = rgb_to_xyz(rgb[0], rgb[1], rgb[2]);
+                assert_relative_eq!(&[x[i], y[i], z[i]][..], &xyz[..], max_relative = 0.00001);
+            }
+        }
+    }
+
+    #[test]
+    fn test_portable_xyzs_to_labs() {
+        for rgbs in RGBS.chunks_exact(8) {
+            let xyzs = rgbs
+                .iter()
+                .map(|rgb| rgb_to_xyz(rgb[0], rgb[1], rgb[2]))
+                .collect::<Vec<_>>();
+            let channel =
+                |i: usize| f32x8::from_array([0, 1, 2, 3, 4, 5, 6, 7].map(|j| xyzs[j][i]));
+            let (l, a, b) = xyzs_to_labs(channel(0), channel(1), channel(2));
+            let (l, a, b) = (l.to_array(), a.to_array(), b.to_array());
+            for (i, &xyz) in xyzs.iter().enumerate() {
+                let lab = Lab {
+                    l: l[i],
+                    a: a[i],
+                    b: b[i],
+                };
+                assert_relative_eq!(
+                    lab,
+                    xyz_to_lab(xyz),
+                    epsilon = 0.0001,
+                    max_relative = 0.00005
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_portable_rgbs_to_labs_many() {
+        let labs_non_simd = __scalar::rgbs_to_labs(&RGBS);
+        let labs_simd = portable::rgbs_to_labs(&RGBS);
+        // Relative error blows up for a* and b* values near zero, so also
+        // accept a small absolute error.
+        assert_relative_eq!(
+            labs_simd.as_slice(),
+            labs_non_simd.as_slice(),
+            epsilon = 0.0001,
+            max_relative = 0.00005
+        );
+    }
+
+    #[test]
+    fn test_portable_rgb_bytes_to_labs() {
+        // Assert that converting a slice of bytes and a slice of rgb triples
+        // returns the same Lab values, including for a trailing partial chunk.
+        let rgbs = &RGBS[..15];
+        let bytes: Vec<u8> = rgbs.iter().flat_map(|rgb| rgb.iter().cloned()).collect();
+
+        let labs_from_triples = portable::rgbs_to_labs(rgbs);
+        let labs_from_bytes = portable::rgb_bytes_to_labs(&bytes);
+        assert_eq!(labs_from_triples, labs_from_bytes);
+    }
+}
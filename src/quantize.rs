@@ -0,0 +1,471 @@
+//! Reducing the colors of an image to a small palette.
+//!
+//! Every method here clusters colors in Lab space, so the palette spends its
+//! colors where differences are most visible, rather than evenly through RGB
+//! as RGB median cut does.
+
+use core::mem;
+use delta_e::DeltaE;
+use palette::{coordinate, Palette};
+use Lab;
+
+/// A method for picking the colors of a quantized palette
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Quantizer {
+    /// k-means clustering, seeded with k-means++ from a random number
+    /// generator seeded with `seed`
+    ///
+    /// Gives the palettes with the least error, and takes the longest. Runs
+    /// at most `max_iterations` rounds of assigning colors to their closest
+    /// cluster and moving each cluster to the mean of its colors, stopping
+    /// early once no color changes clusters. The same seed always gives the
+    /// same palette.
+    KMeans { seed: u64, max_iterations: usize },
+    /// Median cut: repeatedly splits the box of colors with the widest
+    /// spread, along that axis, at its median color
+    MedianCut,
+    /// Like `MedianCut`, but splits the box of colors with the largest
+    /// squared error from its mean, where the sum of the two halves' errors
+    /// is smallest, which is Wu's criterion
+    ///
+    /// Unlike Wu's algorithm this works on the colors themselves, not on a
+    /// coarse histogram of them, so it's exact, but slower on large images.
+    Wu,
+}
+
+/// A palette, and which of its colors each of the quantized colors maps to
+#[derive(Debug, Clone)]
+pub struct Quantized {
+    /// The palette, of at most as many colors as asked for
+    pub palette: Palette,
+    /// The index into `palette` of the closest palette color to each of the
+    /// quantized colors, by CIE76
+    pub indices: Vec<usize>,
+}
+
+/// Picks a palette of at most `colors` colors for `labs` with `quantizer`,
+/// and maps each of `labs` to its closest palette color
+///
+/// The palette only has fewer colors than asked for if `labs` has fewer
+/// distinct colors. Every method is deterministic.
+///
+/// # Panics
+///
+/// Panics if `labs` is empty or `colors` is 0.
+///
+/// # Example
+/// ```
+/// # use lab::{quantize, rgb_bytes_to_labs, Quantizer};
+/// let pixels = [255, 0, 0, 250, 5, 0, 0, 0, 255, 10, 0, 250];
+/// let labs = rgb_bytes_to_labs(&pixels);
+/// let quantized = quantize(&labs, 2, Quantizer::KMeans { seed: 1, max_iterations: 16 });
+/// assert_eq!(quantized.palette.len(), 2);
+/// assert_eq!(quantized.indices[0], quantized.indices[1]);
+/// assert_eq!(quantized.indices[2], quantized.indices[3]);
+/// assert_ne!(quantized.indices[0], quantized.indices[2]);
+/// ```
+pub fn quantize(labs: &[Lab], colors: usize, quantizer: Quantizer) -> Quantized {
+    assert!(!labs.is_empty(), "there are no colors to quantize");
+    assert!(colors > 0, "a palette needs at least one color");
+    match quantizer {
+        Quantizer::KMeans {
+            seed,
+            max_iterations,
+        } => k_means(labs, colors, seed, max_iterations),
+        Quantizer::MedianCut => map_to_centers(labs, &cut(labs, colors, Split::Median)),
+        Quantizer::Wu => map_to_centers(labs, &cut(labs, colors, Split::Variance)),
+    }
+}
+
+fn map_to_centers(labs: &[Lab], centers: &[Lab]) -> Quantized {
+    let palette = Palette::new(centers);
+    let indices = palette.map_labs(labs, DeltaE::Cie76);
+    Quantized { palette, indices }
+}
+
+// SplitMix64, which is small, fast, and good enough for picking seeds.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in 0..1.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+// The mean of `labs`, summed in f64 so that large images don't lose
+// precision.
+fn mean<'a, I: Iterator<Item = &'a Lab>>(labs: I) -> Option<Lab> {
+    let (mut l, mut a, mut b, mut n) = (0.0, 0.0, 0.0, 0usize);
+    for lab in labs {
+        l += lab.l as f64;
+        a += lab.a as f64;
+        b += lab.b as f64;
+        n += 1;
+    }
+    if n == 0 {
+        return None;
+    }
+    let n = n as f64;
+    Some(Lab {
+        l: (l / n) as f32,
+        a: (a / n) as f32,
+        b: (b / n) as f32,
+    })
+}
+
+// k-means++: each center after a random first one is picked with a
+// probability proportional to its squared distance from the closest center
+// so far, which spreads them out.
+fn k_means_plus_plus(labs: &[Lab], colors: usize, rng: &mut Rng) -> Vec<Lab> {
+    let first = labs[(rng.next_u64() % labs.len() as u64) as usize];
+    let mut centers = vec![first];
+    let mut distances: Vec<f64> = labs
+        .iter()
+        .map(|lab| lab.squared_distance(&first) as f64)
+        .collect();
+    while centers.len() < colors {
+        let total: f64 = distances.iter().sum();
+        if total <= 0.0 {
+            // Every color is already a center.
+            break;
+        }
+        let target = rng.next_f64() * total;
+        let mut sum = 0.0;
+        let mut chosen = None;
+        for (i, &distance) in distances.iter().enumerate() {
+            if distance > 0.0 {
+                sum += distance;
+                chosen = Some(i);
+                if sum > target {
+                    break;
+                }
+            }
+        }
+        let center = labs[chosen.unwrap()];
+        for (distance, lab) in distances.iter_mut().zip(labs) {
+            *distance = distance.min(lab.squared_distance(&center) as f64);
+        }
+        centers.push(center);
+    }
+    centers
+}
+
+fn k_means(labs: &[Lab], colors: usize, seed: u64, max_iterations: usize) -> Quantized {
+    let mut rng = Rng(seed);
+    let mut centers = k_means_plus_plus(labs, colors, &mut rng);
+    let mut palette = Palette::new(&centers);
+    // No color maps to `usize::MAX`, so the first iteration never stops.
+    let mut indices = vec![usize::MAX; labs.len()];
+    let mut next = vec![0; labs.len()];
+    let mut sums = vec![([0.0f64; 3], 0usize); centers.len()];
+    let mut converged = false;
+    for _ in 0..max_iterations {
+        palette.map_labs_into(labs, DeltaE::Cie76, &mut next);
+        if next == indices {
+            converged = true;
+            break;
+        }
+        mem::swap(&mut indices, &mut next);
+
+        for sum in sums.iter_mut() {
+            *sum = ([0.0; 3], 0);
+        }
+        for (lab, &i) in labs.iter().zip(&indices) {
+            let (ref mut sum, ref mut n) = sums[i];
+            sum[0] += lab.l as f64;
+            sum[1] += lab.a as f64;
+            sum[2] += lab.b as f64;
+            *n += 1;
+        }
+        for (center, &(sum, n)) in centers.iter_mut().zip(&sums) {
+            // A cluster that lost all of its colors stays where it was.
+            if n > 0 {
+                let n = n as f64;
+                *center = Lab {
+                    l: (sum[0] / n) as f32,
+                    a: (sum[1] / n) as f32,
+                    b: (sum[2] / n) as f32,
+                };
+            }
+        }
+        palette.set_colors(&centers);
+    }
+    // Without converging, the colors haven't been mapped to the last centers.
+    if !converged {
+        palette.map_labs_into(labs, DeltaE::Cie76, &mut indices);
+    }
+    Quantized { palette, indices }
+}
+
+#[derive(Copy, Clone)]
+enum Split {
+    Median,
+    Variance,
+}
+
+// A box of colors: a range of `cut`'s colors, which splitting sorts.
+struct ColorBox {
+    start: usize,
+    end: usize,
+    // How much splitting the box is worth: its widest spread for median cut,
+    // and its squared error for Wu. 0 if it has only one distinct color.
+    priority: f64,
+    axis: usize,
+    // Where to split it, as an offset from `start`, once sorted by `axis`.
+    at: usize,
+}
+
+impl ColorBox {
+    fn new(colors: &mut [Lab], start: usize, end: usize, split: Split) -> Self {
+        let mut color_box = ColorBox {
+            start,
+            end,
+            priority: 0.0,
+            axis: 0,
+            at: 0,
+        };
+        let colors = &mut colors[start..end];
+        match split {
+            Split::Median => {
+                for axis in 0..3 {
+                    let (min, max) = colors.iter().fold(
+                        (f32::INFINITY, f32::NEG_INFINITY),
+                        |(min, max), lab| {
+                            let c = coordinate(lab, axis);
+                            (min.min(c), max.max(c))
+                        },
+                    );
+                    let spread = (max - min) as f64;
+                    if spread > color_box.priority {
+                        color_box.priority = spread;
+                        color_box.axis = axis;
+                    }
+                }
+                if color_box.priority > 0.0 {
+                    sort(colors, color_box.axis);
+                    // The median, moved past any colors equal to it so that
+                    // both halves are non-empty.
+                    let axis = color_box.axis;
+                    let median = coordinate(&colors[colors.len() / 2], axis);
+                    let below = colors
+                        .iter()
+                        .filter(|lab| coordinate(lab, axis) < median)
+                        .count();
+                    color_box.at = if below > 0 {
+                        below
+                    } else {
+                        colors
+                            .iter()
+                            .filter(|lab| coordinate(lab, axis) <= median)
+                            .count()
+                    };
+                }
+            }
+            Split::Variance => {
+                let mut best = f64::INFINITY;
+                let mut error = 0.0;
+                for axis in 0..3 {
+                    sort(colors, axis);
+                    let (split_error, at, total) = best_split(colors, axis);
+                    error = total;
+                    if split_error < best && at > 0 {
+                        best = split_error;
+                        color_box.axis = axis;
+                        color_box.at = at;
+                    }
+                }
+                if best.is_finite() {
+                    color_box.priority = error;
+                    sort(colors, color_box.axis);
+                }
+            }
+        }
+        color_box
+    }
+}
+
+fn sort(colors: &mut [Lab], axis: usize) {
+    colors.sort_by(|x, y| {
+        coordinate(x, axis)
+            .partial_cmp(&coordinate(y, axis))
+            .unwrap_or(core::cmp::Ordering::Equal)
+    });
+}
+
+// For colors sorted along `axis`, finds the split between distinct values
+// along it with the least total squared error in the two halves. Returns
+// that error, the split, or 0 if there's none, and the error of the whole.
+fn best_split(colors: &[Lab], axis: usize) -> (f64, usize, f64) {
+    // Running sums of the colors and their squares, from the start.
+    let mut sums = Vec::with_capacity(colors.len() + 1);
+    let (mut sum, mut squares) = ([0.0f64; 3], 0.0f64);
+    sums.push((sum, squares));
+    for lab in colors {
+        let (l, a, b) = (lab.l as f64, lab.a as f64, lab.b as f64);
+        sum[0] += l;
+        sum[1] += a;
+        sum[2] += b;
+        squares += l * l + a * a + b * b;
+        sums.push((sum, squares));
+    }
+    // The squared error of n colors is the sum of their squares minus the
+    // squared length of their sum over n.
+    let error = |sum: [f64; 3], squares: f64, n: usize| {
+        let n = n as f64;
+        (squares - (sum[0] * sum[0] + sum[1] * sum[1] + sum[2] * sum[2]) / n).max(0.0)
+    };
+    let (total, total_squares) = sums[colors.len()];
+    let mut best = (f64::INFINITY, 0);
+    for at in 1..colors.len() {
+        if coordinate(&colors[at - 1], axis) == coordinate(&colors[at], axis) {
+            continue;
+        }
+        let (sum, squares) = sums[at];
+        let rest = [total[0] - sum[0], total[1] - sum[1], total[2] - sum[2]];
+        let split =
+            error(sum, squares, at) + error(rest, total_squares - squares, colors.len() - at);
+        if split < best.0 {
+            best = (split, at);
+        }
+    }
+    (best.0, best.1, error(total, total_squares, colors.len()))
+}
+
+fn cut(labs: &[Lab], colors: usize, split: Split) -> Vec<Lab> {
+    let mut sorted = labs.to_vec();
+    let whole = ColorBox::new(&mut sorted, 0, labs.len(), split);
+    let mut boxes = vec![whole];
+    while boxes.len() < colors {
+        // The first of the boxes most worth splitting, if any can be.
+        let (i, _) =
+            boxes
+                .iter()
+                .enumerate()
+                .fold((None, 0.0), |(best, priority), (i, color_box)| {
+                    if color_box.priority > priority {
+                        (Some(i), color_box.priority)
+                    } else {
+                        (best, priority)
+                    }
+                });
+        let i = match i {
+            Some(i) => i,
+            None => break,
+        };
+        let color_box = boxes.swap_remove(i);
+        let middle = color_box.start + color_box.at;
+        boxes.push(ColorBox::new(&mut sorted, color_box.start, middle, split));
+        boxes.push(ColorBox::new(&mut sorted, middle, color_box.end, split));
+    }
+    boxes
+        .iter()
+        .map(|color_box| mean(sorted[color_box.start..color_box.end].iter()).unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{quantize, Quantizer};
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+    use Lab;
+
+    const QUANTIZERS: [Quantizer; 3] = [
+        Quantizer::KMeans {
+            seed: 7,
+            max_iterations: 16,
+        },
+        Quantizer::MedianCut,
+        Quantizer::Wu,
+    ];
+
+    lazy_static! {
+        static ref LABS: Vec<Lab> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard)
+                .take(2048)
+                .map(|rgb: [u8; 3]| Lab::from_rgb(&rgb))
+                .collect()
+        };
+    }
+
+    // The total squared distance of each color from its palette color.
+    fn error(labs: &[Lab], colors: usize, quantizer: Quantizer) -> f64 {
+        let quantized = quantize(labs, colors, quantizer);
+        labs.iter()
+            .zip(&quantized.indices)
+            .map(|(lab, &i)| lab.squared_distance(&quantized.palette.colors()[i]) as f64)
+            .sum()
+    }
+
+    #[test]
+    fn test_palette_size_and_indices() {
+        for &quantizer in QUANTIZERS.iter() {
+            for &colors in &[1, 2, 16, 255] {
+                let quantized = quantize(&LABS, colors, quantizer);
+                assert_eq!(quantized.palette.len(), colors, "{:?}", quantizer);
+                assert_eq!(quantized.indices.len(), LABS.len());
+                assert!(quantized.indices.iter().all(|&i| i < colors));
+            }
+        }
+    }
+
+    #[test]
+    fn test_exact_colors() {
+        // With as many colors as there are distinct ones, each is its own
+        // palette color.
+        let rgbs = [[255, 0, 0], [0, 128, 0], [20, 20, 200], [250, 250, 250]];
+        let labs: Vec<Lab> = (0..40).map(|i| Lab::from_rgb(&rgbs[i * 7 % 4])).collect();
+        for &quantizer in QUANTIZERS.iter() {
+            for &colors in &[4, 10] {
+                let quantized = quantize(&labs, colors, quantizer);
+                assert_eq!(quantized.palette.len(), 4, "{:?}", quantizer);
+                for (lab, &i) in labs.iter().zip(&quantized.indices) {
+                    assert_eq!(quantized.palette.colors()[i], *lab, "{:?}", quantizer);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        for &quantizer in QUANTIZERS.iter() {
+            let a = quantize(&LABS, 16, quantizer);
+            let b = quantize(&LABS, 16, quantizer);
+            assert_eq!(a.palette.colors(), b.palette.colors());
+            assert_eq!(a.indices, b.indices);
+        }
+    }
+
+    #[test]
+    fn test_k_means_improves_on_seeding() {
+        let seeding = Quantizer::KMeans {
+            seed: 7,
+            max_iterations: 1,
+        };
+        assert!(error(&LABS, 16, QUANTIZERS[0]) < error(&LABS, 16, seeding));
+    }
+
+    #[test]
+    fn test_wu_improves_on_median_cut() {
+        assert!(error(&LABS, 16, Quantizer::Wu) < error(&LABS, 16, Quantizer::MedianCut));
+    }
+
+    #[test]
+    #[should_panic(expected = "a palette needs at least one color")]
+    fn test_no_colors() {
+        quantize(&LABS, 0, Quantizer::MedianCut);
+    }
+}
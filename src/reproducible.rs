@@ -0,0 +1,297 @@
+//! Conversions that give bit for bit the same results on every platform.
+//!
+//! The other conversions trade reproducibility for speed: the SIMD kernels
+//! approximate `powf` differently from the scalar code, `mul3` uses fused
+//! multiply-adds only when the `fma` target feature is enabled, and `powf`
+//! itself comes from the platform's math library. The functions here use only
+//! addition, subtraction, multiplication, division and square roots of `f64`s,
+//! which IEEE 754 requires to be correctly rounded, and compute powers and
+//! roots from those with a fixed number of Newton iterations. Rust never fuses
+//! or reorders floating point operations, so the results don't depend on the
+//! target, its features or the CPU the code runs on.
+//!
+//! The one exception is 32-bit x86 without SSE2 (the `i586` targets), where
+//! the x87 FPU rounds intermediate results differently.
+
+use {clip64, Lab, CBRT_EPSILON, EPSILON, E_0_255, KAPPA, S_0};
+
+// Generated by srgb-matrices.py
+const WHITE_X: f64 = 0.9504492182750991;
+const WHITE_Z: f64 = 1.0889166484304715;
+
+/// Reproducible version of [`rgbs_to_labs`](fn.rgbs_to_labs.html)
+///
+/// The results are within 0.0001 of those of
+/// [`Lab::from_rgb`](struct.Lab.html#method.from_rgb), but are the same on
+/// every platform.
+///
+/// # Example
+/// ```
+/// # use lab::Lab;
+/// let labs = lab::reproducible_rgbs_to_labs(&[[253, 120, 138]]);
+/// assert_eq!(labs, vec![Lab { l: 66.6377, a: 52.25013, b: 14.858597 }]);
+/// ```
+pub fn reproducible_rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
+    rgbs.iter()
+        .map(|rgb| rgb_to_lab(rgb[0], rgb[1], rgb[2]))
+        .collect()
+}
+
+/// Reproducible version of [`rgb_bytes_to_labs`](fn.rgb_bytes_to_labs.html)
+pub fn reproducible_rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
+    bytes
+        .chunks_exact(3)
+        .map(|rgb| rgb_to_lab(rgb[0], rgb[1], rgb[2]))
+        .collect()
+}
+
+/// Reproducible version of [`labs_to_rgbs`](fn.labs_to_rgbs.html)
+///
+/// # Example
+/// ```
+/// # use lab::Lab;
+/// let pink = Lab { l: 66.637695, a: 52.250145, b: 14.858591 };
+/// assert_eq!(lab::reproducible_labs_to_rgbs(&[pink]), vec![[253, 120, 138]]);
+/// ```
+pub fn reproducible_labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
+    labs.iter().map(lab_to_rgb).collect()
+}
+
+/// Reproducible version of [`labs_to_rgb_bytes`](fn.labs_to_rgb_bytes.html)
+pub fn reproducible_labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
+    labs.iter()
+        .map(lab_to_rgb)
+        .fold(Vec::with_capacity(labs.len() * 3), |mut acc, rgb| {
+            acc.extend_from_slice(&rgb);
+            acc
+        })
+}
+
+// Newton's method for y = x^(1/n), for positive, normal `x`. The initial
+// guess divides the bits of `x`, and so roughly its logarithm, by n, which
+// puts it within a few percent of the root. Each iteration then roughly
+// doubles the number of correct bits, so 7 of them are plenty.
+#[inline(always)]
+fn root(x: f64, n: u32) -> f64 {
+    const ONE: u64 = 0x3ff0_0000_0000_0000;
+    let mut y = f64::from_bits(ONE - ONE / n as u64 + x.to_bits() / n as u64);
+    for _ in 0..7 {
+        let y_n_1 = (2..n).fold(y, |acc, _| acc * y);
+        y -= (y_n_1 * y - x) / (n as f64 * y_n_1);
+    }
+    y
+}
+
+#[inline(always)]
+fn rgb_to_lab(r: u8, g: u8, b: u8) -> Lab {
+    #[inline]
+    fn rgb_to_xyz_map(c: u8) -> f64 {
+        let c = c as f64;
+        if c > E_0_255 as f64 {
+            // ((c + A) / D)^2.4 = t² · t^(2/5)
+            let t = (c + 0.055 * 255.0) / (1.055 * 255.0);
+            let t_1_5 = root(t, 5);
+            t * t * t_1_5 * t_1_5
+        } else {
+            c / (12.92 * 255.0)
+        }
+    }
+
+    #[inline]
+    fn xyz_to_lab_map(c: f64) -> f64 {
+        if c > EPSILON as f64 {
+            root(c, 3)
+        } else {
+            (KAPPA as f64 * c + 16.0) / 116.0
+        }
+    }
+
+    let r = rgb_to_xyz_map(r);
+    let g = rgb_to_xyz_map(g);
+    let b = rgb_to_xyz_map(b);
+
+    // Generated by srgb-matrices.py
+    let x = r * 0.4124108464885388 + g * 0.3575845678529519 + b * 0.18045380393360833;
+    let y = r * 0.21264934272065283 + g * 0.7151691357059038 + b * 0.07218152157344333;
+    let z = r * 0.019331758429150258 + g * 0.11919485595098397 + b * 0.9503900340503373;
+
+    let x = xyz_to_lab_map(x / WHITE_X);
+    let y = xyz_to_lab_map(y);
+    let z = xyz_to_lab_map(z / WHITE_Z);
+
+    Lab {
+        l: ((116.0 * y) - 16.0) as f32,
+        a: (500.0 * (x - y)) as f32,
+        b: (200.0 * (y - z)) as f32,
+    }
+}
+
+#[inline(always)]
+fn lab_to_rgb(lab: &Lab) -> [u8; 3] {
+    #[inline]
+    fn lab_to_xyz_map(f: f64) -> f64 {
+        if f > CBRT_EPSILON as f64 {
+            f * f * f
+        } else {
+            ((f * 116.0) - 16.0) / KAPPA as f64
+        }
+    }
+
+    #[inline]
+    fn xyz_to_rgb_map(c: f64) -> u8 {
+        let c = if c > S_0 as f64 {
+            // c^(1/2.4) = (c^(1/12))^5
+            let t = root(root(c, 4), 3);
+            1.055 * (t * t * t * t * t) - 0.055
+        } else {
+            12.92 * c
+        };
+        // Rounding is exact, so `round` gives the same result everywhere.
+        (clip64(c) * 255.0).round() as u8
+    }
+
+    let l = lab.l as f64;
+    let fy = (l + 16.0) / 116.0;
+    let fx = (lab.a as f64 / 500.0) + fy;
+    let fz = fy - (lab.b as f64 / 200.0);
+    let x = lab_to_xyz_map(fx) * WHITE_X;
+    let y = if l > EPSILON as f64 * KAPPA as f64 {
+        fy * fy * fy
+    } else {
+        l / KAPPA as f64
+    };
+    let z = lab_to_xyz_map(fz) * WHITE_Z;
+
+    // Generated by srgb-matrices.py
+    let r = x * 3.240812398895283 + y * -1.5373084456298136 + z * -0.4985865229069666;
+    let g = x * -0.9692430170086407 + y * 1.8759663029085742 + z * 0.04155503085668564;
+    let b = x * 0.055638398436112804 + y * -0.20400746093241362 + z * 1.0571295702861434;
+
+    [xyz_to_rgb_map(r), xyz_to_rgb_map(g), xyz_to_rgb_map(b)]
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        reproducible_labs_to_rgb_bytes, reproducible_labs_to_rgbs, reproducible_rgb_bytes_to_labs,
+        reproducible_rgbs_to_labs, root,
+    };
+    use approx::assert_relative_eq;
+    use Lab;
+
+    // Every 5th value of each channel, which includes 0 and 255. The test
+    // colors are generated rather than random so that they don't depend on
+    // the `rand` crate's algorithms.
+    fn grid() -> Vec<[u8; 3]> {
+        (0..=255)
+            .step_by(5)
+            .flat_map(|r| (0..=255).step_by(5).map(move |g| (r, g)))
+            .flat_map(|(r, g)| (0..=255).step_by(5).map(move |b| [r, g, b]))
+            .collect()
+    }
+
+    // 64-bit FNV-1a
+    fn hash<I: IntoIterator<Item = u8>>(bytes: I) -> u64 {
+        bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |h, byte| {
+            (h ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+    }
+
+    fn hash_labs(labs: &[Lab]) -> u64 {
+        hash(labs.iter().flat_map(|lab| {
+            let bits = [lab.l.to_bits(), lab.a.to_bits(), lab.b.to_bits()];
+            bits.iter()
+                .flat_map(|b| b.to_le_bytes().to_vec())
+                .collect::<Vec<_>>()
+        }))
+    }
+
+    // The expected hashes were computed on x86_64. Every target that CI tests
+    // must produce the same ones; if a change to this module is meant to
+    // change the results, update them and say so in the changelog.
+    const RGBS_TO_LABS_HASH: u64 = 0x4748_f622_36ed_d122;
+    const LABS_TO_RGBS_HASH: u64 = 0xaa9d_234f_f86e_73e8;
+
+    #[test]
+    fn test_reproducible_rgbs_to_labs_golden() {
+        let labs = reproducible_rgbs_to_labs(&grid());
+        assert_eq!(hash_labs(&labs), RGBS_TO_LABS_HASH);
+    }
+
+    #[test]
+    fn test_reproducible_labs_to_rgbs_golden() {
+        // Lab values on a grid that covers colors out of the sRGB gamut too.
+        let labs: Vec<Lab> = (0..=40)
+            .flat_map(|l| (-32..=32).map(move |a| (l, a)))
+            .flat_map(|(l, a)| {
+                (-32..=32).map(move |b| Lab {
+                    l: l as f32 * 2.5 + 0.1,
+                    a: a as f32 * 4.0 + 0.1,
+                    b: b as f32 * 4.0 + 0.1,
+                })
+            })
+            .collect();
+        let rgbs = reproducible_labs_to_rgbs(&labs);
+        assert_eq!(
+            hash(rgbs.iter().flat_map(|rgb| rgb.iter().cloned())),
+            LABS_TO_RGBS_HASH
+        );
+    }
+
+    #[test]
+    fn test_reproducible_close_to_scalar() {
+        let rgbs = grid();
+        let labs = reproducible_rgbs_to_labs(&rgbs);
+        let expected: Vec<Lab> = rgbs.iter().map(Lab::from_rgb).collect();
+        assert_relative_eq!(labs.as_slice(), expected.as_slice(), epsilon = 0.0001);
+        let rgbs_back = reproducible_labs_to_rgbs(&labs);
+        assert_eq!(rgbs_back, rgbs);
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn test_reproducible_with_fma() {
+        use super::{lab_to_rgb, rgb_to_lab};
+
+        // The same code compiled with FMA and AVX2 enabled must give the same
+        // results, which it would not if any operations were fused.
+        #[target_feature(enable = "avx2,fma")]
+        unsafe fn rgbs_to_labs_fma(rgbs: &[[u8; 3]]) -> Vec<Lab> {
+            rgbs.iter()
+                .map(|rgb| rgb_to_lab(rgb[0], rgb[1], rgb[2]))
+                .collect()
+        }
+        #[target_feature(enable = "avx2,fma")]
+        unsafe fn labs_to_rgbs_fma(labs: &[Lab]) -> Vec<[u8; 3]> {
+            labs.iter().map(lab_to_rgb).collect()
+        }
+        if !(is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")) {
+            return;
+        }
+        let rgbs = grid();
+        let labs = reproducible_rgbs_to_labs(&rgbs);
+        assert_eq!(unsafe { rgbs_to_labs_fma(&rgbs) }, labs);
+        assert_eq!(
+            unsafe { labs_to_rgbs_fma(&labs) },
+            reproducible_labs_to_rgbs(&labs)
+        );
+    }
+
+    #[test]
+    fn test_reproducible_bytes() {
+        let rgbs = grid();
+        let bytes: Vec<u8> = rgbs.iter().flat_map(|rgb| rgb.iter().cloned()).collect();
+        let labs = reproducible_rgbs_to_labs(&rgbs);
+        assert_eq!(reproducible_rgb_bytes_to_labs(&bytes), labs);
+        assert_eq!(reproducible_labs_to_rgb_bytes(&labs), bytes);
+    }
+
+    #[test]
+    fn test_root() {
+        for &y in &[1e-60, 0.01, 0.25, 0.5, 1.0, 1.5, 2.0, 3.0, 10.0, 1e60] {
+            for n in 3..6 {
+                assert_relative_eq!(root(f64::powi(y, n), n as u32), y, max_relative = 1e-15);
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! S-CIELAB, the spatial extension of CIELAB.
+//!
+//! The eye doesn't resolve fine detail, and resolves it even less in color
+//! than in lightness, so a halftone seen from afar looks like its average
+//! color. S-CIELAB (Zhang and Wandell, 1996) blurs an image the way the eye
+//! does before converting it to Lab: XYZ is converted to an opponent space
+//! of luminance, red–green and blue–yellow, each channel is filtered with a
+//! sum of Gaussians whose size depends on how many pixels fit in a degree of
+//! the field of view, and the result is converted back to XYZ and then Lab.
+
+use {rgb_to_xyz, xyz_to_lab, Lab};
+
+// XYZ to the opponent space of Poirson and Wandell, and back.
+const OPPONENT: [[f32; 3]; 3] = [
+    [0.279, 0.72, -0.107],
+    [-0.449, 0.29, -0.077],
+    [0.086, -0.59, 0.501],
+];
+const OPPONENT_INVERSE: [[f32; 3]; 3] = [
+    [0.6265545042498855, -1.867177597834202, -0.15315637341017127],
+    [1.3698554501238207, 0.9347558241303042, 0.4362290052321003],
+    [1.5056507549053175, 1.4213237717577263, 2.536021080239948],
+];
+
+// The weight and spread in degrees of each Gaussian of the filters for
+// luminance, red–green and blue–yellow.
+const FILTERS: [&[(f32, f32)]; 3] = [
+    &[(0.921, 0.0283), (0.105, 0.133), (-0.108, 4.336)],
+    &[(0.531, 0.0392), (0.330, 0.494)],
+    &[(0.488, 0.0536), (0.371, 0.386)],
+];
+
+/// Converts a `width`×`height` image of RGB bytes to `Lab` as it is seen
+/// with `pixels_per_degree` pixels in each degree of the field of view
+///
+/// The more pixels per degree, as when the image is viewed from further
+/// away or on a denser display, the more fine detail is blurred. A uniform
+/// image converts to the same colors as [`rgb_bytes_to_labs`] gives.
+///
+/// [`rgb_bytes_to_labs`]: fn.rgb_bytes_to_labs.html
+///
+/// # Example
+/// ```
+/// # use lab::{scielab, Lab};
+/// // A checkerboard of black and white, seen from far enough away to look
+/// // like a single grey.
+/// let pixels: Vec<u8> = (0..16 * 16)
+///     .flat_map(|i| if (i % 16 + i / 16) % 2 == 0 { [0; 3] } else { [255; 3] })
+///     .collect();
+/// let labs = scielab(&pixels, 16, 16, 60.0);
+/// let grey = Lab::from_rgb(&[188, 188, 188]);
+/// assert!(labs[8 * 16 + 8].squared_distance(&grey) < 4.0);
+/// ```
+///
+/// # Panics
+///
+/// Panics if `bytes` isn't `width * height * 3` long, or if
+/// `pixels_per_degree` isn't positive.
+pub fn scielab(bytes: &[u8], width: usize, height: usize, pixels_per_degree: f32) -> Vec<Lab> {
+    let mut labs = vec![Lab::default(); width * height];
+    scielab_into(bytes, width, height, pixels_per_degree, &mut labs);
+    labs
+}
+
+/// Converts a `width`×`height` image of RGB bytes to `Lab` as
+/// [`scielab`](fn.scielab.html) does, writing the colors to `labs`
+///
+/// # Panics
+///
+/// Panics if `bytes` isn't `width * height * 3` long, `labs` isn't
+/// `width * height` long, or if `pixels_per_degree` isn't positive.
+pub fn scielab_into(
+    bytes: &[u8],
+    width: usize,
+    height: usize,
+    pixels_per_degree: f32,
+    labs: &mut [Lab],
+) {
+    assert_eq!(
+        bytes.len(),
+        width * height * 3,
+        "image length must be width * height * 3"
+    );
+    assert_eq!(labs.len(), width * height, "output length must match input");
+    assert!(
+        pixels_per_degree > 0.0,
+        "pixels per degree must be positive"
+    );
+
+    let mut channels = vec![vec![0.0; width * height]; 3];
+    for (i, rgb) in bytes.chunks_exact(3).enumerate() {
+        let opponent = transform(&OPPONENT, rgb_to_xyz(rgb[0], rgb[1], rgb[2]));
+        for (channel, &value) in channels.iter_mut().zip(&opponent) {
+            channel[i] = value;
+        }
+    }
+    for (channel, gaussians) in channels.iter_mut().zip(&FILTERS) {
+        *channel = filter(channel, width, height, gaussians, pixels_per_degree);
+    }
+    for (i, lab) in labs.iter_mut().enumerate() {
+        let opponent = [channels[0][i], channels[1][i], channels[2][i]];
+        *lab = xyz_to_lab(transform(&OPPONENT_INVERSE, opponent));
+    }
+}
+
+fn transform(matrix: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    let mut out = [0.0; 3];
+    for (out, row) in out.iter_mut().zip(matrix) {
+        *out = row[0] * v[0] + row[1] * v[1] + row[2] * v[2];
+    }
+    out
+}
+
+// Filters a channel with the weighted sum of `gaussians`, normalized so that
+// a uniform channel is left as it is. Each Gaussian is separable, so it's
+// applied to rows and then columns.
+fn filter(
+    channel: &[f32],
+    width: usize,
+    height: usize,
+    gaussians: &[(f32, f32)],
+    pixels_per_degree: f32,
+) -> Vec<f32> {
+    let total: f32 = gaussians.iter().map(|&(weight, _)| weight).sum();
+    let mut filtered = vec![0.0; channel.len()];
+    let mut rows = vec![0.0; channel.len()];
+    let mut both = vec![0.0; channel.len()];
+    for &(weight, spread) in gaussians {
+        let kernel = gaussian(spread * pixels_per_degree, pixels_per_degree);
+        convolve(channel, &mut rows, width, height, &kernel, false);
+        convolve(&rows, &mut both, width, height, &kernel, true);
+        for (filtered, value) in filtered.iter_mut().zip(&both) {
+            *filtered += weight / total * value;
+        }
+    }
+    filtered
+}
+
+// A kernel of exp(-x² / spread²), out to three spreads but no further than
+// half a degree either way, and normalized to sum to 1. The reference
+// implementation limits the filters to a degree of visual angle too; the
+// widest luminance term would otherwise reach thousands of pixels.
+fn gaussian(spread: f32, pixels_per_degree: f32) -> Vec<f32> {
+    let radius = (3.0 * spread).min(pixels_per_degree / 2.0).ceil() as isize;
+    let kernel: Vec<f32> = (-radius..=radius)
+        .map(|x| (-((x * x) as f32) / (spread * spread)).exp())
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    kernel.iter().map(|w| w / sum).collect()
+}
+
+// Convolves each row, or each column if `vertical`, with `kernel`, repeating
+// the pixels at the edges.
+fn convolve(
+    input: &[f32],
+    output: &mut [f32],
+    width: usize,
+    height: usize,
+    kernel: &[f32],
+    vertical: bool,
+) {
+    let (len, lines) = if vertical {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    let index = |line: usize, i: usize| {
+        if vertical {
+            i * width + line
+        } else {
+            line * width + i
+        }
+    };
+    let radius = (kernel.len() / 2) as isize;
+    for line in 0..lines {
+        for i in 0..len {
+            let mut sum = 0.0;
+            for (k, &w) in kernel.iter().enumerate() {
+                let j = (i as isize + k as isize - radius).clamp(0, len as isize - 1);
+                sum += w * input[index(line, j as usize)];
+            }
+            output[index(line, i)] = sum;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{scielab, scielab_into};
+    use {rgb_bytes_to_labs, DeltaE, ImageDiff, Lab};
+
+    fn checkerboard(size: usize, dark: [u8; 3], light: [u8; 3]) -> Vec<u8> {
+        (0..size * size)
+            .flat_map(|i| {
+                if (i % size + i / size).is_multiple_of(2) {
+                    dark
+                } else {
+                    light
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_uniform() {
+        let pixels: Vec<u8> = (0..12 * 8).flat_map(|_| [200, 40, 120]).collect();
+        let expected = rgb_bytes_to_labs(&pixels);
+        for &pixels_per_degree in &[1.0, 20.0, 60.0] {
+            let labs = scielab(&pixels, 12, 8, pixels_per_degree);
+            for (lab, expected) in labs.iter().zip(&expected) {
+                assert!(lab.squared_distance(expected) < 1e-4, "{:?}", lab);
+            }
+        }
+    }
+
+    #[test]
+    fn test_halftone() {
+        let halftone = checkerboard(16, [0, 0, 0], [255, 255, 255]);
+        let grey: Vec<u8> = (0..16 * 16).flat_map(|_| [188, 188, 188]).collect();
+
+        let per_pixel = ImageDiff::new(&halftone, &grey, 16, 16, DeltaE::Cie76);
+        assert!(per_pixel.distribution().mean() > 20.0);
+        let far = ImageDiff::scielab(&halftone, &grey, 16, 16, 60.0, DeltaE::Cie76);
+        assert!(far.distribution().mean() < 2.0, "{:?}", far);
+        // Up close, the checkerboard is visible again.
+        let near = ImageDiff::scielab(&halftone, &grey, 16, 16, 2.0, DeltaE::Cie76);
+        assert!(near.distribution().mean() > 10.0, "{:?}", near);
+    }
+
+    #[test]
+    fn test_color_blurs_more_than_lightness() {
+        // Alternating colors of about the same lightness, and alternating
+        // lightnesses of about the same color.
+        let red_green = checkerboard(16, [200, 80, 80], [80, 150, 80]);
+        let dark_light = checkerboard(16, [90, 90, 90], [160, 160, 160]);
+        let blurred = |bytes: &[u8]| {
+            let labs = scielab(bytes, 16, 16, 20.0);
+            DeltaE::Cie76.distance(&labs[8 * 16 + 8], &labs[8 * 16 + 9])
+        };
+        let original = |bytes: &[u8]| {
+            let labs = rgb_bytes_to_labs(bytes);
+            DeltaE::Cie76.distance(&labs[8 * 16 + 8], &labs[8 * 16 + 9])
+        };
+        let color = blurred(&red_green) / original(&red_green);
+        let lightness = blurred(&dark_light) / original(&dark_light);
+        assert!(color < lightness, "{} {}", color, lightness);
+    }
+
+    #[test]
+    fn test_large_image() {
+        // A 300×200 image, half red and half blue. The filters reach half a
+        // degree, 10 pixels here, so the colors only blur near the edge.
+        let (width, height) = (300, 200);
+        let pixels: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                if i % width < width / 2 {
+                    [220, 30, 40]
+                } else {
+                    [30, 40, 220]
+                }
+            })
+            .collect();
+        let labs = scielab(&pixels, width, height, 20.0);
+        let red = Lab::from_rgb(&[220, 30, 40]);
+        let blue = Lab::from_rgb(&[30, 40, 220]);
+        for y in (0..height).step_by(50) {
+            let row = &labs[y * width..(y + 1) * width];
+            assert!(row[width / 2 - 12].squared_distance(&red) < 1e-4);
+            assert!(row[width / 2 + 11].squared_distance(&blue) < 1e-4);
+            assert!(row[width / 2 - 1].squared_distance(&red) > 1.0);
+            assert!(row[width / 2].squared_distance(&blue) > 1.0);
+        }
+    }
+
+    #[test]
+    fn test_into() {
+        let pixels = checkerboard(6, [10, 200, 30], [250, 0, 90]);
+        let mut labs = vec![Lab::default(); 36];
+        scielab_into(&pixels, 6, 6, 30.0, &mut labs);
+        assert_eq!(labs, scielab(&pixels, 6, 6, 30.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "pixels per degree must be positive")]
+    fn test_bad_pixels_per_degree() {
+        scielab(&[0; 12], 2, 2, 0.0);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::simd::math::powf256_ps;
+use crate::simd::avx2::math::powf256_ps;
 use crate::{Lab, CBRT_EPSILON, EPSILON, KAPPA, S_0};
 use std::arch::x86_64::*;
 use std::{f32, iter, mem};
@@ -9,7 +9,8 @@ static BLANK_LAB: Lab = Lab {
     b: f32::NAN,
 };
 
-pub fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
+#[target_feature(enable = "avx2,fma")]
+pub unsafe fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
     let chunks = labs.chunks_exact(8);
     let remainder = chunks.remainder();
     let mut vs = chunks.fold(Vec::with_capacity(labs.len()), |mut v, labs| {
@@ -23,7 +24,7 @@ pub fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
     // and SIMD floating point math (especially on TravisCI for some reason?)
     // and I don't want the trailing N items to be computed by a different
     // algorithm.
-    if remainder.len() > 0 {
+    if !remainder.is_empty() {
         let labs: Vec<Lab> = remainder
             .iter()
             .cloned()
@@ -38,7 +39,8 @@ pub fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
     vs
 }
 
-pub fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
+#[target_feature(enable = "avx2,fma")]
+pub unsafe fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
     let chunks = labs.chunks_exact(8);
     let remainder = chunks.remainder();
     let mut vs = chunks.fold(Vec::with_capacity(labs.len()), |mut v, labs| {
@@ -47,7 +49,7 @@ pub fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
         v
     });
 
-    if remainder.len() > 0 {
+    if !remainder.is_empty() {
         let labs: Vec<Lab> = remainder
             .iter()
             .cloned()
@@ -63,11 +65,13 @@ pub fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
 }
 
 #[allow(dead_code)]
-pub fn labs_to_rgbs_chunk(labs: &[Lab]) -> [[u8; 3]; 8] {
+#[target_feature(enable = "avx2,fma")]
+pub unsafe fn labs_to_rgbs_chunk(labs: &[Lab]) -> [[u8; 3]; 8] {
     unsafe { slice_labs_to_slice_rgbs(labs) }
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn slice_labs_to_slice_rgbs(labs: &[Lab]) -> [[u8; 3]; 8] {
     let (l, a, b) = lab_slice_to_simd(labs);
     let (x, y, z) = labs_to_xyzs(l, a, b);
@@ -76,6 +80,7 @@ unsafe fn slice_labs_to_slice_rgbs(labs: &[Lab]) -> [[u8; 3]; 8] {
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn slice_labs_to_rgb_bytes(labs: &[Lab]) -> [u8; 8 * 3] {
     let (l, a, b) = lab_slice_to_simd(labs);
     let (x, y, z) = labs_to_xyzs(l, a, b);
@@ -84,6 +89,7 @@ unsafe fn slice_labs_to_rgb_bytes(labs: &[Lab]) -> [u8; 8 * 3] {
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn lab_slice_to_simd(labs: &[Lab]) -> (__m256, __m256, __m256) {
     let labs = &labs[..8];
     let l = _mm256_set_ps(
@@ -99,6 +105,7 @@ unsafe fn lab_slice_to_simd(labs: &[Lab]) -> (__m256, __m256, __m256) {
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn labs_to_xyzs(l: __m256, a: __m256, b: __m256) -> (__m256, __m256, __m256) {
     let fy = _mm256_div_ps(
         _mm256_add_ps(l, _mm256_set1_ps(16.0)),
@@ -122,7 +129,7 @@ unsafe fn labs_to_xyzs(l: __m256, a: __m256, b: __m256) -> (__m256, __m256, __m2
                 *el = unpacked_false_branch[i];
             }
         }
-        mem::transmute(unpacked)
+        mem::transmute::<[f32; 8], __m256>(unpacked)
     };
 
     let yr = {
@@ -137,7 +144,7 @@ unsafe fn labs_to_xyzs(l: __m256, a: __m256, b: __m256) -> (__m256, __m256, __m2
                 *el = unpacked_false_branch[i];
             }
         }
-        mem::transmute(unpacked)
+        mem::transmute::<[f32; 8], __m256>(unpacked)
     };
 
     let zr = {
@@ -155,7 +162,7 @@ unsafe fn labs_to_xyzs(l: __m256, a: __m256, b: __m256) -> (__m256, __m256, __m2
                 *el = unpacked_false_branch[i];
             }
         }
-        mem::transmute(unpacked)
+        mem::transmute::<[f32; 8], __m256>(unpacked)
     };
 
     (
@@ -166,6 +173,7 @@ unsafe fn labs_to_xyzs(l: __m256, a: __m256, b: __m256) -> (__m256, __m256, __m2
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn xyzs_to_rgbs(x: __m256, y: __m256, z: __m256) -> (__m256, __m256, __m256) {
     let r = {
         let prod_x = _mm256_mul_ps(x, _mm256_set1_ps(3.240812398895283));
@@ -193,6 +201,7 @@ unsafe fn xyzs_to_rgbs(x: __m256, y: __m256, z: __m256) -> (__m256, __m256, __m2
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn xyzs_to_rgbs_map(c: __m256) -> __m256 {
     let mask = _mm256_cmp_ps(c, _mm256_set1_ps(S_0), _CMP_GT_OQ);
     let false_branch = _mm256_mul_ps(c, _mm256_set1_ps(12.92));
@@ -206,6 +215,7 @@ unsafe fn xyzs_to_rgbs_map(c: __m256) -> __m256 {
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn simd_to_rgb_array(r: __m256, g: __m256, b: __m256) -> [[u8; 3]; 8] {
     let r: [f32; 8] = mem::transmute(_mm256_round_ps(r, _MM_FROUND_TO_NEAREST_INT));
     let g: [f32; 8] = mem::transmute(_mm256_round_ps(g, _MM_FROUND_TO_NEAREST_INT));
@@ -225,6 +235,7 @@ unsafe fn simd_to_rgb_array(r: __m256, g: __m256, b: __m256) -> [[u8; 3]; 8] {
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn simd_to_rgb_bytes(r: __m256, g: __m256, b: __m256) -> [u8; 8 * 3] {
     let r: [f32; 8] = mem::transmute(_mm256_round_ps(r, _MM_FROUND_TO_NEAREST_INT));
     let g: [f32; 8] = mem::transmute(_mm256_round_ps(g, _MM_FROUND_TO_NEAREST_INT));
@@ -247,7 +258,8 @@ unsafe fn simd_to_rgb_bytes(r: __m256, g: __m256, b: __m256) -> [u8; 8 * 3] {
 
 #[cfg(test)]
 mod test {
-    use crate::{labs_to_rgbs, simd, Lab};
+    use crate::simd::{avx2, kernel, Kernel};
+    use crate::{Lab, __scalar};
     use lazy_static::lazy_static;
     use rand;
     use rand::distributions::Standard;
@@ -263,13 +275,19 @@ mod test {
 
     #[test]
     fn test_simd_labs_to_rgbs() {
-        let labs = simd::rgbs_to_labs(&RGBS);
-        let rgbs = simd::labs_to_rgbs(&labs);
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
+        let labs = unsafe { avx2::rgbs_to_labs(&RGBS) };
+        let rgbs = unsafe { avx2::labs_to_rgbs(&labs) };
         assert_eq!(rgbs.as_slice(), RGBS.as_slice());
     }
 
     #[test]
     fn test_simd_labs_to_rgb_bytes() {
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
         // Assert that returning a single slice of bytes returns the same values as
         // returning them in rgb triples.
         #[rustfmt::skip]
@@ -283,26 +301,29 @@ mod test {
             Lab { l: 40.166237, a: 55.847153, b: -94.75334 },
             Lab { l: 28.53371, a: 58.779716, b: -44.23661 },
         ];
-        let rgbs = simd::labs_to_rgbs(&labs).iter().fold(
+        let rgbs = unsafe { avx2::labs_to_rgbs(&labs) }.iter().fold(
             Vec::with_capacity(labs.len() * 3),
             |mut acc, rgb| {
                 acc.extend_from_slice(rgb);
                 acc
             },
         );
-        let bytes = simd::labs_to_rgb_bytes(&labs);
+        let bytes = unsafe { avx2::labs_to_rgb_bytes(&labs) };
         assert_eq!(rgbs, bytes);
     }
 
     #[test]
     fn test_simd_labs_to_rgbs_unsaturated() {
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
         let labs = vec![Lab {
             l: 66.6348,
             a: 52.260696,
             b: 14.850557,
         }];
-        let rgbs_non_simd = labs_to_rgbs(&labs);
-        let rgbs_simd = simd::labs_to_rgbs(&labs);
+        let rgbs_non_simd = __scalar::labs_to_rgbs(&labs);
+        let rgbs_simd = unsafe { avx2::labs_to_rgbs(&labs) };
         assert_eq!(rgbs_simd, rgbs_non_simd);
     }
 }
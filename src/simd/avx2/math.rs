@@ -33,6 +33,9 @@ freely, subject to the following restrictions:
 
 */
 
+// Constants are kept as written in the original C sources.
+#![allow(clippy::approx_constant)]
+
 use std::arch::x86_64::*;
 use std::mem;
 
@@ -50,6 +53,7 @@ static LOG_P8: f32 = 3.3333331174E-1;
 static LOG_Q1: f32 = -2.12194440e-4;
 static LOG_Q2: f32 = 0.693359375;
 
+#[target_feature(enable = "avx2,fma")]
 pub unsafe fn log256_ps(x: __m256) -> __m256 {
     let one = _mm256_set1_ps(1.0);
     let p5 = _mm256_set1_ps(0.5);
@@ -111,7 +115,7 @@ pub unsafe fn log256_ps(x: __m256) -> __m256 {
     x = _mm256_add_ps(x, y);
     x = _mm256_add_ps(x, tmp);
     x = _mm256_or_ps(x, invalid_mask); // negative arg will be NAN
-    return x;
+    x
 }
 
 static EXP_HI: f32 = 88.3762626647949;
@@ -126,6 +130,7 @@ static EXP_P3: f32 = 4.1665795894E-2;
 static EXP_P4: f32 = 1.6666665459E-1;
 static EXP_P5: f32 = 5.0000001201E-1;
 
+#[target_feature(enable = "avx2,fma")]
 pub unsafe fn exp256_ps(x: __m256) -> __m256 {
     let one = _mm256_set1_ps(1.0);
 
@@ -177,9 +182,10 @@ pub unsafe fn exp256_ps(x: __m256) -> __m256 {
     imm0 = _mm256_slli_epi32(imm0, 23);
     let pow2n = _mm256_castsi256_ps(imm0);
     y = _mm256_mul_ps(y, pow2n);
-    return y;
+    y
 }
 
+#[target_feature(enable = "avx2,fma")]
 pub unsafe fn powf256_ps(x: __m256, y: __m256) -> __m256 {
     let invalid_mask = _mm256_cmp_ps(x, _mm256_setzero_ps(), _CMP_LE_OS);
     let result = exp256_ps(_mm256_mul_ps(y, log256_ps(x)));
@@ -189,12 +195,16 @@ pub unsafe fn powf256_ps(x: __m256, y: __m256) -> __m256 {
 #[cfg(test)]
 mod test {
     use super::{exp256_ps, log256_ps, powf256_ps};
+    use crate::simd::{kernel, Kernel};
     use approx::assert_relative_eq;
     use std::arch::x86_64::*;
     use std::{f32, mem};
 
     #[test]
     fn test_log256_ps() {
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
         let scalar_result: Vec<_> = {
             let vals: [f32; 8] = [0.5, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
             vals.iter().copied().map(f32::ln).collect()
@@ -211,6 +221,9 @@ mod test {
 
     #[test]
     fn test_negative_log_returns_nan() {
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
         let avx_result: Vec<_> = unsafe {
             let vals = _mm256_set_ps(-0.5, 1.0, -2.0, 3.0, -4.0, 5.0, -6.0, 7.0);
             let result = log256_ps(vals);
@@ -229,6 +242,9 @@ mod test {
 
     #[test]
     fn test_exp256_ps() {
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
         let scalar_result: Vec<_> = {
             let vals: [f32; 8] = [-1.5, 0.5, 1.0, 2.0, 4.0, 5.0, 6.0, 10.0];
             vals.iter().copied().map(f32::exp).collect()
@@ -245,6 +261,9 @@ mod test {
 
     #[test]
     fn test_powf256_ps() {
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
         let exponent = 4.0;
         let scalar_result: Vec<_> = {
             let vals: [f32; 8] = [0.25, 0.5, 1.0, 2.0, 4.0, 5.0, 6.0, 10.0];
@@ -1,11 +1,12 @@
-use crate::simd::math::powf256_ps;
+use crate::simd::avx2::math::powf256_ps;
 use crate::{Lab, EPSILON, E_0_255, KAPPA};
 use std::arch::x86_64::*;
 use std::{iter, mem};
 
 static BLANK_RGB: [u8; 3] = [0u8; 3];
 
-pub fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
+#[target_feature(enable = "avx2,fma")]
+pub unsafe fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
     let chunks = rgbs.chunks_exact(8);
     let remainder = chunks.remainder();
     let mut vs = chunks.fold(Vec::with_capacity(rgbs.len()), |mut v, rgbs| {
@@ -19,7 +20,7 @@ pub fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
     // and SIMD floating point math (especially on TravisCI for some reason?)
     // and I don't want the trailing N items to be computed by a different
     // algorithm.
-    if remainder.len() > 0 {
+    if !remainder.is_empty() {
         let rgbs: Vec<[u8; 3]> = remainder
             .iter()
             .cloned()
@@ -33,7 +34,8 @@ pub fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
     vs
 }
 
-pub fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
+#[target_feature(enable = "avx2,fma")]
+pub unsafe fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
     let chunks = bytes.chunks_exact(8 * 3);
     let remainder = chunks.remainder();
     let mut vs = chunks.fold(Vec::with_capacity(bytes.len() / 3), |mut v, bytes| {
@@ -42,7 +44,7 @@ pub fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
         v
     });
 
-    if remainder.len() > 0 {
+    if !remainder.is_empty() {
         let bytes: Vec<u8> = remainder
             .iter()
             .cloned()
@@ -57,10 +59,12 @@ pub fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
 }
 
 #[allow(dead_code)]
-pub fn rgbs_to_labs_chunk(rgbs: &[[u8; 3]]) -> [Lab; 8] {
+#[target_feature(enable = "avx2,fma")]
+pub unsafe fn rgbs_to_labs_chunk(rgbs: &[[u8; 3]]) -> [Lab; 8] {
     unsafe { slice_rgbs_to_slice_labs(rgbs) }
 }
 
+#[target_feature(enable = "avx2,fma")]
 unsafe fn slice_rgbs_to_slice_labs(rgbs: &[[u8; 3]]) -> [Lab; 8] {
     let (r, g, b) = rgb_bytes_to_simd(rgbs);
     let (x, y, z) = rgbs_to_xyzs(r, g, b);
@@ -69,6 +73,7 @@ unsafe fn slice_rgbs_to_slice_labs(rgbs: &[[u8; 3]]) -> [Lab; 8] {
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn slice_bytes_to_slice_labs(bytes: &[u8]) -> [Lab; 8] {
     let (r, g, b) = byte_slice_to_simd(bytes);
     let (x, y, z) = rgbs_to_xyzs(r, g, b);
@@ -77,6 +82,7 @@ unsafe fn slice_bytes_to_slice_labs(bytes: &[u8]) -> [Lab; 8] {
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn rgb_bytes_to_simd(rgbs: &[[u8; 3]]) -> (__m256, __m256, __m256) {
     let r = _mm256_set_ps(
         rgbs[0][0] as f32,
@@ -112,6 +118,8 @@ unsafe fn rgb_bytes_to_simd(rgbs: &[[u8; 3]]) -> (__m256, __m256, __m256) {
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
+#[allow(clippy::identity_op, clippy::erasing_op)]
 unsafe fn byte_slice_to_simd(bytes: &[u8]) -> (__m256, __m256, __m256) {
     let r = _mm256_set_ps(
         bytes[3 * 0] as f32,
@@ -146,6 +154,7 @@ unsafe fn byte_slice_to_simd(bytes: &[u8]) -> (__m256, __m256, __m256) {
     (r, g, b)
 }
 
+#[target_feature(enable = "avx2,fma")]
 unsafe fn rgbs_to_xyzs(r: __m256, g: __m256, b: __m256) -> (__m256, __m256, __m256) {
     let r = rgbs_to_xyzs_map(r);
     let g = rgbs_to_xyzs_map(g);
@@ -176,6 +185,7 @@ unsafe fn rgbs_to_xyzs(r: __m256, g: __m256, b: __m256) -> (__m256, __m256, __m2
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn rgbs_to_xyzs_map(c: __m256) -> __m256 {
     let mask = _mm256_cmp_ps(c, _mm256_set1_ps(E_0_255), _CMP_GT_OQ);
     let true_branch = {
@@ -192,6 +202,7 @@ unsafe fn rgbs_to_xyzs_map(c: __m256) -> __m256 {
     _mm256_blendv_ps(false_branch, true_branch, mask)
 }
 
+#[target_feature(enable = "avx2,fma")]
 unsafe fn xyzs_to_labs(x: __m256, y: __m256, z: __m256) -> (__m256, __m256, __m256) {
     let x = xyzs_to_labs_map(_mm256_div_ps(x, _mm256_set1_ps(0.9504492182750991)));
     let y = xyzs_to_labs_map(y);
@@ -208,6 +219,7 @@ unsafe fn xyzs_to_labs(x: __m256, y: __m256, z: __m256) -> (__m256, __m256, __m2
 }
 
 #[inline]
+#[target_feature(enable = "avx2,fma")]
 unsafe fn xyzs_to_labs_map(c: __m256) -> __m256 {
     let mask = _mm256_cmp_ps(c, _mm256_set1_ps(EPSILON), _CMP_GT_OQ);
     // do false branch first
@@ -222,6 +234,7 @@ unsafe fn xyzs_to_labs_map(c: __m256) -> __m256 {
     _mm256_blendv_ps(false_branch, true_branch, mask)
 }
 
+#[target_feature(enable = "avx2,fma")]
 unsafe fn simd_to_lab_array(l: __m256, a: __m256, b: __m256) -> [Lab; 8] {
     let l: [f32; 8] = mem::transmute(l);
     let a: [f32; 8] = mem::transmute(a);
@@ -263,7 +276,8 @@ unsafe fn simd_to_lab_array(l: __m256, a: __m256, b: __m256) -> [Lab; 8] {
 
 #[cfg(test)]
 mod test {
-    use crate::{rgbs_to_labs, simd};
+    use crate::simd::{avx2, kernel, Kernel};
+    use crate::__scalar;
     use approx::assert_relative_eq;
     use lazy_static::lazy_static;
     use rand;
@@ -280,6 +294,9 @@ mod test {
 
     #[test]
     fn test_simd_rgbs_to_labs() {
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
         let rgbs = vec![
             [253, 120, 138], // Lab { l: 66.6348, a: 52.260696, b: 14.850557 }
             [25, 20, 22],    // Lab { l: 6.9093895, a: 2.8204322, b: -0.45616925 }
@@ -291,8 +308,8 @@ mod test {
             [249, 165, 33],  // Lab { l: 74.29188, a: 21.827251, b: 72.75864 }
         ];
 
-        let labs_non_simd = rgbs_to_labs(&rgbs);
-        let labs_simd = simd::rgbs_to_labs(&rgbs);
+        let labs_non_simd = __scalar::rgbs_to_labs(&rgbs);
+        let labs_simd = unsafe { avx2::rgbs_to_labs(&rgbs) };
         assert_relative_eq!(
             labs_simd.as_slice(),
             labs_non_simd.as_slice(),
@@ -302,6 +319,9 @@ mod test {
 
     #[test]
     fn test_simd_rgb_bytes_to_labs() {
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
         // Assert that converting a slice of bytes and a slice of rgb triples
         // returns the same Lab values.
         let rgbs = vec![
@@ -326,27 +346,36 @@ mod test {
             249, 165, 33,  // Lab { l: 74.29188, a: 21.827251, b: 72.75864 }
         ];
 
-        let labs_from_triples = simd::rgbs_to_labs(&rgbs);
-        let labs_from_bytes = simd::rgb_bytes_to_labs(&bytes);
+        let labs_from_triples = unsafe { avx2::rgbs_to_labs(&rgbs) };
+        let labs_from_bytes = unsafe { avx2::rgb_bytes_to_labs(&bytes) };
         assert_eq!(labs_from_triples, labs_from_bytes);
     }
 
     #[test]
     fn test_simd_rgbs_to_labs_many() {
-        let labs_non_simd = rgbs_to_labs(&RGBS);
-        let labs_simd = simd::rgbs_to_labs(&RGBS);
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
+        let labs_non_simd = __scalar::rgbs_to_labs(&RGBS);
+        let labs_simd = unsafe { avx2::rgbs_to_labs(&RGBS) };
+        // Relative error blows up for a* and b* values near zero, so also
+        // accept a small absolute error.
         assert_relative_eq!(
             labs_simd.as_slice(),
             labs_non_simd.as_slice(),
+            epsilon = 0.0001,
             max_relative = 0.00005
         );
     }
 
     #[test]
     fn test_simd_rgbs_to_labs_unsaturated() {
+        if kernel() < Kernel::Avx2 {
+            return;
+        }
         let rgbs = vec![[253, 120, 138]];
-        let labs_non_simd = rgbs_to_labs(&rgbs);
-        let labs_simd = simd::rgbs_to_labs(&rgbs);
+        let labs_non_simd = __scalar::rgbs_to_labs(&rgbs);
+        let labs_simd = unsafe { avx2::rgbs_to_labs(&rgbs) };
         assert_relative_eq!(
             labs_simd.as_slice(),
             labs_non_simd.as_slice(),
@@ -0,0 +1,292 @@
+use crate::simd::avx512::math::powf512_ps;
+use crate::{Lab, CBRT_EPSILON, EPSILON, KAPPA, S_0};
+use std::arch::x86_64::*;
+use std::{f32, iter};
+
+static BLANK_LAB: Lab = Lab {
+    l: f32::NAN,
+    a: f32::NAN,
+    b: f32::NAN,
+};
+
+#[target_feature(enable = "avx512f")]
+pub unsafe fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
+    let chunks = labs.chunks_exact(16);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(labs.len()), |mut v, labs| {
+        let rgbs = slice_labs_to_slice_rgbs(labs);
+        v.extend_from_slice(&rgbs);
+        v
+    });
+
+    // As with the other kernels, pad the remainder rather than handing it to
+    // the scalar code so that every item is computed by the same algorithm.
+    if !remainder.is_empty() {
+        let labs: Vec<Lab> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_LAB))
+            .take(16)
+            .collect();
+
+        let rgbs = slice_labs_to_slice_rgbs(&labs);
+        vs.extend_from_slice(&rgbs[..remainder.len()]);
+    }
+
+    vs
+}
+
+#[target_feature(enable = "avx512f")]
+pub unsafe fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
+    let chunks = labs.chunks_exact(16);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(labs.len() * 3), |mut v, labs| {
+        let bytes = slice_labs_to_rgb_bytes(labs);
+        v.extend_from_slice(&bytes);
+        v
+    });
+
+    if !remainder.is_empty() {
+        let labs: Vec<Lab> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_LAB))
+            .take(16)
+            .collect();
+
+        let bytes = slice_labs_to_rgb_bytes(&labs);
+        vs.extend_from_slice(&bytes[..remainder.len() * 3]);
+    }
+
+    vs
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn slice_labs_to_slice_rgbs(labs: &[Lab]) -> [[u8; 3]; 16] {
+    let (l, a, b) = lab_slice_to_simd(labs);
+    let (x, y, z) = labs_to_xyzs(l, a, b);
+    let (r, g, b) = xyzs_to_rgbs(x, y, z);
+    let (r, g, b) = (clamp_round(r), clamp_round(g), clamp_round(b));
+
+    let mut rgbs = [[0u8; 3]; 16];
+    for (i, rgb) in rgbs.iter_mut().enumerate() {
+        *rgb = [r[i] as u8, g[i] as u8, b[i] as u8];
+    }
+    rgbs
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn slice_labs_to_rgb_bytes(labs: &[Lab]) -> [u8; 16 * 3] {
+    let (l, a, b) = lab_slice_to_simd(labs);
+    let (x, y, z) = labs_to_xyzs(l, a, b);
+    let (r, g, b) = xyzs_to_rgbs(x, y, z);
+    let (r, g, b) = (clamp_round(r), clamp_round(g), clamp_round(b));
+
+    let mut bytes = [0u8; 16 * 3];
+    for (i, rgb) in bytes.chunks_exact_mut(3).enumerate() {
+        rgb[0] = r[i] as u8;
+        rgb[1] = g[i] as u8;
+        rgb[2] = b[i] as u8;
+    }
+    bytes
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn lab_slice_to_simd(labs: &[Lab]) -> (__m512, __m512, __m512) {
+    let labs = &labs[..16];
+    let mut l = [0f32; 16];
+    let mut a = [0f32; 16];
+    let mut b = [0f32; 16];
+    for (i, lab) in labs.iter().enumerate() {
+        l[i] = lab.l;
+        a[i] = lab.a;
+        b[i] = lab.b;
+    }
+    (
+        _mm512_loadu_ps(l.as_ptr()),
+        _mm512_loadu_ps(a.as_ptr()),
+        _mm512_loadu_ps(b.as_ptr()),
+    )
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn labs_to_xyzs(l: __m512, a: __m512, b: __m512) -> (__m512, __m512, __m512) {
+    let fy = _mm512_div_ps(
+        _mm512_add_ps(l, _mm512_set1_ps(16.0)),
+        _mm512_set1_ps(116.0),
+    );
+    let fx = _mm512_add_ps(_mm512_div_ps(a, _mm512_set1_ps(500.0)), fy);
+    let fz = _mm512_sub_ps(fy, _mm512_div_ps(b, _mm512_set1_ps(200.0)));
+
+    let xr = labs_to_xyzs_map(fx);
+
+    let yr = {
+        let mask = _mm512_cmp_ps_mask(l, _mm512_set1_ps(EPSILON * KAPPA), _CMP_GT_OQ);
+        let false_branch = _mm512_div_ps(l, _mm512_set1_ps(KAPPA));
+        let true_branch = _mm512_mul_ps(_mm512_mul_ps(fy, fy), fy);
+        _mm512_mask_blend_ps(mask, false_branch, true_branch)
+    };
+
+    let zr = labs_to_xyzs_map(fz);
+
+    (
+        _mm512_mul_ps(xr, _mm512_set1_ps(0.9504492182750991)),
+        yr,
+        _mm512_mul_ps(zr, _mm512_set1_ps(1.0889166484304715)),
+    )
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn labs_to_xyzs_map(f: __m512) -> __m512 {
+    let mask = _mm512_cmp_ps_mask(f, _mm512_set1_ps(CBRT_EPSILON), _CMP_GT_OQ);
+    let false_branch = {
+        let temp1 = _mm512_mul_ps(f, _mm512_set1_ps(116.0));
+        let temp2 = _mm512_sub_ps(temp1, _mm512_set1_ps(16.0));
+        _mm512_div_ps(temp2, _mm512_set1_ps(KAPPA))
+    };
+    let true_branch = _mm512_mul_ps(_mm512_mul_ps(f, f), f);
+    _mm512_mask_blend_ps(mask, false_branch, true_branch)
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn xyzs_to_rgbs(x: __m512, y: __m512, z: __m512) -> (__m512, __m512, __m512) {
+    let r = {
+        let prod_x = _mm512_mul_ps(x, _mm512_set1_ps(3.240812398895283));
+        let prod_y = _mm512_mul_ps(y, _mm512_set1_ps(-1.5373084456298136));
+        let prod_z = _mm512_mul_ps(z, _mm512_set1_ps(-0.4985865229069666));
+        let sum = _mm512_add_ps(_mm512_add_ps(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map(sum)
+    };
+    let g = {
+        let prod_x = _mm512_mul_ps(x, _mm512_set1_ps(-0.9692430170086407));
+        let prod_y = _mm512_mul_ps(y, _mm512_set1_ps(1.8759663029085742));
+        let prod_z = _mm512_mul_ps(z, _mm512_set1_ps(0.04155503085668564));
+        let sum = _mm512_add_ps(_mm512_add_ps(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map(sum)
+    };
+    let b = {
+        let prod_x = _mm512_mul_ps(x, _mm512_set1_ps(0.055638398436112804));
+        let prod_y = _mm512_mul_ps(y, _mm512_set1_ps(-0.20400746093241362));
+        let prod_z = _mm512_mul_ps(z, _mm512_set1_ps(1.0571295702861434));
+        let sum = _mm512_add_ps(_mm512_add_ps(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map(sum)
+    };
+
+    (r, g, b)
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn xyzs_to_rgbs_map(c: __m512) -> __m512 {
+    let mask = _mm512_cmp_ps_mask(c, _mm512_set1_ps(S_0), _CMP_GT_OQ);
+    let false_branch = _mm512_mul_ps(c, _mm512_set1_ps(12.92));
+    let true_branch = {
+        let raised = powf512_ps(c, _mm512_set1_ps(1.0 / 2.4));
+        let temp2 = _mm512_mul_ps(raised, _mm512_set1_ps(1.055));
+        _mm512_sub_ps(temp2, _mm512_set1_ps(0.055))
+    };
+    let blended = _mm512_mask_blend_ps(mask, false_branch, true_branch);
+    _mm512_mul_ps(blended, _mm512_set1_ps(255.0))
+}
+
+/// Clamps to 0..=255 and rounds to nearest, ties to even; NaN lanes become 0.
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn clamp_round(c: __m512) -> [f32; 16] {
+    // `_mm512_max_ps` returns its second operand when either is NaN.
+    let clamped = _mm512_min_ps(_mm512_max_ps(c, _mm512_setzero_ps()), _mm512_set1_ps(255.0));
+    let mut out = [0f32; 16];
+    _mm512_storeu_ps(
+        out.as_mut_ptr(),
+        _mm512_roundscale_ps(clamped, _MM_FROUND_TO_NEAREST_INT),
+    );
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use crate::simd::{avx2, avx512, kernel, Kernel};
+    use crate::{Lab, __scalar};
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    #[test]
+    fn test_avx512_labs_to_rgbs() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        let labs = unsafe { avx512::rgbs_to_labs(&RGBS) };
+        let rgbs = unsafe { avx512::labs_to_rgbs(&labs) };
+        assert_eq!(rgbs.as_slice(), RGBS.as_slice());
+    }
+
+    #[test]
+    fn test_avx512_labs_to_rgbs_matches_avx2() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        let mut rng: rand::rngs::StdRng = rand::SeedableRng::from_seed([1u8; 32]);
+        let labs: Vec<Lab> = (0..509)
+            .map(|_| Lab {
+                l: rng.gen_range(-10.0..110.0),
+                a: rng.gen_range(-140.0..140.0),
+                b: rng.gen_range(-140.0..140.0),
+            })
+            .collect();
+        let rgbs_avx2 = unsafe { avx2::labs_to_rgbs(&labs) };
+        let rgbs_avx512 = unsafe { avx512::labs_to_rgbs(&labs) };
+        assert_eq!(rgbs_avx512, rgbs_avx2);
+    }
+
+    #[test]
+    fn test_avx512_labs_to_rgb_bytes() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        // Assert that returning a single slice of bytes returns the same values as
+        // returning them in rgb triples, including for a trailing partial chunk.
+        let labs = __scalar::rgbs_to_labs(&RGBS[..37]);
+        let rgbs = unsafe { avx512::labs_to_rgbs(&labs) }.iter().fold(
+            Vec::with_capacity(labs.len() * 3),
+            |mut acc, rgb| {
+                acc.extend_from_slice(rgb);
+                acc
+            },
+        );
+        let bytes = unsafe { avx512::labs_to_rgb_bytes(&labs) };
+        assert_eq!(rgbs, bytes);
+    }
+
+    #[test]
+    fn test_avx512_labs_to_rgbs_out_of_gamut() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        #[rustfmt::skip]
+        let labs = vec![
+            Lab { l: 200.0, a: 0.0, b: 0.0 },
+            Lab { l: -50.0, a: 0.0, b: 0.0 },
+            Lab { l: 1.0e9, a: -1.0e9, b: 1.0e9 },
+            Lab { l: 50.0, a: 0.0, b: 300.0 },
+        ];
+        let rgbs_non_simd = __scalar::labs_to_rgbs(&labs);
+        let rgbs_simd = unsafe { avx512::labs_to_rgbs(&labs) };
+        assert_eq!(rgbs_simd, rgbs_non_simd);
+    }
+}
@@ -0,0 +1,293 @@
+/*
+A widening to AVX-512F of the AVX-optimized exp() and log() functions by
+Giovanni Garberoglio, available at http://software-lisc.fbk.eu/avx_mathfun/
+
+Copyright (C) 2012 Giovanni Garberoglio
+Interdisciplinary Laboratory for Computational Science (LISC)
+Fondazione Bruno Kessler and University of Trento
+via Sommarive, 18
+I-38123 Trento (Italy)
+
+which was itself a translation of Simple SSE and SSE2 optimized sin, cos, log,
+and exp by Julien Pommier, available at http://gruntthepeon.free.fr/ssemath/
+
+Copyright (C) 2007  Julien Pommier
+
+Both are provided under the zlib license:
+
+This software is provided 'as-is', without any express or implied
+warranty.  In no event will the authors be held liable for any damages
+arising from the use of this software.
+
+Permission is granted to anyone to use this software for any purpose,
+including commercial applications, and to alter it and redistribute it
+freely, subject to the following restrictions:
+
+1. The origin of this software must not be misrepresented; you must not
+ claim that you wrote the original software. If you use this software
+ in a product, an acknowledgment in the product documentation would be
+ appreciated but is not required.
+2. Altered source versions must be plainly marked as such, and must not be
+ misrepresented as being the original software.
+3. This notice may not be removed or altered from any source distribution.
+
+AVX-512F has no bitwise operations on floating point vectors (those come with
+AVX-512DQ), so masking is done with mask registers and integer operations.
+*/
+
+// Constants are kept as written in the original C sources.
+#![allow(clippy::approx_constant)]
+
+use std::arch::x86_64::*;
+
+static X7F: i32 = 0x7f;
+static SQRTHF: f32 = 0.70710678118654752;
+static LOG_P0: f32 = 7.0376836292E-2;
+static LOG_P1: f32 = -1.1514610310E-1;
+static LOG_P2: f32 = 1.1676998740E-1;
+static LOG_P3: f32 = -1.2420140846E-1;
+static LOG_P4: f32 = 1.4249322787E-1;
+static LOG_P5: f32 = -1.6668057665E-1;
+static LOG_P6: f32 = 2.0000714765E-1;
+static LOG_P7: f32 = -2.4999993993E-1;
+static LOG_P8: f32 = 3.3333331174E-1;
+static LOG_Q1: f32 = -2.12194440e-4;
+static LOG_Q2: f32 = 0.693359375;
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+pub unsafe fn log512_ps(x: __m512) -> __m512 {
+    let one = _mm512_set1_ps(1.0);
+    let p5 = _mm512_set1_ps(0.5);
+
+    let invalid_mask = _mm512_cmp_ps_mask(x, _mm512_setzero_ps(), _CMP_LE_OS);
+
+    let min_norm_pos = _mm512_castsi512_ps(_mm512_set1_epi32(0x00800000));
+    let mut x = _mm512_max_ps(x, min_norm_pos); /* cut off denormalized stuff */
+
+    let mut imm0 = _mm512_srli_epi32(_mm512_castps_si512(x), 23);
+
+    /* keep only the fractional part */
+    let inv_mant_mask = _mm512_set1_epi32(!0x7f800000);
+    x = _mm512_castsi512_ps(_mm512_and_epi32(_mm512_castps_si512(x), inv_mant_mask));
+    x = _mm512_castsi512_ps(_mm512_or_epi32(
+        _mm512_castps_si512(x),
+        _mm512_castps_si512(p5),
+    ));
+
+    imm0 = _mm512_sub_epi32(imm0, _mm512_set1_epi32(X7F));
+    let mut e = _mm512_cvtepi32_ps(imm0);
+
+    e = _mm512_add_ps(e, one);
+
+    let mask = _mm512_cmp_ps_mask(x, _mm512_set1_ps(SQRTHF), _CMP_LT_OS);
+    let mut tmp = _mm512_maskz_mov_ps(mask, x);
+    x = _mm512_sub_ps(x, one);
+    e = _mm512_sub_ps(e, _mm512_maskz_mov_ps(mask, one));
+    x = _mm512_add_ps(x, tmp);
+
+    let z = _mm512_mul_ps(x, x);
+
+    let mut y = _mm512_set1_ps(LOG_P0);
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(LOG_P1));
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(LOG_P2));
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(LOG_P3));
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(LOG_P4));
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(LOG_P5));
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(LOG_P6));
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(LOG_P7));
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(LOG_P8));
+    y = _mm512_mul_ps(y, x);
+
+    y = _mm512_mul_ps(y, z);
+
+    tmp = _mm512_mul_ps(e, _mm512_set1_ps(LOG_Q1));
+    y = _mm512_add_ps(y, tmp);
+
+    tmp = _mm512_mul_ps(z, p5);
+    y = _mm512_sub_ps(y, tmp);
+
+    tmp = _mm512_mul_ps(e, _mm512_set1_ps(LOG_Q2));
+    x = _mm512_add_ps(x, y);
+    x = _mm512_add_ps(x, tmp);
+    // negative arg will be NAN
+    _mm512_mask_mov_ps(x, invalid_mask, _mm512_set1_ps(f32::NAN))
+}
+
+static EXP_HI: f32 = 88.3762626647949;
+static EXP_LO: f32 = -88.3762626647949;
+static LOG2EF: f32 = 1.44269504088896341;
+static EXP_C1: f32 = 0.693359375;
+static EXP_C2: f32 = -2.12194440e-4;
+static EXP_P0: f32 = 1.9875691500E-4;
+static EXP_P1: f32 = 1.3981999507E-3;
+static EXP_P2: f32 = 8.3334519073E-3;
+static EXP_P3: f32 = 4.1665795894E-2;
+static EXP_P4: f32 = 1.6666665459E-1;
+static EXP_P5: f32 = 5.0000001201E-1;
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+pub unsafe fn exp512_ps(x: __m512) -> __m512 {
+    let one = _mm512_set1_ps(1.0);
+
+    let mut x = _mm512_min_ps(x, _mm512_set1_ps(EXP_HI));
+    x = _mm512_max_ps(x, _mm512_set1_ps(EXP_LO));
+
+    /* express exp(x) as exp(g + n*log(2)) */
+    let mut fx = _mm512_mul_ps(x, _mm512_set1_ps(LOG2EF));
+    fx = _mm512_add_ps(fx, _mm512_set1_ps(0.5));
+
+    let mut tmp = _mm512_roundscale_ps(fx, _MM_FROUND_TO_NEG_INF);
+
+    /* if greater, substract 1 */
+    let mask = _mm512_cmp_ps_mask(tmp, fx, _CMP_GT_OS);
+    fx = _mm512_sub_ps(tmp, _mm512_maskz_mov_ps(mask, one));
+
+    tmp = _mm512_mul_ps(fx, _mm512_set1_ps(EXP_C1));
+    let mut z = _mm512_mul_ps(fx, _mm512_set1_ps(EXP_C2));
+    x = _mm512_sub_ps(x, tmp);
+    x = _mm512_sub_ps(x, z);
+
+    z = _mm512_mul_ps(x, x);
+
+    let mut y = _mm512_set1_ps(EXP_P0);
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(EXP_P1));
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(EXP_P2));
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(EXP_P3));
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(EXP_P4));
+    y = _mm512_mul_ps(y, x);
+    y = _mm512_add_ps(y, _mm512_set1_ps(EXP_P5));
+    y = _mm512_mul_ps(y, z);
+    y = _mm512_add_ps(y, x);
+    y = _mm512_add_ps(y, one);
+
+    /* build 2^n */
+    let mut imm0 = _mm512_cvttps_epi32(fx);
+    imm0 = _mm512_add_epi32(imm0, _mm512_set1_epi32(X7F));
+    imm0 = _mm512_slli_epi32(imm0, 23);
+    let pow2n = _mm512_castsi512_ps(imm0);
+    _mm512_mul_ps(y, pow2n)
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+pub unsafe fn powf512_ps(x: __m512, y: __m512) -> __m512 {
+    let invalid_mask = _mm512_cmp_ps_mask(x, _mm512_setzero_ps(), _CMP_LE_OS);
+    let result = exp512_ps(_mm512_mul_ps(y, log512_ps(x)));
+    _mm512_mask_mov_ps(result, invalid_mask, _mm512_set1_ps(f32::NAN))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{exp512_ps, log512_ps, powf512_ps};
+    use crate::simd::avx2::math::powf256_ps;
+    use crate::simd::{kernel, Kernel};
+    use approx::assert_relative_eq;
+    use std::arch::x86_64::*;
+    use std::f32;
+
+    static VALS: [f32; 16] = [
+        0.25, 0.5, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 10.0, 0.01, 0.1, 0.75, 1.5, 20.0, 100.0,
+    ];
+
+    unsafe fn apply(f: unsafe fn(__m512) -> __m512, vals: &[f32; 16]) -> [f32; 16] {
+        let mut out = [0f32; 16];
+        _mm512_storeu_ps(out.as_mut_ptr(), f(_mm512_loadu_ps(vals.as_ptr())));
+        out
+    }
+
+    #[test]
+    fn test_log512_ps() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        let scalar_result: Vec<_> = VALS.iter().copied().map(f32::ln).collect();
+        let avx_result = unsafe { apply(log512_ps, &VALS) };
+
+        assert_relative_eq!(scalar_result.as_slice(), &avx_result[..])
+    }
+
+    #[test]
+    fn test_negative_log_returns_nan() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        let mut vals = VALS;
+        for v in vals.iter_mut().step_by(2) {
+            *v = -*v;
+        }
+        let avx_result = unsafe { apply(log512_ps, &vals) };
+        for (i, v) in avx_result.iter().enumerate() {
+            assert_eq!(i % 2 == 0, v.is_nan());
+        }
+    }
+
+    #[test]
+    fn test_exp512_ps() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        let vals = VALS.map(|v| v.min(10.0) - 1.5);
+        let scalar_result: Vec<_> = vals.iter().copied().map(f32::exp).collect();
+        let avx_result = unsafe { apply(exp512_ps, &vals) };
+
+        assert_relative_eq!(scalar_result.as_slice(), &avx_result[..])
+    }
+
+    #[test]
+    fn test_powf512_ps() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        let exponent = 4.0;
+        let vals = VALS.map(|v| v.min(10.0));
+        let scalar_result: Vec<_> = vals.iter().map(|&n| n.powf(exponent)).collect();
+        let avx_result: Vec<_> = unsafe {
+            let mut out = [0f32; 16];
+            let result = powf512_ps(_mm512_loadu_ps(vals.as_ptr()), _mm512_set1_ps(exponent));
+            _mm512_storeu_ps(out.as_mut_ptr(), result);
+            out.to_vec()
+        };
+
+        assert_relative_eq!(
+            scalar_result.as_slice(),
+            avx_result.as_slice(),
+            max_relative = 0.000001
+        )
+    }
+
+    #[test]
+    fn test_powf512_ps_matches_avx2() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        let exponents = [1.0 / 3.0, 2.4, 1.0 / 2.4];
+        for &exponent in exponents.iter() {
+            let mut avx2_result = [0f32; 16];
+            let mut avx512_result = [0f32; 16];
+            unsafe {
+                for (vals, out) in VALS.chunks_exact(8).zip(avx2_result.chunks_exact_mut(8)) {
+                    let result =
+                        powf256_ps(_mm256_loadu_ps(vals.as_ptr()), _mm256_set1_ps(exponent));
+                    _mm256_storeu_ps(out.as_mut_ptr(), result);
+                }
+                let result = powf512_ps(_mm512_loadu_ps(VALS.as_ptr()), _mm512_set1_ps(exponent));
+                _mm512_storeu_ps(avx512_result.as_mut_ptr(), result);
+            }
+            assert_eq!(avx512_result, avx2_result);
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! 16-wide conversion kernels using AVX-512F
+//!
+//! These perform the same sequence of floating point operations as the AVX2
+//! kernels, so both produce identical results.
+
+mod labs_to_rgbs;
+mod math;
+mod rgbs_to_labs;
+
+pub use self::labs_to_rgbs::{labs_to_rgb_bytes, labs_to_rgbs};
+pub use self::rgbs_to_labs::{rgb_bytes_to_labs, rgbs_to_labs};
@@ -0,0 +1,293 @@
+use crate::simd::avx512::math::powf512_ps;
+use crate::{Lab, EPSILON, E_0_255, KAPPA};
+use std::arch::x86_64::*;
+use std::iter;
+
+static BLANK_RGB: [u8; 3] = [0u8; 3];
+
+#[target_feature(enable = "avx512f")]
+pub unsafe fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
+    let chunks = rgbs.chunks_exact(16);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(rgbs.len()), |mut v, rgbs| {
+        let labs = slice_rgbs_to_slice_labs(rgbs);
+        v.extend_from_slice(&labs);
+        v
+    });
+
+    // As with the other kernels, pad the remainder rather than handing it to
+    // the scalar code so that every item is computed by the same algorithm.
+    if !remainder.is_empty() {
+        let rgbs: Vec<[u8; 3]> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_RGB))
+            .take(16)
+            .collect();
+        let labs = slice_rgbs_to_slice_labs(&rgbs);
+        vs.extend_from_slice(&labs[..remainder.len()]);
+    }
+
+    vs
+}
+
+#[target_feature(enable = "avx512f")]
+pub unsafe fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
+    let chunks = bytes.chunks_exact(16 * 3);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(bytes.len() / 3), |mut v, bytes| {
+        let labs = slice_bytes_to_slice_labs(bytes);
+        v.extend_from_slice(&labs);
+        v
+    });
+
+    if !remainder.is_empty() {
+        let bytes: Vec<u8> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(0u8))
+            .take(16 * 3)
+            .collect();
+        let labs = slice_bytes_to_slice_labs(&bytes);
+        vs.extend_from_slice(&labs[..remainder.len() / 3]);
+    }
+
+    vs
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn slice_rgbs_to_slice_labs(rgbs: &[[u8; 3]]) -> [Lab; 16] {
+    let (r, g, b) = rgb_bytes_to_simd(rgbs);
+    let (x, y, z) = rgbs_to_xyzs(r, g, b);
+    let (l, a, b) = xyzs_to_labs(x, y, z);
+    simd_to_lab_array(l, a, b)
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn slice_bytes_to_slice_labs(bytes: &[u8]) -> [Lab; 16] {
+    let (r, g, b) = byte_slice_to_simd(bytes);
+    let (x, y, z) = rgbs_to_xyzs(r, g, b);
+    let (l, a, b) = xyzs_to_labs(x, y, z);
+    simd_to_lab_array(l, a, b)
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn rgb_bytes_to_simd(rgbs: &[[u8; 3]]) -> (__m512, __m512, __m512) {
+    let rgbs = &rgbs[..16];
+    let mut r = [0f32; 16];
+    let mut g = [0f32; 16];
+    let mut b = [0f32; 16];
+    for (i, rgb) in rgbs.iter().enumerate() {
+        r[i] = rgb[0] as f32;
+        g[i] = rgb[1] as f32;
+        b[i] = rgb[2] as f32;
+    }
+    (
+        _mm512_loadu_ps(r.as_ptr()),
+        _mm512_loadu_ps(g.as_ptr()),
+        _mm512_loadu_ps(b.as_ptr()),
+    )
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn byte_slice_to_simd(bytes: &[u8]) -> (__m512, __m512, __m512) {
+    let bytes = &bytes[..16 * 3];
+    let mut r = [0f32; 16];
+    let mut g = [0f32; 16];
+    let mut b = [0f32; 16];
+    for (i, rgb) in bytes.chunks_exact(3).enumerate() {
+        r[i] = rgb[0] as f32;
+        g[i] = rgb[1] as f32;
+        b[i] = rgb[2] as f32;
+    }
+    (
+        _mm512_loadu_ps(r.as_ptr()),
+        _mm512_loadu_ps(g.as_ptr()),
+        _mm512_loadu_ps(b.as_ptr()),
+    )
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn rgbs_to_xyzs(r: __m512, g: __m512, b: __m512) -> (__m512, __m512, __m512) {
+    let r = rgbs_to_xyzs_map(r);
+    let g = rgbs_to_xyzs_map(g);
+    let b = rgbs_to_xyzs_map(b);
+
+    let x = {
+        let prod_r = _mm512_mul_ps(r, _mm512_set1_ps(0.4124108464885388));
+        let prod_g = _mm512_mul_ps(g, _mm512_set1_ps(0.3575845678529519));
+        let prod_b = _mm512_mul_ps(b, _mm512_set1_ps(0.18045380393360833));
+        _mm512_add_ps(_mm512_add_ps(prod_r, prod_g), prod_b)
+    };
+
+    let y = {
+        let prod_r = _mm512_mul_ps(r, _mm512_set1_ps(0.21264934272065283));
+        let prod_g = _mm512_mul_ps(g, _mm512_set1_ps(0.7151691357059038));
+        let prod_b = _mm512_mul_ps(b, _mm512_set1_ps(0.07218152157344333));
+        _mm512_add_ps(_mm512_add_ps(prod_r, prod_g), prod_b)
+    };
+
+    let z = {
+        let prod_r = _mm512_mul_ps(r, _mm512_set1_ps(0.019331758429150258));
+        let prod_g = _mm512_mul_ps(g, _mm512_set1_ps(0.11919485595098397));
+        let prod_b = _mm512_mul_ps(b, _mm512_set1_ps(0.9503900340503373));
+        _mm512_add_ps(_mm512_add_ps(prod_r, prod_g), prod_b)
+    };
+
+    (x, y, z)
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn rgbs_to_xyzs_map(c: __m512) -> __m512 {
+    let mask = _mm512_cmp_ps_mask(c, _mm512_set1_ps(E_0_255), _CMP_GT_OQ);
+    let true_branch = {
+        const A: f32 = 0.055 * 255.0;
+        const D: f32 = 1.055 * 255.0;
+        let t0 = _mm512_div_ps(_mm512_add_ps(c, _mm512_set1_ps(A)), _mm512_set1_ps(D));
+        powf512_ps(t0, _mm512_set1_ps(2.4))
+    };
+
+    let false_branch = {
+        const D: f32 = 12.92 * 255.0;
+        _mm512_div_ps(c, _mm512_set1_ps(D))
+    };
+    _mm512_mask_blend_ps(mask, false_branch, true_branch)
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn xyzs_to_labs(x: __m512, y: __m512, z: __m512) -> (__m512, __m512, __m512) {
+    let x = xyzs_to_labs_map(_mm512_div_ps(x, _mm512_set1_ps(0.9504492182750991)));
+    let y = xyzs_to_labs_map(y);
+    let z = xyzs_to_labs_map(_mm512_div_ps(z, _mm512_set1_ps(1.0889166484304715)));
+
+    let l = _mm512_add_ps(
+        _mm512_mul_ps(y, _mm512_set1_ps(116.0)),
+        _mm512_set1_ps(-16.0),
+    );
+    let a = _mm512_mul_ps(_mm512_sub_ps(x, y), _mm512_set1_ps(500.0));
+    let b = _mm512_mul_ps(_mm512_sub_ps(y, z), _mm512_set1_ps(200.0));
+
+    (l, a, b)
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn xyzs_to_labs_map(c: __m512) -> __m512 {
+    let mask = _mm512_cmp_ps_mask(c, _mm512_set1_ps(EPSILON), _CMP_GT_OQ);
+    // do false branch first
+    let false_branch = _mm512_div_ps(
+        _mm512_add_ps(
+            _mm512_mul_ps(c, _mm512_set1_ps(KAPPA)),
+            _mm512_set1_ps(16.0),
+        ),
+        _mm512_set1_ps(116.0),
+    );
+    let true_branch = powf512_ps(c, _mm512_set1_ps(1.0 / 3.0));
+    _mm512_mask_blend_ps(mask, false_branch, true_branch)
+}
+
+#[inline]
+#[target_feature(enable = "avx512f")]
+unsafe fn simd_to_lab_array(l: __m512, a: __m512, b: __m512) -> [Lab; 16] {
+    let mut ls = [0f32; 16];
+    let mut as_ = [0f32; 16];
+    let mut bs = [0f32; 16];
+    _mm512_storeu_ps(ls.as_mut_ptr(), l);
+    _mm512_storeu_ps(as_.as_mut_ptr(), a);
+    _mm512_storeu_ps(bs.as_mut_ptr(), b);
+
+    let mut labs = [Lab::default(); 16];
+    for (i, lab) in labs.iter_mut().enumerate() {
+        *lab = Lab {
+            l: ls[i],
+            a: as_[i],
+            b: bs[i],
+        };
+    }
+    labs
+}
+
+#[cfg(test)]
+mod test {
+    use crate::simd::{avx2, avx512, kernel, Kernel};
+    use crate::__scalar;
+    use approx::assert_relative_eq;
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    #[test]
+    fn test_avx512_rgbs_to_labs_many() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        let labs_non_simd = __scalar::rgbs_to_labs(&RGBS);
+        let labs_simd = unsafe { avx512::rgbs_to_labs(&RGBS) };
+        // Relative error blows up for a* and b* values near zero, so also
+        // accept a small absolute error.
+        assert_relative_eq!(
+            labs_simd.as_slice(),
+            labs_non_simd.as_slice(),
+            epsilon = 0.0001,
+            max_relative = 0.00005
+        );
+    }
+
+    #[test]
+    fn test_avx512_rgbs_to_labs_matches_avx2() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        // 509 items leaves a trailing partial chunk for both kernels.
+        let rgbs = &RGBS[..509];
+        let labs_avx2 = unsafe { avx2::rgbs_to_labs(rgbs) };
+        let labs_avx512 = unsafe { avx512::rgbs_to_labs(rgbs) };
+        assert_eq!(labs_avx512, labs_avx2);
+    }
+
+    #[test]
+    fn test_avx512_rgb_bytes_to_labs() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        // Assert that converting a slice of bytes and a slice of rgb triples
+        // returns the same Lab values, including for a trailing partial chunk.
+        let rgbs = &RGBS[..37];
+        let bytes: Vec<u8> = rgbs.iter().flat_map(|rgb| rgb.iter().cloned()).collect();
+
+        let labs_from_triples = unsafe { avx512::rgbs_to_labs(rgbs) };
+        let labs_from_bytes = unsafe { avx512::rgb_bytes_to_labs(&bytes) };
+        assert_eq!(labs_from_triples, labs_from_bytes);
+    }
+
+    #[test]
+    fn test_avx512_rgbs_to_labs_unsaturated() {
+        if kernel() < Kernel::Avx512 {
+            return;
+        }
+        let rgbs = vec![[253, 120, 138]];
+        let labs_non_simd = __scalar::rgbs_to_labs(&rgbs);
+        let labs_simd = unsafe { avx512::rgbs_to_labs(&rgbs) };
+        assert_relative_eq!(
+            labs_simd.as_slice(),
+            labs_non_simd.as_slice(),
+            max_relative = 0.00002
+        );
+    }
+}
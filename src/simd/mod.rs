@@ -1,11 +1,180 @@
-//! Experimental functions for parallel conversion using AVX and SSE4.1
+//! Parallel conversion functions using SSE2, SSE4.1, AVX2 and AVX-512F on
+//! x86_64, NEON on AArch64, and SIMD128 on WebAssembly
 //!
-//! This module is conditionally compiled by the cfg gate
-//! `#[cfg(target_arch = "x86_64")]`
+//! This module is only compiled for x86_64, AArch64, and wasm32 with the
+//! `simd128` target feature enabled. The x86_64 and AArch64 kernels are
+//! compiled with `#[target_feature]` attributes regardless of the build
+//! target, so the functions exported from here check at runtime which
+//! instructions the CPU supports and use the widest kernel available. SSE2
+//! and NEON are part of the x86_64 and AArch64 baselines respectively, so
+//! there is always a kernel to fall back to. Detection only happens once; its
+//! result is cached.
 
-mod labs_to_rgbs;
-mod math;
-mod rgbs_to_labs;
+#[cfg(target_arch = "x86_64")]
+mod avx2;
+#[cfg(target_arch = "x86_64")]
+mod avx512;
+#[cfg(target_arch = "aarch64")]
+mod neon;
+#[cfg(target_arch = "wasm32")]
+mod simd128;
+#[cfg(target_arch = "x86_64")]
+mod sse;
 
-pub use self::labs_to_rgbs::{labs_to_rgb_bytes, labs_to_rgbs, labs_to_rgbs_chunk};
-pub use self::rgbs_to_labs::{rgb_bytes_to_labs, rgbs_to_labs, rgbs_to_labs_chunk};
+use std::sync::atomic::{AtomicU8, Ordering};
+use Lab;
+
+/// The set of conversion kernels chosen for the running CPU
+///
+/// Variants are ordered by width, so a CPU that supports a kernel also
+/// supports every kernel that compares less than it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub(crate) enum Kernel {
+    #[cfg(target_arch = "x86_64")]
+    Sse2 = 1,
+    #[cfg(target_arch = "x86_64")]
+    Sse41 = 2,
+    #[cfg(target_arch = "x86_64")]
+    Avx2 = 3,
+    #[cfg(target_arch = "x86_64")]
+    Avx512 = 4,
+    #[cfg(target_arch = "aarch64")]
+    Neon = 5,
+    #[cfg(target_arch = "wasm32")]
+    Simd128 = 6,
+}
+
+const UNDETECTED: u8 = 0;
+
+static KERNEL: AtomicU8 = AtomicU8::new(UNDETECTED);
+
+#[cfg(target_arch = "x86_64")]
+fn detect() -> Kernel {
+    if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+        if is_x86_feature_detected!("avx512f") {
+            Kernel::Avx512
+        } else {
+            Kernel::Avx2
+        }
+    } else if is_x86_feature_detected!("sse4.1") {
+        Kernel::Sse41
+    } else {
+        Kernel::Sse2
+    }
+}
+
+// NEON is enabled on every AArch64 target that has std.
+#[cfg(target_arch = "aarch64")]
+fn detect() -> Kernel {
+    Kernel::Neon
+}
+
+// This module is only compiled for wasm32 when simd128 is enabled.
+#[cfg(target_arch = "wasm32")]
+fn detect() -> Kernel {
+    Kernel::Simd128
+}
+
+/// Returns the best kernel supported by the running CPU, detecting it on the
+/// first call.
+#[inline]
+pub(crate) fn kernel() -> Kernel {
+    match KERNEL.load(Ordering::Relaxed) {
+        UNDETECTED => {
+            let kernel = detect();
+            KERNEL.store(kernel as u8, Ordering::Relaxed);
+            kernel
+        }
+        #[cfg(target_arch = "x86_64")]
+        k if k == Kernel::Avx512 as u8 => Kernel::Avx512,
+        #[cfg(target_arch = "x86_64")]
+        k if k == Kernel::Avx2 as u8 => Kernel::Avx2,
+        #[cfg(target_arch = "x86_64")]
+        k if k == Kernel::Sse41 as u8 => Kernel::Sse41,
+        #[cfg(target_arch = "x86_64")]
+        _ => Kernel::Sse2,
+        #[cfg(target_arch = "aarch64")]
+        _ => Kernel::Neon,
+        #[cfg(target_arch = "wasm32")]
+        _ => Kernel::Simd128,
+    }
+}
+
+pub fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
+    match kernel() {
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx512 => unsafe { avx512::rgbs_to_labs(rgbs) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx2 => unsafe { avx2::rgbs_to_labs(rgbs) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Sse41 => unsafe { sse::sse41::rgbs_to_labs(rgbs) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Sse2 => sse::sse2::rgbs_to_labs(rgbs),
+        #[cfg(target_arch = "aarch64")]
+        Kernel::Neon => unsafe { neon::rgbs_to_labs(rgbs) },
+        #[cfg(target_arch = "wasm32")]
+        Kernel::Simd128 => simd128::rgbs_to_labs(rgbs),
+    }
+}
+
+pub fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
+    match kernel() {
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx512 => unsafe { avx512::rgb_bytes_to_labs(bytes) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx2 => unsafe { avx2::rgb_bytes_to_labs(bytes) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Sse41 => unsafe { sse::sse41::rgb_bytes_to_labs(bytes) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Sse2 => sse::sse2::rgb_bytes_to_labs(bytes),
+        #[cfg(target_arch = "aarch64")]
+        Kernel::Neon => unsafe { neon::rgb_bytes_to_labs(bytes) },
+        #[cfg(target_arch = "wasm32")]
+        Kernel::Simd128 => simd128::rgb_bytes_to_labs(bytes),
+    }
+}
+
+pub fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
+    match kernel() {
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx512 => unsafe { avx512::labs_to_rgbs(labs) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx2 => unsafe { avx2::labs_to_rgbs(labs) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Sse41 => unsafe { sse::sse41::labs_to_rgbs(labs) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Sse2 => sse::sse2::labs_to_rgbs(labs),
+        #[cfg(target_arch = "aarch64")]
+        Kernel::Neon => unsafe { neon::labs_to_rgbs(labs) },
+        #[cfg(target_arch = "wasm32")]
+        Kernel::Simd128 => simd128::labs_to_rgbs(labs),
+    }
+}
+
+pub fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
+    match kernel() {
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx512 => unsafe { avx512::labs_to_rgb_bytes(labs) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Avx2 => unsafe { avx2::labs_to_rgb_bytes(labs) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Sse41 => unsafe { sse::sse41::labs_to_rgb_bytes(labs) },
+        #[cfg(target_arch = "x86_64")]
+        Kernel::Sse2 => sse::sse2::labs_to_rgb_bytes(labs),
+        #[cfg(target_arch = "aarch64")]
+        Kernel::Neon => unsafe { neon::labs_to_rgb_bytes(labs) },
+        #[cfg(target_arch = "wasm32")]
+        Kernel::Simd128 => simd128::labs_to_rgb_bytes(labs),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{detect, kernel};
+
+    #[test]
+    fn test_kernel_is_cached() {
+        assert_eq!(kernel(), detect());
+        assert_eq!(kernel(), detect());
+    }
+}
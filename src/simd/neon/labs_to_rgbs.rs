@@ -0,0 +1,275 @@
+use crate::simd::neon::math::powf_ps;
+use crate::{Lab, CBRT_EPSILON, EPSILON, KAPPA, S_0};
+use std::arch::aarch64::*;
+use std::{f32, iter};
+
+static BLANK_LAB: Lab = Lab {
+    l: f32::NAN,
+    a: f32::NAN,
+    b: f32::NAN,
+};
+
+#[target_feature(enable = "neon")]
+pub unsafe fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
+    let chunks = labs.chunks_exact(4);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(labs.len()), |mut v, labs| {
+        let rgbs = slice_labs_to_slice_rgbs(labs);
+        v.extend_from_slice(&rgbs);
+        v
+    });
+
+    // As with the x86_64 kernels, pad the remainder rather than handing it to
+    // the scalar code so that every item is computed by the same algorithm.
+    if !remainder.is_empty() {
+        let labs: Vec<Lab> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_LAB))
+            .take(4)
+            .collect();
+
+        let rgbs = slice_labs_to_slice_rgbs(&labs);
+        vs.extend_from_slice(&rgbs[..remainder.len()]);
+    }
+
+    vs
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
+    let chunks = labs.chunks_exact(4);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(labs.len() * 3), |mut v, labs| {
+        let bytes = slice_labs_to_rgb_bytes(labs);
+        v.extend_from_slice(&bytes);
+        v
+    });
+
+    if !remainder.is_empty() {
+        let labs: Vec<Lab> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_LAB))
+            .take(4)
+            .collect();
+
+        let bytes = slice_labs_to_rgb_bytes(&labs);
+        vs.extend_from_slice(&bytes[..remainder.len() * 3]);
+    }
+
+    vs
+}
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn slice_labs_to_slice_rgbs(labs: &[Lab]) -> [[u8; 3]; 4] {
+    let (l, a, b) = lab_slice_to_simd(labs);
+    let (x, y, z) = labs_to_xyzs(l, a, b);
+    let (r, g, b) = xyzs_to_rgbs(x, y, z);
+    let (r, g, b) = (clamp_round(r), clamp_round(g), clamp_round(b));
+
+    let mut rgbs = [[0u8; 3]; 4];
+    for (i, rgb) in rgbs.iter_mut().enumerate() {
+        *rgb = [r[i] as u8, g[i] as u8, b[i] as u8];
+    }
+    rgbs
+}
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn slice_labs_to_rgb_bytes(labs: &[Lab]) -> [u8; 4 * 3] {
+    let (l, a, b) = lab_slice_to_simd(labs);
+    let (x, y, z) = labs_to_xyzs(l, a, b);
+    let (r, g, b) = xyzs_to_rgbs(x, y, z);
+    let (r, g, b) = (clamp_round(r), clamp_round(g), clamp_round(b));
+
+    let mut bytes = [0u8; 4 * 3];
+    for (i, rgb) in bytes.chunks_exact_mut(3).enumerate() {
+        rgb[0] = r[i] as u8;
+        rgb[1] = g[i] as u8;
+        rgb[2] = b[i] as u8;
+    }
+    bytes
+}
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn lab_slice_to_simd(labs: &[Lab]) -> (float32x4_t, float32x4_t, float32x4_t) {
+    let labs = &labs[..4];
+    let l = [labs[0].l, labs[1].l, labs[2].l, labs[3].l];
+    let a = [labs[0].a, labs[1].a, labs[2].a, labs[3].a];
+    let b = [labs[0].b, labs[1].b, labs[2].b, labs[3].b];
+    (
+        vld1q_f32(l.as_ptr()),
+        vld1q_f32(a.as_ptr()),
+        vld1q_f32(b.as_ptr()),
+    )
+}
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn labs_to_xyzs(
+    l: float32x4_t,
+    a: float32x4_t,
+    b: float32x4_t,
+) -> (float32x4_t, float32x4_t, float32x4_t) {
+    let fy = vdivq_f32(vaddq_f32(l, vdupq_n_f32(16.0)), vdupq_n_f32(116.0));
+    let fx = vaddq_f32(vdivq_f32(a, vdupq_n_f32(500.0)), fy);
+    let fz = vsubq_f32(fy, vdivq_f32(b, vdupq_n_f32(200.0)));
+
+    let xr = labs_to_xyzs_map(fx);
+
+    let yr = {
+        let mask = vcgtq_f32(l, vdupq_n_f32(EPSILON * KAPPA));
+        let false_branch = vdivq_f32(l, vdupq_n_f32(KAPPA));
+        let true_branch = vmulq_f32(vmulq_f32(fy, fy), fy);
+        vbslq_f32(mask, true_branch, false_branch)
+    };
+
+    let zr = labs_to_xyzs_map(fz);
+
+    (
+        vmulq_f32(xr, vdupq_n_f32(0.9504492182750991)),
+        yr,
+        vmulq_f32(zr, vdupq_n_f32(1.0889166484304715)),
+    )
+}
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn labs_to_xyzs_map(f: float32x4_t) -> float32x4_t {
+    let mask = vcgtq_f32(f, vdupq_n_f32(CBRT_EPSILON));
+    let false_branch = {
+        let temp1 = vmulq_f32(f, vdupq_n_f32(116.0));
+        let temp2 = vsubq_f32(temp1, vdupq_n_f32(16.0));
+        vdivq_f32(temp2, vdupq_n_f32(KAPPA))
+    };
+    let true_branch = vmulq_f32(vmulq_f32(f, f), f);
+    vbslq_f32(mask, true_branch, false_branch)
+}
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn xyzs_to_rgbs(
+    x: float32x4_t,
+    y: float32x4_t,
+    z: float32x4_t,
+) -> (float32x4_t, float32x4_t, float32x4_t) {
+    let r = {
+        let prod_x = vmulq_f32(x, vdupq_n_f32(3.240812398895283));
+        let prod_y = vmulq_f32(y, vdupq_n_f32(-1.5373084456298136));
+        let prod_z = vmulq_f32(z, vdupq_n_f32(-0.4985865229069666));
+        let sum = vaddq_f32(vaddq_f32(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map(sum)
+    };
+    let g = {
+        let prod_x = vmulq_f32(x, vdupq_n_f32(-0.9692430170086407));
+        let prod_y = vmulq_f32(y, vdupq_n_f32(1.8759663029085742));
+        let prod_z = vmulq_f32(z, vdupq_n_f32(0.04155503085668564));
+        let sum = vaddq_f32(vaddq_f32(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map(sum)
+    };
+    let b = {
+        let prod_x = vmulq_f32(x, vdupq_n_f32(0.055638398436112804));
+        let prod_y = vmulq_f32(y, vdupq_n_f32(-0.20400746093241362));
+        let prod_z = vmulq_f32(z, vdupq_n_f32(1.0571295702861434));
+        let sum = vaddq_f32(vaddq_f32(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map(sum)
+    };
+
+    (r, g, b)
+}
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn xyzs_to_rgbs_map(c: float32x4_t) -> float32x4_t {
+    let mask = vcgtq_f32(c, vdupq_n_f32(S_0));
+    let false_branch = vmulq_f32(c, vdupq_n_f32(12.92));
+    let true_branch = {
+        let raised = powf_ps(c, vdupq_n_f32(1.0 / 2.4));
+        let temp2 = vmulq_f32(raised, vdupq_n_f32(1.055));
+        vsubq_f32(temp2, vdupq_n_f32(0.055))
+    };
+    let blended = vbslq_f32(mask, true_branch, false_branch);
+    vmulq_f32(blended, vdupq_n_f32(255.0))
+}
+
+/// Clamps to 0..=255 and rounds to nearest, ties to even; NaN lanes become 0.
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn clamp_round(c: float32x4_t) -> [f32; 4] {
+    // Unlike `vmaxq_f32`, `vmaxnmq_f32` returns the number when one operand
+    // is NaN.
+    let clamped = vminq_f32(vmaxnmq_f32(c, vdupq_n_f32(0.0)), vdupq_n_f32(255.0));
+    let mut out = [0f32; 4];
+    vst1q_f32(out.as_mut_ptr(), vrndnq_f32(clamped));
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use crate::simd::neon;
+    use crate::{Lab, __scalar};
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    #[test]
+    fn test_neon_labs_to_rgbs() {
+        let labs = unsafe { neon::rgbs_to_labs(&RGBS) };
+        let rgbs = unsafe { neon::labs_to_rgbs(&labs) };
+        assert_eq!(rgbs.as_slice(), RGBS.as_slice());
+    }
+
+    #[test]
+    fn test_neon_labs_to_rgb_bytes() {
+        // Assert that returning a single slice of bytes returns the same values as
+        // returning them in rgb triples, including for a trailing partial chunk.
+        let labs = __scalar::rgbs_to_labs(&RGBS[..15]);
+        let rgbs = unsafe { neon::labs_to_rgbs(&labs) }.iter().fold(
+            Vec::with_capacity(labs.len() * 3),
+            |mut acc, rgb| {
+                acc.extend_from_slice(rgb);
+                acc
+            },
+        );
+        let bytes = unsafe { neon::labs_to_rgb_bytes(&labs) };
+        assert_eq!(rgbs, bytes);
+    }
+
+    #[test]
+    fn test_neon_labs_to_rgbs_unsaturated() {
+        let labs = vec![Lab {
+            l: 66.6348,
+            a: 52.260696,
+            b: 14.850557,
+        }];
+        let rgbs_non_simd = __scalar::labs_to_rgbs(&labs);
+        let rgbs_simd = unsafe { neon::labs_to_rgbs(&labs) };
+        assert_eq!(rgbs_simd, rgbs_non_simd);
+    }
+
+    #[test]
+    fn test_neon_labs_to_rgbs_out_of_gamut() {
+        #[rustfmt::skip]
+        let labs = vec![
+            Lab { l: 200.0, a: 0.0, b: 0.0 },
+            Lab { l: -50.0, a: 0.0, b: 0.0 },
+            Lab { l: 1.0e9, a: -1.0e9, b: 1.0e9 },
+            Lab { l: 50.0, a: 0.0, b: 300.0 },
+        ];
+        let rgbs_non_simd = __scalar::labs_to_rgbs(&labs);
+        let rgbs_simd = unsafe { neon::labs_to_rgbs(&labs) };
+        assert_eq!(rgbs_simd, rgbs_non_simd);
+    }
+}
@@ -0,0 +1,242 @@
+/*
+A transliteration of NEON-optimized log() and exp() functions from
+NEON_mathfun by Julien Pommier, available at http://gruntthepeon.free.fr/ssemath/
+
+Copyright (C) 2011  Julien Pommier
+
+Provided under the zlib license:
+
+This software is provided 'as-is', without any express or implied
+warranty.  In no event will the authors be held liable for any damages
+arising from the use of this software.
+
+Permission is granted to anyone to use this software for any purpose,
+including commercial applications, and to alter it and redistribute it
+freely, subject to the following restrictions:
+
+1. The origin of this software must not be misrepresented; you must not
+ claim that you wrote the original software. If you use this software
+ in a product, an acknowledgment in the product documentation would be
+ appreciated but is not required.
+2. Altered source versions must be plainly marked as such, and must not be
+ misrepresented as being the original software.
+3. This notice may not be removed or altered from any source distribution.
+
+The denormal and invalid argument handling follows the SSE and AVX versions
+used by the x86_64 kernels, so that all paths agree on edge cases.
+*/
+
+// Constants are kept as written in the original C sources.
+#![allow(clippy::approx_constant)]
+
+use std::arch::aarch64::*;
+
+static X7F: i32 = 0x7f;
+static SQRTHF: f32 = 0.70710678118654752;
+static LOG_P0: f32 = 7.0376836292E-2;
+static LOG_P1: f32 = -1.1514610310E-1;
+static LOG_P2: f32 = 1.1676998740E-1;
+static LOG_P3: f32 = -1.2420140846E-1;
+static LOG_P4: f32 = 1.4249322787E-1;
+static LOG_P5: f32 = -1.6668057665E-1;
+static LOG_P6: f32 = 2.0000714765E-1;
+static LOG_P7: f32 = -2.4999993993E-1;
+static LOG_P8: f32 = 3.3333331174E-1;
+static LOG_Q1: f32 = -2.12194440e-4;
+static LOG_Q2: f32 = 0.693359375;
+
+#[inline]
+#[target_feature(enable = "neon")]
+pub unsafe fn log_ps(x: float32x4_t) -> float32x4_t {
+    let one = vdupq_n_f32(1.0);
+    let p5 = vdupq_n_f32(0.5);
+
+    let invalid_mask = vcleq_f32(x, vdupq_n_f32(0.0));
+
+    let min_norm_pos = vreinterpretq_f32_u32(vdupq_n_u32(0x00800000));
+    let mut x = vmaxq_f32(x, min_norm_pos); /* cut off denormalized stuff */
+
+    let mut emm0 = vreinterpretq_s32_u32(vshrq_n_u32::<23>(vreinterpretq_u32_f32(x)));
+
+    /* keep only the fractional part */
+    let inv_mant_mask = vdupq_n_u32(!0x7f800000);
+    x = vreinterpretq_f32_u32(vandq_u32(vreinterpretq_u32_f32(x), inv_mant_mask));
+    x = vreinterpretq_f32_u32(vorrq_u32(
+        vreinterpretq_u32_f32(x),
+        vreinterpretq_u32_f32(p5),
+    ));
+
+    emm0 = vsubq_s32(emm0, vdupq_n_s32(X7F));
+    let mut e = vcvtq_f32_s32(emm0);
+
+    e = vaddq_f32(e, one);
+
+    let mask = vcltq_f32(x, vdupq_n_f32(SQRTHF));
+    let mut tmp = vreinterpretq_f32_u32(vandq_u32(vreinterpretq_u32_f32(x), mask));
+    x = vsubq_f32(x, one);
+    e = vsubq_f32(
+        e,
+        vreinterpretq_f32_u32(vandq_u32(vreinterpretq_u32_f32(one), mask)),
+    );
+    x = vaddq_f32(x, tmp);
+
+    let z = vmulq_f32(x, x);
+
+    let mut y = vdupq_n_f32(LOG_P0);
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(LOG_P1));
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(LOG_P2));
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(LOG_P3));
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(LOG_P4));
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(LOG_P5));
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(LOG_P6));
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(LOG_P7));
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(LOG_P8));
+    y = vmulq_f32(y, x);
+
+    y = vmulq_f32(y, z);
+
+    tmp = vmulq_f32(e, vdupq_n_f32(LOG_Q1));
+    y = vaddq_f32(y, tmp);
+
+    tmp = vmulq_f32(z, p5);
+    y = vsubq_f32(y, tmp);
+
+    tmp = vmulq_f32(e, vdupq_n_f32(LOG_Q2));
+    x = vaddq_f32(x, y);
+    x = vaddq_f32(x, tmp);
+    // negative arg will be NAN
+    vreinterpretq_f32_u32(vorrq_u32(vreinterpretq_u32_f32(x), invalid_mask))
+}
+
+static EXP_HI: f32 = 88.3762626647949;
+static EXP_LO: f32 = -88.3762626647949;
+static LOG2EF: f32 = 1.44269504088896341;
+static EXP_C1: f32 = 0.693359375;
+static EXP_C2: f32 = -2.12194440e-4;
+static EXP_P0: f32 = 1.9875691500E-4;
+static EXP_P1: f32 = 1.3981999507E-3;
+static EXP_P2: f32 = 8.3334519073E-3;
+static EXP_P3: f32 = 4.1665795894E-2;
+static EXP_P4: f32 = 1.6666665459E-1;
+static EXP_P5: f32 = 5.0000001201E-1;
+
+#[inline]
+#[target_feature(enable = "neon")]
+pub unsafe fn exp_ps(x: float32x4_t) -> float32x4_t {
+    let one = vdupq_n_f32(1.0);
+
+    let mut x = vminq_f32(x, vdupq_n_f32(EXP_HI));
+    x = vmaxq_f32(x, vdupq_n_f32(EXP_LO));
+
+    /* express exp(x) as exp(g + n*log(2)) */
+    let mut fx = vmulq_f32(x, vdupq_n_f32(LOG2EF));
+    fx = vaddq_f32(fx, vdupq_n_f32(0.5));
+
+    /* perform a floorf */
+    let tmp = vcvtq_f32_s32(vcvtq_s32_f32(fx));
+
+    /* if greater, substract 1 */
+    let mask = vcgtq_f32(tmp, fx);
+    let mask = vreinterpretq_f32_u32(vandq_u32(mask, vreinterpretq_u32_f32(one)));
+    fx = vsubq_f32(tmp, mask);
+
+    let tmp = vmulq_f32(fx, vdupq_n_f32(EXP_C1));
+    let mut z = vmulq_f32(fx, vdupq_n_f32(EXP_C2));
+    x = vsubq_f32(x, tmp);
+    x = vsubq_f32(x, z);
+
+    z = vmulq_f32(x, x);
+
+    let mut y = vdupq_n_f32(EXP_P0);
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(EXP_P1));
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(EXP_P2));
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(EXP_P3));
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(EXP_P4));
+    y = vmulq_f32(y, x);
+    y = vaddq_f32(y, vdupq_n_f32(EXP_P5));
+    y = vmulq_f32(y, z);
+    y = vaddq_f32(y, x);
+    y = vaddq_f32(y, one);
+
+    /* build 2^n */
+    let mut mm = vcvtq_s32_f32(fx);
+    mm = vaddq_s32(mm, vdupq_n_s32(X7F));
+    mm = vshlq_n_s32::<23>(mm);
+    let pow2n = vreinterpretq_f32_s32(mm);
+    vmulq_f32(y, pow2n)
+}
+
+#[inline]
+#[target_feature(enable = "neon")]
+pub unsafe fn powf_ps(x: float32x4_t, y: float32x4_t) -> float32x4_t {
+    let invalid_mask = vcleq_f32(x, vdupq_n_f32(0.0));
+    let result = exp_ps(vmulq_f32(y, log_ps(x)));
+    vreinterpretq_f32_u32(vorrq_u32(vreinterpretq_u32_f32(result), invalid_mask))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{exp_ps, log_ps, powf_ps};
+    use approx::assert_relative_eq;
+    use std::arch::aarch64::*;
+    use std::f32;
+
+    unsafe fn load(vals: &[f32; 4]) -> float32x4_t {
+        vld1q_f32(vals.as_ptr())
+    }
+
+    unsafe fn store(v: float32x4_t) -> [f32; 4] {
+        let mut out = [0.0; 4];
+        vst1q_f32(out.as_mut_ptr(), v);
+        out
+    }
+
+    #[test]
+    fn test_log_ps() {
+        let vals: [f32; 4] = [0.5, 1.0, 2.0, 3.0];
+        let scalar_result: Vec<_> = vals.iter().copied().map(f32::ln).collect();
+        let neon_result = unsafe { store(log_ps(load(&vals))) };
+
+        assert_relative_eq!(scalar_result.as_slice(), &neon_result[..])
+    }
+
+    #[test]
+    fn test_negative_log_returns_nan() {
+        let neon_result = unsafe { store(log_ps(load(&[-0.5, 1.0, -2.0, 3.0]))) };
+        assert!(f32::is_nan(neon_result[0]));
+        assert!(!f32::is_nan(neon_result[1]));
+        assert!(f32::is_nan(neon_result[2]));
+        assert!(!f32::is_nan(neon_result[3]));
+    }
+
+    #[test]
+    fn test_exp_ps() {
+        let vals: [f32; 4] = [-1.5, 0.5, 4.0, 10.0];
+        let scalar_result: Vec<_> = vals.iter().copied().map(f32::exp).collect();
+        let neon_result = unsafe { store(exp_ps(load(&vals))) };
+
+        assert_relative_eq!(scalar_result.as_slice(), &neon_result[..])
+    }
+
+    #[test]
+    fn test_powf_ps() {
+        let exponent = 4.0;
+        let vals: [f32; 4] = [0.25, 0.5, 2.0, 10.0];
+        let scalar_result: Vec<_> = vals.iter().map(|&n| n.powf(exponent)).collect();
+        let neon_result = unsafe { store(powf_ps(load(&vals), vdupq_n_f32(exponent))) };
+
+        assert_relative_eq!(scalar_result.as_slice(), &neon_result[..])
+    }
+}
@@ -0,0 +1,8 @@
+//! 4-wide conversion kernels using AArch64 NEON
+
+mod labs_to_rgbs;
+mod math;
+mod rgbs_to_labs;
+
+pub use self::labs_to_rgbs::{labs_to_rgb_bytes, labs_to_rgbs};
+pub use self::rgbs_to_labs::{rgb_bytes_to_labs, rgbs_to_labs};
@@ -0,0 +1,252 @@
+use crate::simd::simd128::math::powf_ps;
+use crate::simd::simd128::{load, store};
+use crate::{Lab, CBRT_EPSILON, EPSILON, KAPPA, S_0};
+use std::arch::wasm32::*;
+use std::{f32, iter};
+
+static BLANK_LAB: Lab = Lab {
+    l: f32::NAN,
+    a: f32::NAN,
+    b: f32::NAN,
+};
+
+pub fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
+    let chunks = labs.chunks_exact(4);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(labs.len()), |mut v, labs| {
+        let rgbs = slice_labs_to_slice_rgbs(labs);
+        v.extend_from_slice(&rgbs);
+        v
+    });
+
+    // As with the other kernels, pad the remainder rather than handing it to
+    // the scalar code so that every item is computed by the same algorithm.
+    if !remainder.is_empty() {
+        let labs: Vec<Lab> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_LAB))
+            .take(4)
+            .collect();
+
+        let rgbs = slice_labs_to_slice_rgbs(&labs);
+        vs.extend_from_slice(&rgbs[..remainder.len()]);
+    }
+
+    vs
+}
+
+pub fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
+    let chunks = labs.chunks_exact(4);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(labs.len() * 3), |mut v, labs| {
+        let bytes = slice_labs_to_rgb_bytes(labs);
+        v.extend_from_slice(&bytes);
+        v
+    });
+
+    if !remainder.is_empty() {
+        let labs: Vec<Lab> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_LAB))
+            .take(4)
+            .collect();
+
+        let bytes = slice_labs_to_rgb_bytes(&labs);
+        vs.extend_from_slice(&bytes[..remainder.len() * 3]);
+    }
+
+    vs
+}
+
+#[inline]
+fn slice_labs_to_slice_rgbs(labs: &[Lab]) -> [[u8; 3]; 4] {
+    let (l, a, b) = lab_slice_to_simd(labs);
+    let (x, y, z) = labs_to_xyzs(l, a, b);
+    let (r, g, b) = xyzs_to_rgbs(x, y, z);
+    let (r, g, b) = (clamp_round(r), clamp_round(g), clamp_round(b));
+
+    let mut rgbs = [[0u8; 3]; 4];
+    for (i, rgb) in rgbs.iter_mut().enumerate() {
+        *rgb = [r[i] as u8, g[i] as u8, b[i] as u8];
+    }
+    rgbs
+}
+
+#[inline]
+fn slice_labs_to_rgb_bytes(labs: &[Lab]) -> [u8; 4 * 3] {
+    let (l, a, b) = lab_slice_to_simd(labs);
+    let (x, y, z) = labs_to_xyzs(l, a, b);
+    let (r, g, b) = xyzs_to_rgbs(x, y, z);
+    let (r, g, b) = (clamp_round(r), clamp_round(g), clamp_round(b));
+
+    let mut bytes = [0u8; 4 * 3];
+    for (i, rgb) in bytes.chunks_exact_mut(3).enumerate() {
+        rgb[0] = r[i] as u8;
+        rgb[1] = g[i] as u8;
+        rgb[2] = b[i] as u8;
+    }
+    bytes
+}
+
+#[inline]
+fn lab_slice_to_simd(labs: &[Lab]) -> (v128, v128, v128) {
+    let labs = &labs[..4];
+    let l = [labs[0].l, labs[1].l, labs[2].l, labs[3].l];
+    let a = [labs[0].a, labs[1].a, labs[2].a, labs[3].a];
+    let b = [labs[0].b, labs[1].b, labs[2].b, labs[3].b];
+    (load(&l), load(&a), load(&b))
+}
+
+#[inline]
+fn labs_to_xyzs(l: v128, a: v128, b: v128) -> (v128, v128, v128) {
+    let fy = f32x4_div(f32x4_add(l, f32x4_splat(16.0)), f32x4_splat(116.0));
+    let fx = f32x4_add(f32x4_div(a, f32x4_splat(500.0)), fy);
+    let fz = f32x4_sub(fy, f32x4_div(b, f32x4_splat(200.0)));
+
+    let xr = labs_to_xyzs_map(fx);
+
+    let yr = {
+        let mask = f32x4_gt(l, f32x4_splat(EPSILON * KAPPA));
+        let false_branch = f32x4_div(l, f32x4_splat(KAPPA));
+        let true_branch = f32x4_mul(f32x4_mul(fy, fy), fy);
+        v128_bitselect(true_branch, false_branch, mask)
+    };
+
+    let zr = labs_to_xyzs_map(fz);
+
+    (
+        f32x4_mul(xr, f32x4_splat(0.9504492182750991)),
+        yr,
+        f32x4_mul(zr, f32x4_splat(1.0889166484304715)),
+    )
+}
+
+#[inline]
+fn labs_to_xyzs_map(f: v128) -> v128 {
+    let mask = f32x4_gt(f, f32x4_splat(CBRT_EPSILON));
+    let false_branch = {
+        let temp1 = f32x4_mul(f, f32x4_splat(116.0));
+        let temp2 = f32x4_sub(temp1, f32x4_splat(16.0));
+        f32x4_div(temp2, f32x4_splat(KAPPA))
+    };
+    let true_branch = f32x4_mul(f32x4_mul(f, f), f);
+    v128_bitselect(true_branch, false_branch, mask)
+}
+
+#[inline]
+fn xyzs_to_rgbs(x: v128, y: v128, z: v128) -> (v128, v128, v128) {
+    let r = {
+        let prod_x = f32x4_mul(x, f32x4_splat(3.240812398895283));
+        let prod_y = f32x4_mul(y, f32x4_splat(-1.5373084456298136));
+        let prod_z = f32x4_mul(z, f32x4_splat(-0.4985865229069666));
+        let sum = f32x4_add(f32x4_add(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map(sum)
+    };
+    let g = {
+        let prod_x = f32x4_mul(x, f32x4_splat(-0.9692430170086407));
+        let prod_y = f32x4_mul(y, f32x4_splat(1.8759663029085742));
+        let prod_z = f32x4_mul(z, f32x4_splat(0.04155503085668564));
+        let sum = f32x4_add(f32x4_add(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map(sum)
+    };
+    let b = {
+        let prod_x = f32x4_mul(x, f32x4_splat(0.055638398436112804));
+        let prod_y = f32x4_mul(y, f32x4_splat(-0.20400746093241362));
+        let prod_z = f32x4_mul(z, f32x4_splat(1.0571295702861434));
+        let sum = f32x4_add(f32x4_add(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map(sum)
+    };
+
+    (r, g, b)
+}
+
+#[inline]
+fn xyzs_to_rgbs_map(c: v128) -> v128 {
+    let mask = f32x4_gt(c, f32x4_splat(S_0));
+    let false_branch = f32x4_mul(c, f32x4_splat(12.92));
+    let true_branch = {
+        let raised = powf_ps(c, f32x4_splat(1.0 / 2.4));
+        let temp2 = f32x4_mul(raised, f32x4_splat(1.055));
+        f32x4_sub(temp2, f32x4_splat(0.055))
+    };
+    let blended = v128_bitselect(true_branch, false_branch, mask);
+    f32x4_mul(blended, f32x4_splat(255.0))
+}
+
+/// Clamps to 0..=255 and rounds to nearest, ties to even; NaN lanes become 0.
+#[inline]
+fn clamp_round(c: v128) -> [f32; 4] {
+    // Unlike `f32x4_max`, the pseudo-maximum returns its first operand when
+    // the comparison fails, which is the case for NaN.
+    let clamped = f32x4_pmin(f32x4_splat(255.0), f32x4_pmax(f32x4_splat(0.0), c));
+    store(f32x4_nearest(clamped))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::simd::simd128;
+    use crate::{Lab, __scalar};
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    #[test]
+    fn test_simd128_labs_to_rgbs() {
+        let labs = simd128::rgbs_to_labs(&RGBS);
+        let rgbs = simd128::labs_to_rgbs(&labs);
+        assert_eq!(rgbs.as_slice(), RGBS.as_slice());
+    }
+
+    #[test]
+    fn test_simd128_labs_to_rgb_bytes() {
+        // Assert that returning a single slice of bytes returns the same values as
+        // returning them in rgb triples, including for a trailing partial chunk.
+        let labs = __scalar::rgbs_to_labs(&RGBS[..15]);
+        let rgbs = simd128::labs_to_rgbs(&labs).iter().fold(
+            Vec::with_capacity(labs.len() * 3),
+            |mut acc, rgb| {
+                acc.extend_from_slice(rgb);
+                acc
+            },
+        );
+        let bytes = simd128::labs_to_rgb_bytes(&labs);
+        assert_eq!(rgbs, bytes);
+    }
+
+    #[test]
+    fn test_simd128_labs_to_rgbs_unsaturated() {
+        let labs = vec![Lab {
+            l: 66.6348,
+            a: 52.260696,
+            b: 14.850557,
+        }];
+        let rgbs_non_simd = __scalar::labs_to_rgbs(&labs);
+        let rgbs_simd = simd128::labs_to_rgbs(&labs);
+        assert_eq!(rgbs_simd, rgbs_non_simd);
+    }
+
+    #[test]
+    fn test_simd128_labs_to_rgbs_out_of_gamut() {
+        #[rustfmt::skip]
+        let labs = vec![
+            Lab { l: 200.0, a: 0.0, b: 0.0 },
+            Lab { l: -50.0, a: 0.0, b: 0.0 },
+            Lab { l: 1.0e9, a: -1.0e9, b: 1.0e9 },
+            Lab { l: 50.0, a: 0.0, b: 300.0 },
+        ];
+        let rgbs_non_simd = __scalar::labs_to_rgbs(&labs);
+        let rgbs_simd = simd128::labs_to_rgbs(&labs);
+        assert_eq!(rgbs_simd, rgbs_non_simd);
+    }
+}
@@ -0,0 +1,222 @@
+/*
+A transliteration to WebAssembly SIMD128 of the SSE2-optimized log() and exp()
+functions from Simple SSE and SSE2 optimized sin, cos, log, and exp by Julien
+Pommier, available at http://gruntthepeon.free.fr/ssemath/
+
+Copyright (C) 2007  Julien Pommier
+
+Provided under the zlib license:
+
+This software is provided 'as-is', without any express or implied
+warranty.  In no event will the authors be held liable for any damages
+arising from the use of this software.
+
+Permission is granted to anyone to use this software for any purpose,
+including commercial applications, and to alter it and redistribute it
+freely, subject to the following restrictions:
+
+1. The origin of this software must not be misrepresented; you must not
+ claim that you wrote the original software. If you use this software
+ in a product, an acknowledgment in the product documentation would be
+ appreciated but is not required.
+2. Altered source versions must be plainly marked as such, and must not be
+ misrepresented as being the original software.
+3. This notice may not be removed or altered from any source distribution.
+
+The denormal and invalid argument handling follows the SSE and AVX versions
+used by the x86_64 kernels, so that all paths agree on edge cases. In
+particular the clamps use the pseudo-minimum and pseudo-maximum instructions,
+which like x86's `minps` and `maxps` return an operand rather than NaN.
+*/
+
+// Constants are kept as written in the original C sources.
+#![allow(clippy::approx_constant)]
+
+use std::arch::wasm32::*;
+
+static X7F: i32 = 0x7f;
+static SQRTHF: f32 = 0.70710678118654752;
+static LOG_P0: f32 = 7.0376836292E-2;
+static LOG_P1: f32 = -1.1514610310E-1;
+static LOG_P2: f32 = 1.1676998740E-1;
+static LOG_P3: f32 = -1.2420140846E-1;
+static LOG_P4: f32 = 1.4249322787E-1;
+static LOG_P5: f32 = -1.6668057665E-1;
+static LOG_P6: f32 = 2.0000714765E-1;
+static LOG_P7: f32 = -2.4999993993E-1;
+static LOG_P8: f32 = 3.3333331174E-1;
+static LOG_Q1: f32 = -2.12194440e-4;
+static LOG_Q2: f32 = 0.693359375;
+
+#[inline]
+pub fn log_ps(x: v128) -> v128 {
+    let one = f32x4_splat(1.0);
+    let p5 = f32x4_splat(0.5);
+
+    let invalid_mask = f32x4_le(x, f32x4_splat(0.0));
+
+    let min_norm_pos = u32x4_splat(0x00800000);
+    let mut x = f32x4_pmax(min_norm_pos, x); /* cut off denormalized stuff */
+
+    let mut emm0 = u32x4_shr(x, 23);
+
+    /* keep only the fractional part */
+    x = v128_and(x, u32x4_splat(!0x7f800000));
+    x = v128_or(x, p5);
+
+    emm0 = i32x4_sub(emm0, i32x4_splat(X7F));
+    let mut e = f32x4_convert_i32x4(emm0);
+
+    e = f32x4_add(e, one);
+
+    let mask = f32x4_lt(x, f32x4_splat(SQRTHF));
+    let mut tmp = v128_and(x, mask);
+    x = f32x4_sub(x, one);
+    e = f32x4_sub(e, v128_and(one, mask));
+    x = f32x4_add(x, tmp);
+
+    let z = f32x4_mul(x, x);
+
+    let mut y = f32x4_splat(LOG_P0);
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(LOG_P1));
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(LOG_P2));
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(LOG_P3));
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(LOG_P4));
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(LOG_P5));
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(LOG_P6));
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(LOG_P7));
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(LOG_P8));
+    y = f32x4_mul(y, x);
+
+    y = f32x4_mul(y, z);
+
+    tmp = f32x4_mul(e, f32x4_splat(LOG_Q1));
+    y = f32x4_add(y, tmp);
+
+    tmp = f32x4_mul(z, p5);
+    y = f32x4_sub(y, tmp);
+
+    tmp = f32x4_mul(e, f32x4_splat(LOG_Q2));
+    x = f32x4_add(x, y);
+    x = f32x4_add(x, tmp);
+    v128_or(x, invalid_mask) // negative arg will be NAN
+}
+
+static EXP_HI: f32 = 88.3762626647949;
+static EXP_LO: f32 = -88.3762626647949;
+static LOG2EF: f32 = 1.44269504088896341;
+static EXP_C1: f32 = 0.693359375;
+static EXP_C2: f32 = -2.12194440e-4;
+static EXP_P0: f32 = 1.9875691500E-4;
+static EXP_P1: f32 = 1.3981999507E-3;
+static EXP_P2: f32 = 8.3334519073E-3;
+static EXP_P3: f32 = 4.1665795894E-2;
+static EXP_P4: f32 = 1.6666665459E-1;
+static EXP_P5: f32 = 5.0000001201E-1;
+
+#[inline]
+pub fn exp_ps(x: v128) -> v128 {
+    let one = f32x4_splat(1.0);
+
+    let mut x = f32x4_pmin(f32x4_splat(EXP_HI), x);
+    x = f32x4_pmax(f32x4_splat(EXP_LO), x);
+
+    /* express exp(x) as exp(g + n*log(2)) */
+    let mut fx = f32x4_mul(x, f32x4_splat(LOG2EF));
+    fx = f32x4_add(fx, f32x4_splat(0.5));
+
+    let tmp = f32x4_floor(fx);
+
+    /* if greater, substract 1 */
+    let mask = v128_and(f32x4_gt(tmp, fx), one);
+    fx = f32x4_sub(tmp, mask);
+
+    let tmp = f32x4_mul(fx, f32x4_splat(EXP_C1));
+    let mut z = f32x4_mul(fx, f32x4_splat(EXP_C2));
+    x = f32x4_sub(x, tmp);
+    x = f32x4_sub(x, z);
+
+    z = f32x4_mul(x, x);
+
+    let mut y = f32x4_splat(EXP_P0);
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(EXP_P1));
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(EXP_P2));
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(EXP_P3));
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(EXP_P4));
+    y = f32x4_mul(y, x);
+    y = f32x4_add(y, f32x4_splat(EXP_P5));
+    y = f32x4_mul(y, z);
+    y = f32x4_add(y, x);
+    y = f32x4_add(y, one);
+
+    /* build 2^n */
+    let mut emm0 = i32x4_trunc_sat_f32x4(fx);
+    emm0 = i32x4_add(emm0, i32x4_splat(X7F));
+    emm0 = i32x4_shl(emm0, 23);
+    f32x4_mul(y, emm0)
+}
+
+#[inline]
+pub fn powf_ps(x: v128, y: v128) -> v128 {
+    let invalid_mask = f32x4_le(x, f32x4_splat(0.0));
+    let result = exp_ps(f32x4_mul(y, log_ps(x)));
+    v128_or(result, invalid_mask)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{exp_ps, log_ps, powf_ps};
+    use crate::simd::simd128::{load, store};
+    use approx::assert_relative_eq;
+    use std::arch::wasm32::*;
+    use std::f32;
+
+    #[test]
+    fn test_log_ps() {
+        let vals: [f32; 4] = [0.5, 1.0, 2.0, 3.0];
+        let scalar_result: Vec<_> = vals.iter().copied().map(f32::ln).collect();
+        let wasm_result = store(log_ps(load(&vals)));
+
+        assert_relative_eq!(scalar_result.as_slice(), &wasm_result[..])
+    }
+
+    #[test]
+    fn test_negative_log_returns_nan() {
+        let wasm_result = store(log_ps(load(&[-0.5, 1.0, -2.0, 3.0])));
+        assert!(f32::is_nan(wasm_result[0]));
+        assert!(!f32::is_nan(wasm_result[1]));
+        assert!(f32::is_nan(wasm_result[2]));
+        assert!(!f32::is_nan(wasm_result[3]));
+    }
+
+    #[test]
+    fn test_exp_ps() {
+        let vals: [f32; 4] = [-1.5, 0.5, 4.0, 10.0];
+        let scalar_result: Vec<_> = vals.iter().copied().map(f32::exp).collect();
+        let wasm_result = store(exp_ps(load(&vals)));
+
+        assert_relative_eq!(scalar_result.as_slice(), &wasm_result[..])
+    }
+
+    #[test]
+    fn test_powf_ps() {
+        let exponent = 4.0;
+        let vals: [f32; 4] = [0.25, 0.5, 2.0, 10.0];
+        let scalar_result: Vec<_> = vals.iter().map(|&n| n.powf(exponent)).collect();
+        let wasm_result = store(powf_ps(load(&vals), f32x4_splat(exponent)));
+
+        assert_relative_eq!(scalar_result.as_slice(), &wasm_result[..])
+    }
+}
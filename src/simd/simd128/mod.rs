@@ -0,0 +1,30 @@
+//! 4-wide conversion kernels using WebAssembly SIMD128
+//!
+//! WebAssembly has no runtime feature detection, so this module is only
+//! compiled when `simd128` is enabled for the whole build, e.g. with
+//! `RUSTFLAGS="-C target-feature=+simd128"`. That also makes every intrinsic
+//! safe to call, so unlike the other kernels these are safe functions.
+
+mod labs_to_rgbs;
+mod math;
+mod rgbs_to_labs;
+
+use std::arch::wasm32::*;
+
+pub use self::labs_to_rgbs::{labs_to_rgb_bytes, labs_to_rgbs};
+pub use self::rgbs_to_labs::{rgb_bytes_to_labs, rgbs_to_labs};
+
+#[inline]
+fn load(vals: &[f32; 4]) -> v128 {
+    f32x4(vals[0], vals[1], vals[2], vals[3])
+}
+
+#[inline]
+fn store(v: v128) -> [f32; 4] {
+    [
+        f32x4_extract_lane::<0>(v),
+        f32x4_extract_lane::<1>(v),
+        f32x4_extract_lane::<2>(v),
+        f32x4_extract_lane::<3>(v),
+    ]
+}
@@ -0,0 +1,265 @@
+use crate::simd::simd128::math::powf_ps;
+use crate::simd::simd128::{load, store};
+use crate::{Lab, EPSILON, E_0_255, KAPPA};
+use std::arch::wasm32::*;
+use std::iter;
+
+static BLANK_RGB: [u8; 3] = [0u8; 3];
+
+pub fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
+    let chunks = rgbs.chunks_exact(4);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(rgbs.len()), |mut v, rgbs| {
+        let labs = slice_rgbs_to_slice_labs(rgbs);
+        v.extend_from_slice(&labs);
+        v
+    });
+
+    // As with the other kernels, pad the remainder rather than handing it to
+    // the scalar code so that every item is computed by the same algorithm.
+    if !remainder.is_empty() {
+        let rgbs: Vec<[u8; 3]> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_RGB))
+            .take(4)
+            .collect();
+        let labs = slice_rgbs_to_slice_labs(&rgbs);
+        vs.extend_from_slice(&labs[..remainder.len()]);
+    }
+
+    vs
+}
+
+pub fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
+    let chunks = bytes.chunks_exact(4 * 3);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(bytes.len() / 3), |mut v, bytes| {
+        let labs = slice_bytes_to_slice_labs(bytes);
+        v.extend_from_slice(&labs);
+        v
+    });
+
+    if !remainder.is_empty() {
+        let bytes: Vec<u8> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(0u8))
+            .take(4 * 3)
+            .collect();
+        let labs = slice_bytes_to_slice_labs(&bytes);
+        vs.extend_from_slice(&labs[..remainder.len() / 3]);
+    }
+
+    vs
+}
+
+#[inline]
+fn slice_rgbs_to_slice_labs(rgbs: &[[u8; 3]]) -> [Lab; 4] {
+    let (r, g, b) = rgb_bytes_to_simd(rgbs);
+    let (x, y, z) = rgbs_to_xyzs(r, g, b);
+    let (l, a, b) = xyzs_to_labs(x, y, z);
+    simd_to_lab_array(l, a, b)
+}
+
+#[inline]
+fn slice_bytes_to_slice_labs(bytes: &[u8]) -> [Lab; 4] {
+    let (r, g, b) = byte_slice_to_simd(bytes);
+    let (x, y, z) = rgbs_to_xyzs(r, g, b);
+    let (l, a, b) = xyzs_to_labs(x, y, z);
+    simd_to_lab_array(l, a, b)
+}
+
+#[inline]
+fn rgb_bytes_to_simd(rgbs: &[[u8; 3]]) -> (v128, v128, v128) {
+    let rgbs = &rgbs[..4];
+    let mut r = [0f32; 4];
+    let mut g = [0f32; 4];
+    let mut b = [0f32; 4];
+    for (i, rgb) in rgbs.iter().enumerate() {
+        r[i] = rgb[0] as f32;
+        g[i] = rgb[1] as f32;
+        b[i] = rgb[2] as f32;
+    }
+    (load(&r), load(&g), load(&b))
+}
+
+#[inline]
+fn byte_slice_to_simd(bytes: &[u8]) -> (v128, v128, v128) {
+    let bytes = &bytes[..4 * 3];
+    let mut r = [0f32; 4];
+    let mut g = [0f32; 4];
+    let mut b = [0f32; 4];
+    for (i, rgb) in bytes.chunks_exact(3).enumerate() {
+        r[i] = rgb[0] as f32;
+        g[i] = rgb[1] as f32;
+        b[i] = rgb[2] as f32;
+    }
+    (load(&r), load(&g), load(&b))
+}
+
+#[inline]
+fn rgbs_to_xyzs(r: v128, g: v128, b: v128) -> (v128, v128, v128) {
+    let r = rgbs_to_xyzs_map(r);
+    let g = rgbs_to_xyzs_map(g);
+    let b = rgbs_to_xyzs_map(b);
+
+    let x = {
+        let prod_r = f32x4_mul(r, f32x4_splat(0.4124108464885388));
+        let prod_g = f32x4_mul(g, f32x4_splat(0.3575845678529519));
+        let prod_b = f32x4_mul(b, f32x4_splat(0.18045380393360833));
+        f32x4_add(f32x4_add(prod_r, prod_g), prod_b)
+    };
+
+    let y = {
+        let prod_r = f32x4_mul(r, f32x4_splat(0.21264934272065283));
+        let prod_g = f32x4_mul(g, f32x4_splat(0.7151691357059038));
+        let prod_b = f32x4_mul(b, f32x4_splat(0.07218152157344333));
+        f32x4_add(f32x4_add(prod_r, prod_g), prod_b)
+    };
+
+    let z = {
+        let prod_r = f32x4_mul(r, f32x4_splat(0.019331758429150258));
+        let prod_g = f32x4_mul(g, f32x4_splat(0.11919485595098397));
+        let prod_b = f32x4_mul(b, f32x4_splat(0.9503900340503373));
+        f32x4_add(f32x4_add(prod_r, prod_g), prod_b)
+    };
+
+    (x, y, z)
+}
+
+#[inline]
+fn rgbs_to_xyzs_map(c: v128) -> v128 {
+    let mask = f32x4_gt(c, f32x4_splat(E_0_255));
+    let true_branch = {
+        const A: f32 = 0.055 * 255.0;
+        const D: f32 = 1.055 * 255.0;
+        let t0 = f32x4_div(f32x4_add(c, f32x4_splat(A)), f32x4_splat(D));
+        powf_ps(t0, f32x4_splat(2.4))
+    };
+
+    let false_branch = {
+        const D: f32 = 12.92 * 255.0;
+        f32x4_div(c, f32x4_splat(D))
+    };
+    v128_bitselect(true_branch, false_branch, mask)
+}
+
+#[inline]
+fn xyzs_to_labs(x: v128, y: v128, z: v128) -> (v128, v128, v128) {
+    let x = xyzs_to_labs_map(f32x4_div(x, f32x4_splat(0.9504492182750991)));
+    let y = xyzs_to_labs_map(y);
+    let z = xyzs_to_labs_map(f32x4_div(z, f32x4_splat(1.0889166484304715)));
+
+    let l = f32x4_add(f32x4_mul(y, f32x4_splat(116.0)), f32x4_splat(-16.0));
+    let a = f32x4_mul(f32x4_sub(x, y), f32x4_splat(500.0));
+    let b = f32x4_mul(f32x4_sub(y, z), f32x4_splat(200.0));
+
+    (l, a, b)
+}
+
+#[inline]
+fn xyzs_to_labs_map(c: v128) -> v128 {
+    let mask = f32x4_gt(c, f32x4_splat(EPSILON));
+    // do false branch first
+    let false_branch = f32x4_div(
+        f32x4_add(f32x4_mul(c, f32x4_splat(KAPPA)), f32x4_splat(16.0)),
+        f32x4_splat(116.0),
+    );
+    let true_branch = powf_ps(c, f32x4_splat(1.0 / 3.0));
+    v128_bitselect(true_branch, false_branch, mask)
+}
+
+#[inline]
+fn simd_to_lab_array(l: v128, a: v128, b: v128) -> [Lab; 4] {
+    let (ls, as_, bs) = (store(l), store(a), store(b));
+
+    let mut labs = [Lab::default(); 4];
+    for (i, lab) in labs.iter_mut().enumerate() {
+        *lab = Lab {
+            l: ls[i],
+            a: as_[i],
+            b: bs[i],
+        };
+    }
+    labs
+}
+
+#[cfg(test)]
+mod test {
+    use crate::__scalar;
+    use crate::simd::simd128;
+    use approx::assert_relative_eq;
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    #[test]
+    fn test_simd128_rgbs_to_labs() {
+        let rgbs = vec![
+            [253, 120, 138], // Lab { l: 66.6348, a: 52.260696, b: 14.850557 }
+            [25, 20, 22],    // Lab { l: 6.9093895, a: 2.8204322, b: -0.45616925 }
+            [63, 81, 181],   // Lab { l: 38.336494, a: 25.586218, b: -55.288517 }
+            [21, 132, 102],  // Lab { l: 49.033485, a: -36.959187, b: 7.9363704 }
+            [255, 193, 7],   // Lab { l: 81.519325, a: 9.4045105, b: 82.69791 }
+            [233, 30, 99],   // Lab { l: 50.865776, a: 74.61989, b: 15.343171 }
+            [155, 96, 132],  // Lab { l: 48.260345, a: 29.383003, b: -9.950054 }
+            [249, 165, 33],  // Lab { l: 74.29188, a: 21.827251, b: 72.75864 }
+        ];
+
+        let labs_non_simd = __scalar::rgbs_to_labs(&rgbs);
+        let labs_simd = simd128::rgbs_to_labs(&rgbs);
+        assert_relative_eq!(
+            labs_simd.as_slice(),
+            labs_non_simd.as_slice(),
+            max_relative = 0.00002
+        );
+    }
+
+    #[test]
+    fn test_simd128_rgb_bytes_to_labs() {
+        // Assert that converting a slice of bytes and a slice of rgb triples
+        // returns the same Lab values, including for a trailing partial chunk.
+        let rgbs = &RGBS[..15];
+        let bytes: Vec<u8> = rgbs.iter().flat_map(|rgb| rgb.iter().cloned()).collect();
+
+        let labs_from_triples = simd128::rgbs_to_labs(rgbs);
+        let labs_from_bytes = simd128::rgb_bytes_to_labs(&bytes);
+        assert_eq!(labs_from_triples, labs_from_bytes);
+    }
+
+    #[test]
+    fn test_simd128_rgbs_to_labs_many() {
+        let labs_non_simd = __scalar::rgbs_to_labs(&RGBS);
+        let labs_simd = simd128::rgbs_to_labs(&RGBS);
+        // Relative error blows up for a* and b* values near zero, so also
+        // accept a small absolute error.
+        assert_relative_eq!(
+            labs_simd.as_slice(),
+            labs_non_simd.as_slice(),
+            epsilon = 0.0001,
+            max_relative = 0.00005
+        );
+    }
+
+    #[test]
+    fn test_simd128_rgbs_to_labs_unsaturated() {
+        let rgbs = vec![[253, 120, 138]];
+        let labs_non_simd = __scalar::rgbs_to_labs(&rgbs);
+        let labs_simd = simd128::rgbs_to_labs(&rgbs);
+        assert_relative_eq!(
+            labs_simd.as_slice(),
+            labs_non_simd.as_slice(),
+            max_relative = 0.00002
+        );
+    }
+}
@@ -0,0 +1,278 @@
+use crate::simd::sse::math::powf_ps;
+use crate::simd::sse::SseOps;
+use crate::{Lab, CBRT_EPSILON, EPSILON, KAPPA, S_0};
+use std::arch::x86_64::*;
+use std::{f32, iter, mem};
+
+static BLANK_LAB: Lab = Lab {
+    l: f32::NAN,
+    a: f32::NAN,
+    b: f32::NAN,
+};
+
+#[inline(always)]
+pub unsafe fn labs_to_rgbs<S: SseOps>(labs: &[Lab]) -> Vec<[u8; 3]> {
+    let chunks = labs.chunks_exact(4);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(labs.len()), |mut v, labs| {
+        let rgbs = slice_labs_to_slice_rgbs::<S>(labs);
+        v.extend_from_slice(&rgbs);
+        v
+    });
+
+    // As with the AVX2 kernels, pad the remainder rather than handing it to
+    // the scalar code so that every item is computed by the same algorithm.
+    if !remainder.is_empty() {
+        let labs: Vec<Lab> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_LAB))
+            .take(4)
+            .collect();
+
+        let rgbs = slice_labs_to_slice_rgbs::<S>(&labs);
+        vs.extend_from_slice(&rgbs[..remainder.len()]);
+    }
+
+    vs
+}
+
+#[inline(always)]
+pub unsafe fn labs_to_rgb_bytes<S: SseOps>(labs: &[Lab]) -> Vec<u8> {
+    let chunks = labs.chunks_exact(4);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(labs.len() * 3), |mut v, labs| {
+        let bytes = slice_labs_to_rgb_bytes::<S>(labs);
+        v.extend_from_slice(&bytes);
+        v
+    });
+
+    if !remainder.is_empty() {
+        let labs: Vec<Lab> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_LAB))
+            .take(4)
+            .collect();
+
+        let bytes = slice_labs_to_rgb_bytes::<S>(&labs);
+        vs.extend_from_slice(&bytes[..remainder.len() * 3]);
+    }
+
+    vs
+}
+
+#[inline(always)]
+unsafe fn slice_labs_to_slice_rgbs<S: SseOps>(labs: &[Lab]) -> [[u8; 3]; 4] {
+    let (l, a, b) = lab_slice_to_simd(labs);
+    let (x, y, z) = labs_to_xyzs::<S>(l, a, b);
+    let (r, g, b) = xyzs_to_rgbs::<S>(x, y, z);
+    simd_to_rgb_array::<S>(r, g, b)
+}
+
+#[inline(always)]
+unsafe fn slice_labs_to_rgb_bytes<S: SseOps>(labs: &[Lab]) -> [u8; 4 * 3] {
+    let (l, a, b) = lab_slice_to_simd(labs);
+    let (x, y, z) = labs_to_xyzs::<S>(l, a, b);
+    let (r, g, b) = xyzs_to_rgbs::<S>(x, y, z);
+    simd_to_rgb_bytes::<S>(r, g, b)
+}
+
+#[inline(always)]
+unsafe fn lab_slice_to_simd(labs: &[Lab]) -> (__m128, __m128, __m128) {
+    let labs = &labs[..4];
+    let l = _mm_set_ps(labs[0].l, labs[1].l, labs[2].l, labs[3].l);
+    let a = _mm_set_ps(labs[0].a, labs[1].a, labs[2].a, labs[3].a);
+    let b = _mm_set_ps(labs[0].b, labs[1].b, labs[2].b, labs[3].b);
+    (l, a, b)
+}
+
+#[inline(always)]
+unsafe fn labs_to_xyzs<S: SseOps>(l: __m128, a: __m128, b: __m128) -> (__m128, __m128, __m128) {
+    let fy = _mm_div_ps(_mm_add_ps(l, _mm_set1_ps(16.0)), _mm_set1_ps(116.0));
+    let fx = _mm_add_ps(_mm_div_ps(a, _mm_set1_ps(500.0)), fy);
+    let fz = _mm_sub_ps(fy, _mm_div_ps(b, _mm_set1_ps(200.0)));
+
+    let xr = labs_to_xyzs_map::<S>(fx);
+
+    let yr = {
+        let mask = _mm_cmpgt_ps(l, _mm_set1_ps(EPSILON * KAPPA));
+        let false_branch = _mm_div_ps(l, _mm_set1_ps(KAPPA));
+        let true_branch = _mm_mul_ps(_mm_mul_ps(fy, fy), fy);
+        S::blendv_ps(false_branch, true_branch, mask)
+    };
+
+    let zr = labs_to_xyzs_map::<S>(fz);
+
+    (
+        _mm_mul_ps(xr, _mm_set1_ps(0.9504492182750991)),
+        yr,
+        _mm_mul_ps(zr, _mm_set1_ps(1.0889166484304715)),
+    )
+}
+
+#[inline(always)]
+unsafe fn labs_to_xyzs_map<S: SseOps>(f: __m128) -> __m128 {
+    let mask = _mm_cmpgt_ps(f, _mm_set1_ps(CBRT_EPSILON));
+    let false_branch = {
+        let temp1 = _mm_mul_ps(f, _mm_set1_ps(116.0));
+        let temp2 = _mm_sub_ps(temp1, _mm_set1_ps(16.0));
+        _mm_div_ps(temp2, _mm_set1_ps(KAPPA))
+    };
+    let true_branch = _mm_mul_ps(_mm_mul_ps(f, f), f);
+    S::blendv_ps(false_branch, true_branch, mask)
+}
+
+#[inline(always)]
+unsafe fn xyzs_to_rgbs<S: SseOps>(x: __m128, y: __m128, z: __m128) -> (__m128, __m128, __m128) {
+    let r = {
+        let prod_x = _mm_mul_ps(x, _mm_set1_ps(3.240812398895283));
+        let prod_y = _mm_mul_ps(y, _mm_set1_ps(-1.5373084456298136));
+        let prod_z = _mm_mul_ps(z, _mm_set1_ps(-0.4985865229069666));
+        let sum = _mm_add_ps(_mm_add_ps(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map::<S>(sum)
+    };
+    let g = {
+        let prod_x = _mm_mul_ps(x, _mm_set1_ps(-0.9692430170086407));
+        let prod_y = _mm_mul_ps(y, _mm_set1_ps(1.8759663029085742));
+        let prod_z = _mm_mul_ps(z, _mm_set1_ps(0.04155503085668564));
+        let sum = _mm_add_ps(_mm_add_ps(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map::<S>(sum)
+    };
+    let b = {
+        let prod_x = _mm_mul_ps(x, _mm_set1_ps(0.055638398436112804));
+        let prod_y = _mm_mul_ps(y, _mm_set1_ps(-0.20400746093241362));
+        let prod_z = _mm_mul_ps(z, _mm_set1_ps(1.0571295702861434));
+        let sum = _mm_add_ps(_mm_add_ps(prod_x, prod_y), prod_z);
+        xyzs_to_rgbs_map::<S>(sum)
+    };
+
+    (r, g, b)
+}
+
+#[inline(always)]
+unsafe fn xyzs_to_rgbs_map<S: SseOps>(c: __m128) -> __m128 {
+    let mask = _mm_cmpgt_ps(c, _mm_set1_ps(S_0));
+    let false_branch = _mm_mul_ps(c, _mm_set1_ps(12.92));
+    let true_branch = {
+        let raised = powf_ps(c, _mm_set1_ps(1.0 / 2.4));
+        let temp2 = _mm_mul_ps(raised, _mm_set1_ps(1.055));
+        _mm_sub_ps(temp2, _mm_set1_ps(0.055))
+    };
+    let blended = S::blendv_ps(false_branch, true_branch, mask);
+    _mm_mul_ps(blended, _mm_set1_ps(255.0))
+}
+
+/// Clamps to 0..=255 and rounds; NaN lanes become 0.
+#[inline(always)]
+unsafe fn clamp_round<S: SseOps>(c: __m128) -> [f32; 4] {
+    // `_mm_max_ps` returns its second operand when either one is NaN.
+    let clamped = _mm_min_ps(_mm_max_ps(c, _mm_setzero_ps()), _mm_set1_ps(255.0));
+    mem::transmute(S::round_ps(clamped))
+}
+
+#[inline(always)]
+unsafe fn simd_to_rgb_array<S: SseOps>(r: __m128, g: __m128, b: __m128) -> [[u8; 3]; 4] {
+    let r = clamp_round::<S>(r);
+    let g = clamp_round::<S>(g);
+    let b = clamp_round::<S>(b);
+
+    let mut rgbs: [mem::MaybeUninit<[u8; 3]>; 4] = mem::MaybeUninit::uninit().assume_init();
+    for (((&r, &g), &b), rgb) in r
+        .iter()
+        .zip(g.iter())
+        .zip(b.iter())
+        .rev()
+        .zip(rgbs.iter_mut())
+    {
+        *rgb = mem::MaybeUninit::new([r as u8, g as u8, b as u8]);
+    }
+    mem::transmute(rgbs)
+}
+
+#[inline(always)]
+unsafe fn simd_to_rgb_bytes<S: SseOps>(r: __m128, g: __m128, b: __m128) -> [u8; 4 * 3] {
+    let r = clamp_round::<S>(r);
+    let g = clamp_round::<S>(g);
+    let b = clamp_round::<S>(b);
+
+    let mut bytes: [mem::MaybeUninit<u8>; 4 * 3] = mem::MaybeUninit::uninit().assume_init();
+    for (((&r, &g), &b), rgb) in r
+        .iter()
+        .zip(g.iter())
+        .zip(b.iter())
+        .rev()
+        .zip(bytes.chunks_exact_mut(3))
+    {
+        rgb[0] = mem::MaybeUninit::new(r as u8);
+        rgb[1] = mem::MaybeUninit::new(g as u8);
+        rgb[2] = mem::MaybeUninit::new(b as u8);
+    }
+    mem::transmute(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::simd::sse::{sse2, sse41};
+    use crate::{Lab, __scalar};
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    #[test]
+    fn test_sse2_labs_to_rgbs() {
+        let labs = sse2::rgbs_to_labs(&RGBS);
+        let rgbs = sse2::labs_to_rgbs(&labs);
+        assert_eq!(rgbs.as_slice(), RGBS.as_slice());
+    }
+
+    #[test]
+    fn test_sse41_labs_to_rgbs() {
+        if !is_x86_feature_detected!("sse4.1") {
+            return;
+        }
+        let labs = unsafe { sse41::rgbs_to_labs(&RGBS) };
+        let rgbs = unsafe { sse41::labs_to_rgbs(&labs) };
+        assert_eq!(rgbs.as_slice(), RGBS.as_slice());
+    }
+
+    #[test]
+    fn test_sse_labs_to_rgb_bytes() {
+        // Assert that returning a single slice of bytes returns the same values as
+        // returning them in rgb triples, including for a trailing partial chunk.
+        let labs = __scalar::rgbs_to_labs(&RGBS[..15]);
+        let rgbs = sse2::labs_to_rgbs(&labs).iter().fold(
+            Vec::with_capacity(labs.len() * 3),
+            |mut acc, rgb| {
+                acc.extend_from_slice(rgb);
+                acc
+            },
+        );
+        let bytes = sse2::labs_to_rgb_bytes(&labs);
+        assert_eq!(rgbs, bytes);
+    }
+
+    #[test]
+    fn test_sse_labs_to_rgbs_out_of_gamut() {
+        #[rustfmt::skip]
+        let labs = vec![
+            Lab { l: 66.6348, a: 52.260696, b: 14.850557 },
+            Lab { l: 200.0, a: 0.0, b: 0.0 },
+            Lab { l: -50.0, a: 0.0, b: 0.0 },
+            Lab { l: 1.0e9, a: -1.0e9, b: 1.0e9 },
+            Lab { l: 50.0, a: 0.0, b: 300.0 },
+        ];
+        let rgbs_non_simd = __scalar::labs_to_rgbs(&labs);
+        let rgbs_simd = sse2::labs_to_rgbs(&labs);
+        assert_eq!(rgbs_simd, rgbs_non_simd);
+    }
+}
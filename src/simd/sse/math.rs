@@ -0,0 +1,250 @@
+/*
+An almost-literal transliteration of SSE2-optimized log() and exp() functions
+from Simple SSE and SSE2 optimized sin, cos, log, and exp by Julien Pommier,
+available at http://gruntthepeon.free.fr/ssemath/
+
+Copyright (C) 2007  Julien Pommier
+
+Provided under the zlib license:
+
+This software is provided 'as-is', without any express or implied
+warranty.  In no event will the authors be held liable for any damages
+arising from the use of this software.
+
+Permission is granted to anyone to use this software for any purpose,
+including commercial applications, and to alter it and redistribute it
+freely, subject to the following restrictions:
+
+1. The origin of this software must not be misrepresented; you must not
+ claim that you wrote the original software. If you use this software
+ in a product, an acknowledgment in the product documentation would be
+ appreciated but is not required.
+2. Altered source versions must be plainly marked as such, and must not be
+ misrepresented as being the original software.
+3. This notice may not be removed or altered from any source distribution.
+
+*/
+
+// Constants are kept as written in the original C sources.
+#![allow(clippy::approx_constant)]
+
+use std::arch::x86_64::*;
+
+static X7F: i32 = 0x7f;
+static SQRTHF: f32 = 0.70710678118654752;
+static LOG_P0: f32 = 7.0376836292E-2;
+static LOG_P1: f32 = -1.1514610310E-1;
+static LOG_P2: f32 = 1.1676998740E-1;
+static LOG_P3: f32 = -1.2420140846E-1;
+static LOG_P4: f32 = 1.4249322787E-1;
+static LOG_P5: f32 = -1.6668057665E-1;
+static LOG_P6: f32 = 2.0000714765E-1;
+static LOG_P7: f32 = -2.4999993993E-1;
+static LOG_P8: f32 = 3.3333331174E-1;
+static LOG_Q1: f32 = -2.12194440e-4;
+static LOG_Q2: f32 = 0.693359375;
+
+#[inline(always)]
+pub unsafe fn log_ps(x: __m128) -> __m128 {
+    let one = _mm_set1_ps(1.0);
+    let p5 = _mm_set1_ps(0.5);
+
+    let invalid_mask = _mm_cmple_ps(x, _mm_setzero_ps());
+
+    let min_norm_pos = _mm_castsi128_ps(_mm_set1_epi32(0x00800000));
+    let mut x = _mm_max_ps(x, min_norm_pos); /* cut off denormalized stuff */
+
+    let mut emm0 = _mm_srli_epi32(_mm_castps_si128(x), 23);
+
+    /* keep only the fractional part */
+    let inv_mant_mask = _mm_castsi128_ps(_mm_set1_epi32(!0x7f800000));
+    x = _mm_and_ps(x, inv_mant_mask);
+    x = _mm_or_ps(x, p5);
+
+    emm0 = _mm_sub_epi32(emm0, _mm_set1_epi32(X7F));
+    let mut e = _mm_cvtepi32_ps(emm0);
+
+    e = _mm_add_ps(e, one);
+
+    let mask = _mm_cmplt_ps(x, _mm_set1_ps(SQRTHF));
+    let mut tmp = _mm_and_ps(x, mask);
+    x = _mm_sub_ps(x, one);
+    e = _mm_sub_ps(e, _mm_and_ps(one, mask));
+    x = _mm_add_ps(x, tmp);
+
+    let z = _mm_mul_ps(x, x);
+
+    let mut y = _mm_set1_ps(LOG_P0);
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(LOG_P1));
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(LOG_P2));
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(LOG_P3));
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(LOG_P4));
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(LOG_P5));
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(LOG_P6));
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(LOG_P7));
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(LOG_P8));
+    y = _mm_mul_ps(y, x);
+
+    y = _mm_mul_ps(y, z);
+
+    tmp = _mm_mul_ps(e, _mm_set1_ps(LOG_Q1));
+    y = _mm_add_ps(y, tmp);
+
+    tmp = _mm_mul_ps(z, p5);
+    y = _mm_sub_ps(y, tmp);
+
+    tmp = _mm_mul_ps(e, _mm_set1_ps(LOG_Q2));
+    x = _mm_add_ps(x, y);
+    x = _mm_add_ps(x, tmp);
+    x = _mm_or_ps(x, invalid_mask); // negative arg will be NAN
+    x
+}
+
+static EXP_HI: f32 = 88.3762626647949;
+static EXP_LO: f32 = -88.3762626647949;
+static LOG2EF: f32 = 1.44269504088896341;
+static EXP_C1: f32 = 0.693359375;
+static EXP_C2: f32 = -2.12194440e-4;
+static EXP_P0: f32 = 1.9875691500E-4;
+static EXP_P1: f32 = 1.3981999507E-3;
+static EXP_P2: f32 = 8.3334519073E-3;
+static EXP_P3: f32 = 4.1665795894E-2;
+static EXP_P4: f32 = 1.6666665459E-1;
+static EXP_P5: f32 = 5.0000001201E-1;
+
+#[inline(always)]
+pub unsafe fn exp_ps(x: __m128) -> __m128 {
+    let one = _mm_set1_ps(1.0);
+
+    let mut x = _mm_min_ps(x, _mm_set1_ps(EXP_HI));
+    x = _mm_max_ps(x, _mm_set1_ps(EXP_LO));
+
+    /* express exp(x) as exp(g + n*log(2)) */
+    let mut fx = _mm_mul_ps(x, _mm_set1_ps(LOG2EF));
+    fx = _mm_add_ps(fx, _mm_set1_ps(0.5));
+
+    /* how to perform a floorf with SSE: just below */
+    let mut emm0 = _mm_cvttps_epi32(fx);
+    let mut tmp = _mm_cvtepi32_ps(emm0);
+
+    /* if greater, substract 1 */
+    let mut mask = _mm_cmpgt_ps(tmp, fx);
+    mask = _mm_and_ps(mask, one);
+    fx = _mm_sub_ps(tmp, mask);
+
+    tmp = _mm_mul_ps(fx, _mm_set1_ps(EXP_C1));
+    let mut z = _mm_mul_ps(fx, _mm_set1_ps(EXP_C2));
+    x = _mm_sub_ps(x, tmp);
+    x = _mm_sub_ps(x, z);
+
+    z = _mm_mul_ps(x, x);
+
+    let mut y = _mm_set1_ps(EXP_P0);
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(EXP_P1));
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(EXP_P2));
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(EXP_P3));
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(EXP_P4));
+    y = _mm_mul_ps(y, x);
+    y = _mm_add_ps(y, _mm_set1_ps(EXP_P5));
+    y = _mm_mul_ps(y, z);
+    y = _mm_add_ps(y, x);
+    y = _mm_add_ps(y, one);
+
+    /* build 2^n */
+    emm0 = _mm_cvttps_epi32(fx);
+    emm0 = _mm_add_epi32(emm0, _mm_set1_epi32(X7F));
+    emm0 = _mm_slli_epi32(emm0, 23);
+    let pow2n = _mm_castsi128_ps(emm0);
+    y = _mm_mul_ps(y, pow2n);
+    y
+}
+
+#[inline(always)]
+pub unsafe fn powf_ps(x: __m128, y: __m128) -> __m128 {
+    let invalid_mask = _mm_cmple_ps(x, _mm_setzero_ps());
+    let result = exp_ps(_mm_mul_ps(y, log_ps(x)));
+    _mm_or_ps(result, invalid_mask)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{exp_ps, log_ps, powf_ps};
+    use approx::assert_relative_eq;
+    use std::arch::x86_64::*;
+    use std::{f32, mem};
+
+    #[test]
+    fn test_log_ps() {
+        let scalar_result: Vec<_> = {
+            let vals: [f32; 4] = [0.5, 1.0, 2.0, 3.0];
+            vals.iter().copied().map(f32::ln).collect()
+        };
+        let sse_result: Vec<_> = unsafe {
+            let vals = _mm_set_ps(0.5, 1.0, 2.0, 3.0);
+            let result = log_ps(vals);
+            let result: [f32; 4] = mem::transmute(result);
+            result.iter().rev().copied().collect()
+        };
+
+        assert_relative_eq!(scalar_result.as_slice(), sse_result.as_slice())
+    }
+
+    #[test]
+    fn test_negative_log_returns_nan() {
+        let sse_result: Vec<_> = unsafe {
+            let vals = _mm_set_ps(-0.5, 1.0, -2.0, 3.0);
+            let result = log_ps(vals);
+            let result: [f32; 4] = mem::transmute(result);
+            result.iter().rev().copied().collect()
+        };
+        assert!(f32::is_nan(sse_result[0]));
+        assert!(!f32::is_nan(sse_result[1]));
+        assert!(f32::is_nan(sse_result[2]));
+        assert!(!f32::is_nan(sse_result[3]));
+    }
+
+    #[test]
+    fn test_exp_ps() {
+        let scalar_result: Vec<_> = {
+            let vals: [f32; 4] = [-1.5, 0.5, 4.0, 10.0];
+            vals.iter().copied().map(f32::exp).collect()
+        };
+        let sse_result: Vec<_> = unsafe {
+            let vals = _mm_set_ps(-1.5, 0.5, 4.0, 10.0);
+            let result = exp_ps(vals);
+            let result: [f32; 4] = mem::transmute(result);
+            result.iter().rev().copied().collect()
+        };
+
+        assert_relative_eq!(scalar_result.as_slice(), sse_result.as_slice())
+    }
+
+    #[test]
+    fn test_powf_ps() {
+        let exponent = 4.0;
+        let scalar_result: Vec<_> = {
+            let vals: [f32; 4] = [0.25, 0.5, 2.0, 10.0];
+            vals.iter().map(|&n| n.powf(exponent)).collect()
+        };
+        let sse_result: Vec<_> = unsafe {
+            let vals = _mm_set_ps(0.25, 0.5, 2.0, 10.0);
+            let result = powf_ps(vals, _mm_set1_ps(exponent));
+            let result: [f32; 4] = mem::transmute(result);
+            result.iter().rev().copied().collect()
+        };
+
+        assert_relative_eq!(scalar_result.as_slice(), sse_result.as_slice())
+    }
+}
@@ -0,0 +1,101 @@
+//! 4-wide conversion kernels using SSE2, or SSE4.1 where it's available
+//!
+//! The kernels are generic over `SseOps`, which provides the handful of
+//! instructions that SSE4.1 adds over the SSE2 baseline. Every function below
+//! the entry points is `#[inline(always)]` so the whole kernel gets compiled
+//! with the entry point's target features.
+
+mod labs_to_rgbs;
+mod math;
+mod rgbs_to_labs;
+
+use std::arch::x86_64::*;
+
+pub(crate) trait SseOps {
+    /// Selects lanes from `b` where `mask` is set and from `a` elsewhere
+    unsafe fn blendv_ps(a: __m128, b: __m128, mask: __m128) -> __m128;
+
+    /// Rounds to the nearest integer, ties to even
+    ///
+    /// Only needs to handle values in the range 0..=255.
+    unsafe fn round_ps(a: __m128) -> __m128;
+}
+
+pub(crate) enum Sse2 {}
+
+impl SseOps for Sse2 {
+    #[inline(always)]
+    unsafe fn blendv_ps(a: __m128, b: __m128, mask: __m128) -> __m128 {
+        _mm_or_ps(_mm_and_ps(mask, b), _mm_andnot_ps(mask, a))
+    }
+
+    #[inline(always)]
+    unsafe fn round_ps(a: __m128) -> __m128 {
+        // Conversion uses the MXCSR rounding mode, which defaults to nearest
+        _mm_cvtepi32_ps(_mm_cvtps_epi32(a))
+    }
+}
+
+pub(crate) enum Sse41 {}
+
+impl SseOps for Sse41 {
+    #[inline]
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn blendv_ps(a: __m128, b: __m128, mask: __m128) -> __m128 {
+        _mm_blendv_ps(a, b, mask)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn round_ps(a: __m128) -> __m128 {
+        _mm_round_ps(a, _MM_FROUND_TO_NEAREST_INT | _MM_FROUND_NO_EXC)
+    }
+}
+
+/// Entry points for the SSE2 kernels, which every x86_64 CPU supports
+pub mod sse2 {
+    use super::{labs_to_rgbs, rgbs_to_labs, Sse2};
+    use Lab;
+
+    pub fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
+        unsafe { rgbs_to_labs::rgbs_to_labs::<Sse2>(rgbs) }
+    }
+
+    pub fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
+        unsafe { rgbs_to_labs::rgb_bytes_to_labs::<Sse2>(bytes) }
+    }
+
+    pub fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
+        unsafe { labs_to_rgbs::labs_to_rgbs::<Sse2>(labs) }
+    }
+
+    pub fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
+        unsafe { labs_to_rgbs::labs_to_rgb_bytes::<Sse2>(labs) }
+    }
+}
+
+/// Entry points for the SSE4.1 kernels
+pub mod sse41 {
+    use super::{labs_to_rgbs, rgbs_to_labs, Sse41};
+    use Lab;
+
+    #[target_feature(enable = "sse4.1")]
+    pub unsafe fn rgbs_to_labs(rgbs: &[[u8; 3]]) -> Vec<Lab> {
+        rgbs_to_labs::rgbs_to_labs::<Sse41>(rgbs)
+    }
+
+    #[target_feature(enable = "sse4.1")]
+    pub unsafe fn rgb_bytes_to_labs(bytes: &[u8]) -> Vec<Lab> {
+        rgbs_to_labs::rgb_bytes_to_labs::<Sse41>(bytes)
+    }
+
+    #[target_feature(enable = "sse4.1")]
+    pub unsafe fn labs_to_rgbs(labs: &[Lab]) -> Vec<[u8; 3]> {
+        labs_to_rgbs::labs_to_rgbs::<Sse41>(labs)
+    }
+
+    #[target_feature(enable = "sse4.1")]
+    pub unsafe fn labs_to_rgb_bytes(labs: &[Lab]) -> Vec<u8> {
+        labs_to_rgbs::labs_to_rgb_bytes::<Sse41>(labs)
+    }
+}
@@ -0,0 +1,276 @@
+use crate::simd::sse::math::powf_ps;
+use crate::simd::sse::SseOps;
+use crate::{Lab, EPSILON, E_0_255, KAPPA};
+use std::arch::x86_64::*;
+use std::{iter, mem};
+
+static BLANK_RGB: [u8; 3] = [0u8; 3];
+
+#[inline(always)]
+pub unsafe fn rgbs_to_labs<S: SseOps>(rgbs: &[[u8; 3]]) -> Vec<Lab> {
+    let chunks = rgbs.chunks_exact(4);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(rgbs.len()), |mut v, rgbs| {
+        let labs = slice_rgbs_to_slice_labs::<S>(rgbs);
+        v.extend_from_slice(&labs);
+        v
+    });
+
+    // As with the AVX2 kernels, pad the remainder rather than handing it to
+    // the scalar code so that every item is computed by the same algorithm.
+    if !remainder.is_empty() {
+        let rgbs: Vec<[u8; 3]> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(BLANK_RGB))
+            .take(4)
+            .collect();
+        let labs = slice_rgbs_to_slice_labs::<S>(&rgbs);
+        vs.extend_from_slice(&labs[..remainder.len()]);
+    }
+
+    vs
+}
+
+#[inline(always)]
+pub unsafe fn rgb_bytes_to_labs<S: SseOps>(bytes: &[u8]) -> Vec<Lab> {
+    let chunks = bytes.chunks_exact(4 * 3);
+    let remainder = chunks.remainder();
+    let mut vs = chunks.fold(Vec::with_capacity(bytes.len() / 3), |mut v, bytes| {
+        let labs = slice_bytes_to_slice_labs::<S>(bytes);
+        v.extend_from_slice(&labs);
+        v
+    });
+
+    if !remainder.is_empty() {
+        let bytes: Vec<u8> = remainder
+            .iter()
+            .cloned()
+            .chain(iter::repeat(0u8))
+            .take(4 * 3)
+            .collect();
+        let labs = slice_bytes_to_slice_labs::<S>(&bytes);
+        vs.extend_from_slice(&labs[..remainder.len() / 3]);
+    }
+
+    vs
+}
+
+#[inline(always)]
+unsafe fn slice_rgbs_to_slice_labs<S: SseOps>(rgbs: &[[u8; 3]]) -> [Lab; 4] {
+    let (r, g, b) = rgb_bytes_to_simd(rgbs);
+    let (x, y, z) = rgbs_to_xyzs::<S>(r, g, b);
+    let (l, a, b) = xyzs_to_labs::<S>(x, y, z);
+    simd_to_lab_array(l, a, b)
+}
+
+#[inline(always)]
+unsafe fn slice_bytes_to_slice_labs<S: SseOps>(bytes: &[u8]) -> [Lab; 4] {
+    let (r, g, b) = byte_slice_to_simd(bytes);
+    let (x, y, z) = rgbs_to_xyzs::<S>(r, g, b);
+    let (l, a, b) = xyzs_to_labs::<S>(x, y, z);
+    simd_to_lab_array(l, a, b)
+}
+
+#[inline(always)]
+unsafe fn rgb_bytes_to_simd(rgbs: &[[u8; 3]]) -> (__m128, __m128, __m128) {
+    let r = _mm_set_ps(
+        rgbs[0][0] as f32,
+        rgbs[1][0] as f32,
+        rgbs[2][0] as f32,
+        rgbs[3][0] as f32,
+    );
+    let g = _mm_set_ps(
+        rgbs[0][1] as f32,
+        rgbs[1][1] as f32,
+        rgbs[2][1] as f32,
+        rgbs[3][1] as f32,
+    );
+    let b = _mm_set_ps(
+        rgbs[0][2] as f32,
+        rgbs[1][2] as f32,
+        rgbs[2][2] as f32,
+        rgbs[3][2] as f32,
+    );
+    (r, g, b)
+}
+
+#[inline(always)]
+#[allow(clippy::identity_op, clippy::erasing_op)]
+unsafe fn byte_slice_to_simd(bytes: &[u8]) -> (__m128, __m128, __m128) {
+    let r = _mm_set_ps(
+        bytes[3 * 0] as f32,
+        bytes[3 * 1] as f32,
+        bytes[3 * 2] as f32,
+        bytes[3 * 3] as f32,
+    );
+    let g = _mm_set_ps(
+        bytes[3 * 0 + 1] as f32,
+        bytes[3 * 1 + 1] as f32,
+        bytes[3 * 2 + 1] as f32,
+        bytes[3 * 3 + 1] as f32,
+    );
+    let b = _mm_set_ps(
+        bytes[3 * 0 + 2] as f32,
+        bytes[3 * 1 + 2] as f32,
+        bytes[3 * 2 + 2] as f32,
+        bytes[3 * 3 + 2] as f32,
+    );
+    (r, g, b)
+}
+
+#[inline(always)]
+unsafe fn rgbs_to_xyzs<S: SseOps>(r: __m128, g: __m128, b: __m128) -> (__m128, __m128, __m128) {
+    let r = rgbs_to_xyzs_map::<S>(r);
+    let g = rgbs_to_xyzs_map::<S>(g);
+    let b = rgbs_to_xyzs_map::<S>(b);
+
+    let x = {
+        let prod_r = _mm_mul_ps(r, _mm_set1_ps(0.4124108464885388));
+        let prod_g = _mm_mul_ps(g, _mm_set1_ps(0.3575845678529519));
+        let prod_b = _mm_mul_ps(b, _mm_set1_ps(0.18045380393360833));
+        _mm_add_ps(_mm_add_ps(prod_r, prod_g), prod_b)
+    };
+
+    let y = {
+        let prod_r = _mm_mul_ps(r, _mm_set1_ps(0.21264934272065283));
+        let prod_g = _mm_mul_ps(g, _mm_set1_ps(0.7151691357059038));
+        let prod_b = _mm_mul_ps(b, _mm_set1_ps(0.07218152157344333));
+        _mm_add_ps(_mm_add_ps(prod_r, prod_g), prod_b)
+    };
+
+    let z = {
+        let prod_r = _mm_mul_ps(r, _mm_set1_ps(0.019331758429150258));
+        let prod_g = _mm_mul_ps(g, _mm_set1_ps(0.11919485595098397));
+        let prod_b = _mm_mul_ps(b, _mm_set1_ps(0.9503900340503373));
+        _mm_add_ps(_mm_add_ps(prod_r, prod_g), prod_b)
+    };
+
+    (x, y, z)
+}
+
+#[inline(always)]
+unsafe fn rgbs_to_xyzs_map<S: SseOps>(c: __m128) -> __m128 {
+    let mask = _mm_cmpgt_ps(c, _mm_set1_ps(E_0_255));
+    let true_branch = {
+        const A: f32 = 0.055 * 255.0;
+        const D: f32 = 1.055 * 255.0;
+        let t0 = _mm_div_ps(_mm_add_ps(c, _mm_set1_ps(A)), _mm_set1_ps(D));
+        powf_ps(t0, _mm_set1_ps(2.4))
+    };
+
+    let false_branch = {
+        const D: f32 = 12.92 * 255.0;
+        _mm_div_ps(c, _mm_set1_ps(D))
+    };
+    S::blendv_ps(false_branch, true_branch, mask)
+}
+
+#[inline(always)]
+unsafe fn xyzs_to_labs<S: SseOps>(x: __m128, y: __m128, z: __m128) -> (__m128, __m128, __m128) {
+    let x = xyzs_to_labs_map::<S>(_mm_div_ps(x, _mm_set1_ps(0.9504492182750991)));
+    let y = xyzs_to_labs_map::<S>(y);
+    let z = xyzs_to_labs_map::<S>(_mm_div_ps(z, _mm_set1_ps(1.0889166484304715)));
+
+    let l = _mm_add_ps(_mm_mul_ps(y, _mm_set1_ps(116.0)), _mm_set1_ps(-16.0));
+    let a = _mm_mul_ps(_mm_sub_ps(x, y), _mm_set1_ps(500.0));
+    let b = _mm_mul_ps(_mm_sub_ps(y, z), _mm_set1_ps(200.0));
+
+    (l, a, b)
+}
+
+#[inline(always)]
+unsafe fn xyzs_to_labs_map<S: SseOps>(c: __m128) -> __m128 {
+    let mask = _mm_cmpgt_ps(c, _mm_set1_ps(EPSILON));
+    // do false branch first
+    let false_branch = _mm_div_ps(
+        _mm_add_ps(_mm_mul_ps(c, _mm_set1_ps(KAPPA)), _mm_set1_ps(16.0)),
+        _mm_set1_ps(116.0),
+    );
+    let true_branch = powf_ps(c, _mm_set1_ps(1.0 / 3.0));
+    S::blendv_ps(false_branch, true_branch, mask)
+}
+
+#[inline(always)]
+unsafe fn simd_to_lab_array(l: __m128, a: __m128, b: __m128) -> [Lab; 4] {
+    let l: [f32; 4] = mem::transmute(l);
+    let a: [f32; 4] = mem::transmute(a);
+    let b: [f32; 4] = mem::transmute(b);
+
+    let mut labs: [mem::MaybeUninit<Lab>; 4] = mem::MaybeUninit::uninit().assume_init();
+    for (((&l, &a), &b), lab) in l
+        .iter()
+        .zip(a.iter())
+        .zip(b.iter())
+        .rev()
+        .zip(labs.iter_mut())
+    {
+        *lab = mem::MaybeUninit::new(Lab { l, a, b });
+    }
+    mem::transmute(labs)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::__scalar;
+    use crate::simd::sse::{sse2, sse41};
+    use approx::assert_relative_eq;
+    use lazy_static::lazy_static;
+    use rand;
+    use rand::distributions::Standard;
+    use rand::Rng;
+
+    lazy_static! {
+        static ref RGBS: Vec<[u8; 3]> = {
+            let rand_seed = [0u8; 32];
+            let rng: rand::rngs::StdRng = rand::SeedableRng::from_seed(rand_seed);
+            rng.sample_iter(&Standard).take(512).collect()
+        };
+    }
+
+    #[test]
+    fn test_sse2_rgbs_to_labs_many() {
+        let labs_non_simd = __scalar::rgbs_to_labs(&RGBS);
+        let labs_simd = sse2::rgbs_to_labs(&RGBS);
+        assert_relative_eq!(
+            labs_simd.as_slice(),
+            labs_non_simd.as_slice(),
+            epsilon = 0.0001,
+            max_relative = 0.00005
+        );
+    }
+
+    #[test]
+    fn test_sse41_rgbs_to_labs_many() {
+        if !is_x86_feature_detected!("sse4.1") {
+            return;
+        }
+        let labs_sse2 = sse2::rgbs_to_labs(&RGBS);
+        let labs_sse41 = unsafe { sse41::rgbs_to_labs(&RGBS) };
+        assert_eq!(labs_sse41, labs_sse2);
+    }
+
+    #[test]
+    fn test_sse_rgb_bytes_to_labs() {
+        // Assert that converting a slice of bytes and a slice of rgb triples
+        // returns the same Lab values, including for a trailing partial chunk.
+        let rgbs = &RGBS[..15];
+        let bytes: Vec<u8> = rgbs.iter().flat_map(|rgb| rgb.iter().cloned()).collect();
+
+        let labs_from_triples = sse2::rgbs_to_labs(rgbs);
+        let labs_from_bytes = sse2::rgb_bytes_to_labs(&bytes);
+        assert_eq!(labs_from_triples, labs_from_bytes);
+    }
+
+    #[test]
+    fn test_sse_rgbs_to_labs_unsaturated() {
+        let rgbs = vec![[253, 120, 138]];
+        let labs_non_simd = __scalar::rgbs_to_labs(&rgbs);
+        let labs_simd = sse2::rgbs_to_labs(&rgbs);
+        assert_relative_eq!(
+            labs_simd.as_slice(),
+            labs_non_simd.as_slice(),
+            max_relative = 0.00002
+        );
+    }
+}
@@ -0,0 +1,424 @@
+//! Color statistics of images.
+
+use core::f32::consts::TAU;
+use hue::Hue;
+use Lab;
+
+/// The distribution of one value, such as L\* or chroma, over an image's
+/// pixels
+#[derive(Debug, Clone)]
+pub struct Distribution {
+    sorted: Vec<f32>,
+    mean: f32,
+}
+
+impl Distribution {
+    pub(crate) fn new(mut values: Vec<f32>) -> Self {
+        let mean = values.iter().map(|&v| v as f64).sum::<f64>() / values.len() as f64;
+        values.sort_by(|a, b| a.total_cmp(b));
+        Distribution {
+            sorted: values,
+            mean: mean as f32,
+        }
+    }
+
+    /// Returns the mean
+    pub fn mean(&self) -> f32 {
+        self.mean
+    }
+
+    /// Returns the standard deviation
+    pub fn std_dev(&self) -> f32 {
+        let mean = self.mean as f64;
+        let variance = self
+            .sorted
+            .iter()
+            .map(|&v| (v as f64 - mean) * (v as f64 - mean))
+            .sum::<f64>()
+            / self.sorted.len() as f64;
+        variance.sqrt() as f32
+    }
+
+    /// Returns the lowest value
+    pub fn min(&self) -> f32 {
+        self.sorted[0]
+    }
+
+    /// Returns the highest value
+    pub fn max(&self) -> f32 {
+        self.sorted[self.sorted.len() - 1]
+    }
+
+    /// Returns the median, the 50th percentile
+    pub fn median(&self) -> f32 {
+        self.percentile(50.0)
+    }
+
+    /// Returns the `p`th percentile, interpolating linearly between the
+    /// closest values
+    ///
+    /// `p` is clamped to 0..100; 0 gives the lowest value and 100 the
+    /// highest.
+    ///
+    /// # Example
+    /// ```
+    /// # use lab::{Lab, LabStats};
+    /// let labs: Vec<Lab> = (0..=10).map(|i| Lab { l: i as f32 * 10.0, a: 0.0, b: 0.0 }).collect();
+    /// let stats = LabStats::new(&labs);
+    /// assert_eq!(stats.l.percentile(25.0), 25.0);
+    /// assert_eq!(stats.l.percentile(95.0), 95.0);
+    /// ```
+    pub fn percentile(&self, p: f32) -> f32 {
+        let rank = p.clamp(0.0, 100.0) as f64 / 100.0 * (self.sorted.len() - 1) as f64;
+        let below = rank.floor() as usize;
+        let above = rank.ceil() as usize;
+        let fraction = (rank - below as f64) as f32;
+        self.sorted[below] + (self.sorted[above] - self.sorted[below]) * fraction
+    }
+}
+
+/// Statistics of the hues of an image's pixels
+///
+/// Each pixel counts as much as its chroma, since the hue of a nearly grey
+/// color is hardly visible, and that of a grey is meaningless.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct HueStats {
+    /// The circular mean hue, or `None` if the image is grey or its hues
+    /// cancel out
+    pub mean: Option<Hue>,
+    /// How concentrated the hues are around the mean, from 0, for hues
+    /// spread evenly around the circle, to 1, for a single hue
+    pub concentration: f32,
+}
+
+/// Color statistics of an image's pixels in Lab
+///
+/// # Example
+/// ```
+/// # use lab::{rgb_bytes_to_labs, LabStats};
+/// let pixels = [255, 0, 0, 240, 20, 10, 200, 0, 0, 128, 128, 128];
+/// let stats = LabStats::new(&rgb_bytes_to_labs(&pixels));
+/// assert!(stats.l.mean() > 40.0 && stats.l.mean() < 60.0);
+/// assert!(stats.chroma.median() > 70.0);
+/// let mean_hue = stats.hue.mean.unwrap();
+/// assert!(mean_hue.to_degrees() > 30.0 && mean_hue.to_degrees() < 45.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LabStats {
+    /// The distribution of L\*
+    pub l: Distribution,
+    /// The distribution of a\*
+    pub a: Distribution,
+    /// The distribution of b\*
+    pub b: Distribution,
+    /// The distribution of chroma
+    pub chroma: Distribution,
+    /// Statistics of the hues
+    pub hue: HueStats,
+    // Each pixel's hue and chroma, for histograms.
+    hues: Vec<(f32, f32)>,
+}
+
+impl LabStats {
+    /// Computes the statistics of `labs`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `labs` is empty.
+    pub fn new(labs: &[Lab]) -> Self {
+        assert!(!labs.is_empty(), "there are no colors to measure");
+        let hues: Vec<(f32, f32)> = labs
+            .iter()
+            .map(|lab| (lab.hue().to_radians(), lab.chroma()))
+            .collect();
+
+        let (mut x, mut y, mut total) = (0.0f64, 0.0f64, 0.0f64);
+        for &(h, c) in &hues {
+            x += (c * h.cos()) as f64;
+            y += (c * h.sin()) as f64;
+            total += c as f64;
+        }
+        let length = x.hypot(y);
+        // Relative to the total, as in `Hue::mean`.
+        let mean = if length <= 1e-6 * total || total == 0.0 {
+            None
+        } else {
+            Some(Hue::from_radians(y.atan2(x) as f32))
+        };
+        let concentration = if total > 0.0 {
+            (length / total) as f32
+        } else {
+            0.0
+        };
+
+        LabStats {
+            l: Distribution::new(labs.iter().map(|lab| lab.l).collect()),
+            a: Distribution::new(labs.iter().map(|lab| lab.a).collect()),
+            b: Distribution::new(labs.iter().map(|lab| lab.b).collect()),
+            chroma: Distribution::new(hues.iter().map(|&(_, c)| c).collect()),
+            hue: HueStats {
+                mean,
+                concentration,
+            },
+            hues,
+        }
+    }
+
+    /// Counts the pixels with at least `min_chroma` in each of `bins` equal
+    /// slices of the hue circle
+    ///
+    /// The first bin starts at a hue of 0°, and the bins go counterclockwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bins` is 0.
+    ///
+    /// # Example
+    /// ```
+    /// # use lab::{rgb_bytes_to_labs, LabStats};
+    /// let pixels = [255, 0, 0, 0, 255, 0, 0, 0, 255, 128, 128, 128];
+    /// let stats = LabStats::new(&rgb_bytes_to_labs(&pixels));
+    /// // Red at 40°, green at 136°, blue at 306°, and no grey.
+    /// assert_eq!(stats.hue_histogram(4, 5.0), [1, 1, 0, 1]);
+    /// ```
+    pub fn hue_histogram(&self, bins: usize, min_chroma: f32) -> Vec<usize> {
+        assert!(bins > 0, "a histogram needs at least one bin");
+        let mut counts = vec![0; bins];
+        for &(h, c) in &self.hues {
+            if c >= min_chroma {
+                let h = if h < 0.0 { h + TAU } else { h };
+                let bin = ((h / TAU * bins as f32) as usize).min(bins - 1);
+                counts[bin] += 1;
+            }
+        }
+        counts
+    }
+}
+
+/// A 3D histogram of colors in Lab
+///
+/// L\* from 0 to 100, and a\* and b\* from -128 to 128, are each divided
+/// into equal bins. Colors outside of those ranges are counted in the
+/// closest bin.
+///
+/// # Example
+/// ```
+/// # use lab::{rgb_bytes_to_labs, Lab, LabHistogram};
+/// let pixels = [255, 0, 0, 250, 0, 0, 0, 0, 255];
+/// let histogram = LabHistogram::new(&rgb_bytes_to_labs(&pixels), [4, 4, 4]);
+/// let red = histogram.bin(&Lab::from_rgb(&[255, 0, 0]));
+/// assert_eq!(histogram.count(red), 2);
+/// assert_eq!(histogram.counts().iter().sum::<usize>(), 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabHistogram {
+    bins: [usize; 3],
+    counts: Vec<usize>,
+}
+
+const RANGES: [(f32, f32); 3] = [(0.0, 100.0), (-128.0, 128.0), (-128.0, 128.0)];
+
+impl LabHistogram {
+    /// Counts `labs` into `bins[0]` bins of L\*, `bins[1]` of a\* and
+    /// `bins[2]` of b\*
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of `bins` is 0.
+    pub fn new(labs: &[Lab], bins: [usize; 3]) -> Self {
+        assert!(
+            bins.iter().all(|&n| n > 0),
+            "a histogram needs at least one bin"
+        );
+        let mut histogram = LabHistogram {
+            bins,
+            counts: vec![0; bins[0] * bins[1] * bins[2]],
+        };
+        for lab in labs {
+            let index = histogram.index(histogram.bin(lab));
+            histogram.counts[index] += 1;
+        }
+        histogram
+    }
+
+    /// Returns the number of bins along L\*, a\* and b\*
+    pub fn bins(&self) -> [usize; 3] {
+        self.bins
+    }
+
+    /// Returns which bin along L\*, a\* and b\* `lab` falls into
+    pub fn bin(&self, lab: &Lab) -> [usize; 3] {
+        let mut bin = [0; 3];
+        for (axis, &value) in [lab.l, lab.a, lab.b].iter().enumerate() {
+            let (min, max) = RANGES[axis];
+            let n = self.bins[axis];
+            let i = ((value - min) / (max - min) * n as f32).max(0.0) as usize;
+            bin[axis] = i.min(n - 1);
+        }
+        bin
+    }
+
+    /// Returns the color at the center of a bin
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bin` is outside of [`bins`](#method.bins) along any axis.
+    pub fn center(&self, bin: [usize; 3]) -> Lab {
+        self.check(bin);
+        let center = |axis: usize| {
+            let (min, max) = RANGES[axis];
+            min + (max - min) * (bin[axis] as f32 + 0.5) / self.bins[axis] as f32
+        };
+        Lab {
+            l: center(0),
+            a: center(1),
+            b: center(2),
+        }
+    }
+
+    /// Returns how many colors fell into a bin
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bin` is outside of [`bins`](#method.bins) along any axis.
+    pub fn count(&self, bin: [usize; 3]) -> usize {
+        self.check(bin);
+        self.counts[self.index(bin)]
+    }
+
+    /// Returns the counts of every bin, with b\* varying fastest and L\*
+    /// slowest
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    fn check(&self, bin: [usize; 3]) {
+        assert!(
+            bin.iter().zip(&self.bins).all(|(&i, &n)| i < n),
+            "a bin must be within the histogram's bins"
+        );
+    }
+
+    fn index(&self, bin: [usize; 3]) -> usize {
+        (bin[0] * self.bins[1] + bin[1]) * self.bins[2] + bin[2]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LabHistogram, LabStats};
+    use Lab;
+
+    fn lab(l: f32, a: f32, b: f32) -> Lab {
+        Lab { l, a, b }
+    }
+
+    #[test]
+    fn test_distribution() {
+        let labs: Vec<Lab> = [3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0]
+            .iter()
+            .map(|&l| lab(l, -l, 2.0 * l))
+            .collect();
+        let stats = LabStats::new(&labs);
+        assert_eq!(stats.l.mean(), 3.875);
+        assert_eq!(stats.l.min(), 1.0);
+        assert_eq!(stats.l.max(), 9.0);
+        assert_eq!(stats.l.median(), 3.5);
+        assert_eq!(stats.l.percentile(0.0), 1.0);
+        assert_eq!(stats.l.percentile(100.0), 9.0);
+        assert_eq!(stats.l.percentile(200.0), 9.0);
+        assert_eq!(stats.a.median(), -3.5);
+        assert_eq!(stats.b.median(), 7.0);
+        assert!((stats.l.std_dev() - 2.5708).abs() < 0.0001);
+
+        let one = LabStats::new(&labs[..1]);
+        assert_eq!(one.l.percentile(30.0), 3.0);
+        assert_eq!(one.l.std_dev(), 0.0);
+    }
+
+    #[test]
+    fn test_chroma_and_hue() {
+        let labs = [
+            lab(50.0, 30.0, 40.0),
+            lab(50.0, 0.0, 20.0),
+            lab(50.0, 0.0, 0.0),
+        ];
+        let stats = LabStats::new(&labs);
+        assert_eq!(stats.chroma.median(), 20.0);
+        assert_eq!(stats.chroma.max(), 50.0);
+        // Weighted by chroma, the grey doesn't count.
+        let mean = stats.hue.mean.unwrap().to_radians();
+        let expected = (40.0f32 + 20.0).atan2(30.0);
+        assert!((mean - expected).abs() < 0.0001);
+        assert!(stats.hue.concentration > 0.9 && stats.hue.concentration < 1.0);
+    }
+
+    #[test]
+    fn test_hue_stats_edge_cases() {
+        let grey = LabStats::new(&[lab(50.0, 0.0, 0.0)]);
+        assert_eq!(grey.hue.mean, None);
+        assert_eq!(grey.hue.concentration, 0.0);
+
+        let opposite = LabStats::new(&[lab(50.0, 20.0, 0.0), lab(50.0, -20.0, 0.0)]);
+        assert_eq!(opposite.hue.mean, None);
+        assert!(opposite.hue.concentration < 1e-6);
+
+        let single = LabStats::new(&[lab(50.0, 20.0, 0.0), lab(60.0, 40.0, 0.0)]);
+        assert_eq!(single.hue.mean.unwrap().to_radians(), 0.0);
+        assert_eq!(single.hue.concentration, 1.0);
+    }
+
+    #[test]
+    fn test_hue_histogram() {
+        let labs = [
+            lab(50.0, 10.0, 1.0),
+            lab(50.0, -10.0, 1.0),
+            lab(50.0, -10.0, -1.0),
+            lab(50.0, 10.0, -1.0),
+            lab(50.0, 0.5, -0.5),
+        ];
+        let stats = LabStats::new(&labs);
+        assert_eq!(stats.hue_histogram(4, 1.0), [1, 1, 1, 1]);
+        assert_eq!(stats.hue_histogram(4, 0.0), [1, 1, 1, 2]);
+        assert_eq!(stats.hue_histogram(1, 0.0), [5]);
+    }
+
+    #[test]
+    fn test_lab_histogram() {
+        let labs = [
+            lab(0.0, -128.0, -128.0),
+            lab(100.0, 128.0, 128.0),
+            lab(-5.0, 500.0, 0.0),
+            lab(49.0, 0.0, 0.0),
+        ];
+        let histogram = LabHistogram::new(&labs, [2, 4, 8]);
+        assert_eq!(histogram.bins(), [2, 4, 8]);
+        assert_eq!(histogram.counts().len(), 64);
+        assert_eq!(histogram.counts().iter().sum::<usize>(), 4);
+        assert_eq!(histogram.bin(&labs[0]), [0, 0, 0]);
+        assert_eq!(histogram.bin(&labs[1]), [1, 3, 7]);
+        assert_eq!(histogram.bin(&labs[2]), [0, 3, 4]);
+        assert_eq!(histogram.bin(&labs[3]), [0, 2, 4]);
+        assert_eq!(histogram.count([0, 0, 0]), 1);
+        assert_eq!(histogram.count([1, 1, 1]), 0);
+        assert_eq!(histogram.center([0, 2, 4]), lab(25.0, 32.0, 16.0));
+        for (i, &count) in histogram.counts().iter().enumerate() {
+            let bin = [i / 32, i / 8 % 4, i % 8];
+            assert_eq!(histogram.count(bin), count);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "a bin must be within the histogram's bins")]
+    fn test_lab_histogram_bin_out_of_range() {
+        let histogram = LabHistogram::new(&[Lab::default()], [2, 4, 8]);
+        histogram.count([0, 5, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "a bin must be within the histogram's bins")]
+    fn test_lab_histogram_center_out_of_range() {
+        let histogram = LabHistogram::new(&[Lab::default()], [2, 4, 8]);
+        histogram.center([2, 0, 0]);
+    }
+}
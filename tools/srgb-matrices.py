@@ -55,9 +55,13 @@ def main():
     S = tuple(sum(W[c] * inv[r][c] for c in (0, 1, 2)) for r in (0, 1, 2))
     M = [[matrix[r][c] * S[c] for c in (0, 1, 2)] for r in (0, 1, 2)]
 
-    print('// Generated by srgb-matrices.py\n'
-          'const WHITE_X: f32 = {};\n'
-          'const WHITE_Z: f32 = {};\n'.format(float(W[0]), float(W[2])))
+    # The same digits are used for the f32 constants in lib.rs and the f64
+    # ones in lab64.rs; they're printed at full f64 precision.
+    for ty in ('f32', 'f64'):
+        print('// Generated by srgb-matrices.py\n'
+              'const WHITE_X: {ty} = {};\n'
+              'const WHITE_Z: {ty} = {};\n'.format(float(W[0]), float(W[2]),
+                                                  ty=ty))
 
     print_let(M, 'xyz', 'rgb')
     print()